@@ -115,5 +115,9 @@ impl App {
             self.emu.step();
             self.render();
         }
+
+        if let Err(err) = self.emu.save() {
+            eprintln!("{:?}", err);
+        }
     }
 }
@@ -0,0 +1,69 @@
+// NTSC CPU clock
+const CPU_FREQUENCY: f64 = 1_789_773.0;
+pub const DEFAULT_SAMPLE_RATE: u32 = 44_100;
+
+// Mixes the audio sources of the console once per CPU cycle and averages
+// them down to the output sample rate. The APU is not emulated yet, so
+// only expansion audio of the cartridge reaches the output
+#[derive(Debug)]
+pub struct Mixer {
+    sample_rate: u32,
+    // CPU cycles per output sample
+    period:      f64,
+    elapsed:     f64,
+    sum:         f32,
+    count:       u32,
+    samples:     Vec<f32>,
+}
+
+impl Mixer {
+    pub fn new(sample_rate: u32) -> Self {
+        let mut s = Self {
+            sample_rate,
+            period: CPU_FREQUENCY / sample_rate.max(1) as f64,
+            elapsed: 0.0,
+            sum: 0.0,
+            count: 0,
+            samples: Vec::new(),
+        };
+        s.reset();
+        s
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+        self.sum = 0.0;
+        self.count = 0;
+        self.samples.clear();
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        self.period = CPU_FREQUENCY / sample_rate.max(1) as f64;
+    }
+
+    pub fn step(&mut self, expansion: f32) {
+        self.sum += expansion;
+        self.count += 1;
+        self.elapsed += 1.0;
+
+        if self.elapsed >= self.period {
+            self.elapsed -= self.period;
+            // Keep at most a second of audio when nobody takes it
+            if self.samples.len() < self.sample_rate as usize {
+                self.samples.push(self.sum / self.count as f32);
+            }
+            self.sum = 0.0;
+            self.count = 0;
+        }
+    }
+
+    // Hands out the samples produced since the previous call
+    pub fn take_samples(&mut self) -> Vec<f32> {
+        std::mem::take(&mut self.samples)
+    }
+}
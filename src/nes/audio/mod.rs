@@ -0,0 +1,7 @@
+mod mixer;
+mod opll;
+mod sunsoft_5b;
+
+pub use mixer::*;
+pub use opll::*;
+pub use sunsoft_5b::*;
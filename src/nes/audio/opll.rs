@@ -0,0 +1,399 @@
+use lazy_static::lazy_static;
+
+// Yamaha OPLL (YM2413) FM synthesis as built into Konami VRC7
+//
+// The VRC7 variant has 6 two-operator channels, no rhythm mode and its own
+// set of 15 instruments. One sample is produced every 72 clocks of the
+// 3.58 MHz chip clock, that is every 36 CPU cycles (49716 Hz)
+//
+// Register  Meaning
+// $00-$07   Custom instrument
+// $10-$15   Frequency of channel 0-5, low 8 bits
+// $20-$25   Channel control
+// 76543210
+//   ||||||
+//   |||||+- Frequency, bit 8
+//   ||+++-- Block (octave)
+//   |+----- Key on
+//   +------ Sustain
+// $30-$35   Channel instrument (high 4 bits) and volume (low 4 bits)
+//
+// Instruments are 8 bytes, where operator 0 is the modulator and
+// operator 1 is the carrier
+// 0-1: AVEK MMMM  Tremolo, vibrato, sustained envelope, key scale rate, multiplier
+// 2:   KKTT TTTT  Key scale level and total level of the modulator
+// 3:   KK-C MFFF  Key scale level of the carrier, rectified carrier
+//                 and modulator waves, feedback of the modulator
+// 4-5: AAAA DDDD  Attack and decay rates
+// 6-7: SSSS RRRR  Sustain level and release rate
+pub const CHANNELS: usize = 6;
+
+const INSTRUMENTS: [[u8; 8]; 16] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    [0x03, 0x21, 0x05, 0x06, 0xE8, 0x81, 0x42, 0x27],
+    [0x13, 0x41, 0x14, 0x0D, 0xD8, 0xF6, 0x23, 0x12],
+    [0x11, 0x11, 0x08, 0x08, 0xFA, 0xB2, 0x20, 0x12],
+    [0x31, 0x61, 0x0C, 0x07, 0xA8, 0x64, 0x61, 0x27],
+    [0x32, 0x21, 0x1E, 0x06, 0xE1, 0x76, 0x01, 0x28],
+    [0x02, 0x01, 0x06, 0x00, 0xA3, 0xE2, 0xF4, 0xF4],
+    [0x21, 0x61, 0x1D, 0x07, 0x82, 0x81, 0x11, 0x07],
+    [0x23, 0x21, 0x22, 0x17, 0xA2, 0x72, 0x01, 0x17],
+    [0x35, 0x11, 0x25, 0x00, 0x40, 0x73, 0x72, 0x01],
+    [0xB5, 0x01, 0x0F, 0x0F, 0xA8, 0xA5, 0x51, 0x02],
+    [0x17, 0xC1, 0x24, 0x07, 0xF8, 0xF8, 0x22, 0x12],
+    [0x71, 0x23, 0x11, 0x06, 0x65, 0x74, 0x18, 0x16],
+    [0x01, 0x02, 0xD3, 0x05, 0xC9, 0x95, 0x03, 0x02],
+    [0x61, 0x63, 0x0C, 0x00, 0x94, 0xC0, 0x33, 0xF6],
+    [0x21, 0x72, 0x0D, 0x00, 0xC1, 0xD5, 0x56, 0x06],
+];
+
+// Frequency multipliers, doubled so that 1/2 is representable
+const MULTIPLIERS: [u32; 16] = [1, 2, 4, 6, 8, 10, 12, 14, 16, 18, 20, 20, 24, 24, 30, 30];
+
+// Attenuation by key scale level, indexed by the top 4 bits of the frequency
+const KEY_SCALE_LEVELS: [i32; 16] = [
+    0, 32, 40, 45, 48, 51, 53, 55, 56, 58, 59, 60, 61, 62, 63, 64,
+];
+
+// Envelope increments over 8 steps of the envelope clock by the lowest 2 bits of the rate
+const ENVELOPE_STEPS: [[u32; 8]; 4] = [
+    [0, 1, 0, 1, 0, 1, 0, 1],
+    [0, 1, 0, 1, 1, 1, 0, 1],
+    [0, 1, 1, 1, 0, 1, 1, 1],
+    [0, 1, 1, 1, 1, 1, 1, 1],
+];
+
+// Frequency offsets of the vibrato in 1/256 of the frequency
+const VIBRATO: [i32; 8] = [0, 1, 2, 1, 0, -1, -2, -1];
+
+// Envelope levels are 7 bits in 0.375 dB steps
+const ENVELOPE_MAX: u32 = 0x7F;
+
+lazy_static! {
+    // Quarter of a sine wave as attenuation in 1/256 of a power of two
+    static ref LOG_SIN: [u32; 256] = {
+        let mut t = [0; 256];
+        for (i, v) in t.iter_mut().enumerate() {
+            let x = ((i as f64 + 0.5) * std::f64::consts::PI / 512.0).sin();
+            *v = (-x.log2() * 256.0).round() as u32;
+        }
+        t
+    };
+
+    // Converts the fractional part of an attenuation back to a linear level
+    static ref EXP: [u32; 256] = {
+        let mut t = [0; 256];
+        for (i, v) in t.iter_mut().enumerate() {
+            *v = (2f64.powf((255 - i) as f64 / 256.0) * 1024.0).round() as u32;
+        }
+        t
+    };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Envelope {
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Operator {
+    // Phase with 19 bits, of which the top 10 address the sine wave
+    phase:    u32,
+    level:    u32,
+    envelope: Envelope,
+    // Last two outputs for the feedback of the modulator
+    outputs:  [i32; 2],
+}
+
+impl Operator {
+    fn new() -> Self {
+        Self {
+            phase:    0,
+            level:    ENVELOPE_MAX,
+            envelope: Envelope::Release,
+            outputs:  [0; 2],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Channel {
+    fnum:       u32,
+    block:      u32,
+    key:        bool,
+    sustain:    bool,
+    instrument: usize,
+    volume:     u32,
+    operators:  [Operator; 2],
+}
+
+impl Channel {
+    fn new() -> Self {
+        Self {
+            fnum:       0,
+            block:      0,
+            key:        false,
+            sustain:    false,
+            instrument: 0,
+            volume:     0,
+            operators:  [Operator::new(); 2],
+        }
+    }
+
+    // Key scale number used by the key scale rate
+    fn key_scale(&self) -> u32 {
+        (self.block << 1) | (self.fnum >> 8)
+    }
+}
+
+pub struct Opll {
+    custom:   [u8; 8],
+    channels: [Channel; CHANNELS],
+    // Counts samples, drives envelopes, tremolo and vibrato
+    counter:  u32,
+}
+
+impl Default for Opll {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Opll {
+    pub fn new() -> Self {
+        let mut s = Self {
+            custom:   [0; 8],
+            channels: [Channel::new(); CHANNELS],
+            counter:  0,
+        };
+        s.reset();
+        s
+    }
+
+    pub fn reset(&mut self) {
+        self.custom = [0; 8];
+        self.channels = [Channel::new(); CHANNELS];
+        self.counter = 0;
+    }
+
+    pub fn write(&mut self, reg: u8, v: u8) {
+        let index = (reg & 0x0F) as usize;
+        match reg {
+            0x00..=0x07 => self.custom[index] = v,
+            0x10..=0x15 => {
+                let ch = &mut self.channels[index];
+                ch.fnum = (ch.fnum & 0x100) | v as u32;
+            }
+            0x20..=0x25 => {
+                let ch = &mut self.channels[index];
+                ch.fnum = (ch.fnum & 0xFF) | ((v as u32 & 0x01) << 8);
+                ch.block = (v as u32 >> 1) & 0x07;
+                ch.sustain = v & 0x20 != 0;
+
+                let key = v & 0x10 != 0;
+                if key && !ch.key {
+                    // Key on restarts the wave from the attack
+                    for op in ch.operators.iter_mut() {
+                        op.phase = 0;
+                        op.envelope = Envelope::Attack;
+                    }
+                } else if !key && ch.key {
+                    for op in ch.operators.iter_mut() {
+                        op.envelope = Envelope::Release;
+                    }
+                }
+                ch.key = key;
+            }
+            0x30..=0x35 => {
+                let ch = &mut self.channels[index];
+                ch.instrument = (v >> 4) as usize;
+                ch.volume = v as u32 & 0x0F;
+            }
+            _ => {}
+        }
+    }
+
+    // Produces the next sample as the sum of all channels, each of which
+    // is within -4095..4095
+    pub fn clock(&mut self) -> i32 {
+        self.counter = self.counter.wrapping_add(1);
+
+        // Tremolo is a 4.8 dB triangle at about 3.7 Hz
+        let step = (self.counter >> 9) % 26;
+        let tremolo = if step < 13 { step } else { 26 - step };
+        // Vibrato is about 6 Hz
+        let vibrato = VIBRATO[((self.counter >> 10) & 0x07) as usize];
+
+        let mut sample = 0;
+        for i in 0..CHANNELS {
+            let patch = match self.channels[i].instrument {
+                0 => self.custom,
+                n => INSTRUMENTS[n],
+            };
+            sample += Self::channel(
+                &mut self.channels[i],
+                &patch,
+                self.counter,
+                tremolo,
+                vibrato,
+            );
+        }
+        sample
+    }
+
+    fn channel(ch: &mut Channel, patch: &[u8; 8], counter: u32, tremolo: u32, vibrato: i32) -> i32 {
+        let mut modulation = 0;
+
+        for n in 0..2 {
+            let flags = patch[n];
+            let sustained = flags & 0x20 != 0;
+            let key_scale = if flags & 0x10 != 0 {
+                ch.key_scale()
+            } else {
+                ch.key_scale() >> 2
+            };
+
+            // Phase generator
+            let mut fnum = ch.fnum as i32;
+            if flags & 0x40 != 0 {
+                fnum += (fnum >> 8) * vibrato;
+            }
+            let inc = ((fnum as u32 * MULTIPLIERS[(flags & 0x0F) as usize]) << ch.block) >> 1;
+            let op = &mut ch.operators[n];
+            op.phase = (op.phase + inc) & 0x7FFFF;
+
+            // Envelope generator
+            let rate = |r: u8| match r {
+                0 => 0,
+                r => (r as u32 * 4 + key_scale).min(63),
+            };
+            let attack = rate(patch[4 + n] >> 4);
+            let decay = rate(patch[4 + n] & 0x0F);
+            let sustain_level = (patch[6 + n] >> 4) as u32;
+            let release = rate(patch[6 + n] & 0x0F);
+
+            match op.envelope {
+                Envelope::Attack => {
+                    if attack >= 60 {
+                        op.level = 0;
+                    } else {
+                        let inc = Self::envelope_inc(attack, counter);
+                        if inc > 0 {
+                            let step = ((op.level + 1) * inc + 7) >> 3;
+                            op.level = op.level.saturating_sub(step);
+                        }
+                    }
+                    if op.level == 0 {
+                        op.envelope = Envelope::Decay;
+                    }
+                }
+                Envelope::Decay => {
+                    op.level += Self::envelope_inc(decay, counter);
+                    if op.level >> 3 >= sustain_level {
+                        op.envelope = Envelope::Sustain;
+                    }
+                }
+                Envelope::Sustain => {
+                    // Percussive instruments keep decaying while the key is held
+                    if !sustained {
+                        op.level += Self::envelope_inc(release, counter);
+                    }
+                }
+                Envelope::Release => {
+                    let release = if ch.sustain {
+                        rate(5)
+                    } else if sustained {
+                        release
+                    } else {
+                        rate(7)
+                    };
+                    op.level += Self::envelope_inc(release, counter);
+                }
+            }
+            op.level = op.level.min(ENVELOPE_MAX);
+
+            // Attenuation of the operator
+            let key_scale_level = match patch[2 + n] >> 6 {
+                0 => 0,
+                shift => {
+                    let top = KEY_SCALE_LEVELS[(ch.fnum >> 5) as usize & 0x0F];
+                    let level = (top << 1) - ((8 - ch.block as i32) << 4);
+                    level.max(0) as u32 >> (3 - shift)
+                }
+            };
+            let base = if n == 0 {
+                (patch[2] & 0x3F) as u32 * 2
+            } else {
+                ch.volume * 8
+            };
+            let am = if flags & 0x80 != 0 { tremolo } else { 0 };
+            let attenuation = (op.level + base + key_scale_level + am).min(ENVELOPE_MAX);
+
+            // Modulator feeds back into itself, the carrier is modulated by the modulator
+            let offset = if n == 0 {
+                match patch[3] & 0x07 {
+                    0 => 0,
+                    fb => (op.outputs[0] + op.outputs[1]) >> (9 - fb),
+                }
+            } else {
+                modulation
+            };
+            let rectified = patch[3] & (0x08 << n) != 0;
+            let phase = ((op.phase >> 9) as i32 + offset) as u32 & 0x3FF;
+            let output = Self::wave(phase, attenuation, rectified);
+
+            op.outputs = [op.outputs[1], output];
+            modulation = output;
+        }
+
+        modulation
+    }
+
+    // Envelope change for the rate at this sample
+    fn envelope_inc(rate: u32, counter: u32) -> u32 {
+        if rate == 0 {
+            return 0;
+        }
+
+        let steps = &ENVELOPE_STEPS[(rate & 0x03) as usize];
+        let high = rate >> 2;
+        if high < 13 {
+            let shift = 13 - high;
+            if counter & ((1 << shift) - 1) != 0 {
+                0
+            } else {
+                steps[((counter >> shift) & 0x07) as usize]
+            }
+        } else {
+            steps[(counter & 0x07) as usize] << (high - 13)
+        }
+    }
+
+    fn wave(phase: u32, attenuation: u32, rectified: bool) -> i32 {
+        let negative = phase & 0x200 != 0;
+        if negative && rectified {
+            return 0;
+        }
+
+        let index = if phase & 0x100 != 0 {
+            !phase & 0xFF
+        } else {
+            phase & 0xFF
+        };
+        let level = LOG_SIN[index as usize] + (attenuation << 4);
+        let v = if level >= 0x1000 {
+            0
+        } else {
+            ((EXP[(level & 0xFF) as usize] << 1) >> (level >> 8)) as i32
+        };
+
+        if negative {
+            -v
+        } else {
+            v
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use lazy_static::lazy_static;
+
+// Sunsoft 5B audio, a licensed YM2149 (AY-3-8910 family)
+//
+// Three square channels share a noise generator and an envelope generator.
+// Tone and noise are clocked every 16 CPU cycles, the envelope every 8
+//
+// Register  Meaning
+// $00-$05   Tone period of channel A, B, C (low 8 bits, then high 4 bits)
+// $06       Noise period (5 bits)
+// $07       Mixer
+// 76543210
+//   ||||||
+//   |||+++- Disable tone of channel A, B, C
+//   +++---- Disable noise of channel A, B, C
+// $08-$0A   Volume of channel A, B, C
+// 76543210
+//    |||||
+//    |++++- Volume
+//    +----- Use the envelope instead of the volume
+// $0B-$0C   Envelope period (low 8 bits, then high 8 bits)
+// $0D       Envelope shape, writing restarts the envelope
+// 76543210
+//     ||||
+//     |||+- Hold
+//     ||+-- Alternate
+//     |+--- Attack
+//     +---- Continue
+pub struct Sunsoft5b {
+    regs:    [u8; 16],
+    divider: u8,
+
+    tone_counters: [u16; 3],
+    tone_outputs:  [bool; 3],
+
+    noise_counter: u16,
+    // 17 bit LFSR, bit 0 is the output
+    noise:         u32,
+
+    envelope_counter: u32,
+    envelope_step:    u8,
+    envelope_attack:  bool,
+    envelope_holding: bool,
+}
+
+lazy_static! {
+    // Linear amplitude of the 32 levels in 1.5 dB steps
+    static ref LEVELS: [f32; 32] = {
+        let mut t = [0.0; 32];
+        for (i, v) in t.iter_mut().enumerate().skip(1) {
+            *v = 10f32.powf(-((31 - i) as f32) * 1.5 / 20.0);
+        }
+        t
+    };
+}
+
+impl Default for Sunsoft5b {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sunsoft5b {
+    pub fn new() -> Self {
+        let mut s = Self {
+            regs:             [0; 16],
+            divider:          0,
+            tone_counters:    [0; 3],
+            tone_outputs:     [false; 3],
+            noise_counter:    0,
+            noise:            1,
+            envelope_counter: 0,
+            envelope_step:    0,
+            envelope_attack:  false,
+            envelope_holding: false,
+        };
+        s.reset();
+        s
+    }
+
+    pub fn reset(&mut self) {
+        self.regs = [0; 16];
+        self.divider = 0;
+        self.tone_counters = [0; 3];
+        self.tone_outputs = [false; 3];
+        self.noise_counter = 0;
+        self.noise = 1;
+        self.envelope_counter = 0;
+        self.envelope_step = 0;
+        self.envelope_attack = false;
+        self.envelope_holding = false;
+    }
+
+    pub fn write(&mut self, reg: u8, v: u8) {
+        let reg = reg as usize;
+        if reg >= self.regs.len() {
+            return;
+        }
+
+        self.regs[reg] = v;
+        if reg == 0x0D {
+            self.envelope_counter = 0;
+            self.envelope_step = 0;
+            self.envelope_attack = v & 0x04 != 0;
+            self.envelope_holding = false;
+        }
+    }
+
+    // Called once per CPU cycle
+    pub fn tick(&mut self) {
+        self.divider = (self.divider + 1) & 0x0F;
+
+        if self.divider & 0x07 == 0 {
+            self.tick_envelope();
+        }
+        if self.divider != 0 {
+            return;
+        }
+
+        for ch in 0..3 {
+            let period = self.tone_period(ch).max(1);
+            self.tone_counters[ch] += 1;
+            if self.tone_counters[ch] >= period {
+                self.tone_counters[ch] = 0;
+                self.tone_outputs[ch] = !self.tone_outputs[ch];
+            }
+        }
+
+        let period = (self.regs[0x06] as u16 & 0x1F).max(1);
+        self.noise_counter += 1;
+        if self.noise_counter >= period {
+            self.noise_counter = 0;
+            let feedback = (self.noise ^ (self.noise >> 3)) & 0x01;
+            self.noise = (self.noise >> 1) | (feedback << 16);
+        }
+    }
+
+    // Sum of the channels, each within 0.0..1.0
+    pub fn output(&self) -> f32 {
+        let mixer = self.regs[0x07];
+        let noise = self.noise & 0x01 != 0;
+        let envelope = self.envelope_level();
+
+        (0..3)
+            .map(|ch| {
+                let tone = self.tone_outputs[ch] || mixer & (0x01 << ch) != 0;
+                let noise = noise || mixer & (0x08 << ch) != 0;
+                if !(tone && noise) {
+                    return 0.0;
+                }
+
+                let volume = self.regs[0x08 + ch];
+                let level = if volume & 0x10 != 0 {
+                    envelope
+                } else {
+                    match volume & 0x0F {
+                        0 => 0,
+                        v => v * 2 + 1,
+                    }
+                };
+                LEVELS[level as usize]
+            })
+            .sum()
+    }
+
+    fn tone_period(&self, ch: usize) -> u16 {
+        self.regs[ch * 2] as u16 | ((self.regs[ch * 2 + 1] as u16 & 0x0F) << 8)
+    }
+
+    fn envelope_level(&self) -> u8 {
+        if self.envelope_attack {
+            self.envelope_step
+        } else {
+            31 - self.envelope_step
+        }
+    }
+
+    fn tick_envelope(&mut self) {
+        let period = (self.regs[0x0B] as u32 | ((self.regs[0x0C] as u32) << 8)).max(1);
+        self.envelope_counter += 1;
+        if self.envelope_counter < period {
+            return;
+        }
+        self.envelope_counter = 0;
+
+        if self.envelope_holding {
+            return;
+        }
+        if self.envelope_step < 31 {
+            self.envelope_step += 1;
+            return;
+        }
+
+        let shape = self.regs[0x0D];
+        let cont = shape & 0x08 != 0;
+        let alternate = shape & 0x02 != 0;
+        let hold = shape & 0x01 != 0;
+
+        if !cont {
+            // Single cycle shapes end silent
+            self.envelope_attack = false;
+            self.envelope_holding = true;
+        } else if hold {
+            self.envelope_attack ^= alternate;
+            self.envelope_holding = true;
+        } else {
+            self.envelope_attack ^= alternate;
+            self.envelope_step = 0;
+        }
+    }
+}
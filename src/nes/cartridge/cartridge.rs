@@ -1,42 +1,119 @@
-use super::mappers::{Mapper, Mapper000};
+use super::disk::DiskImage;
+use super::flash::Flash;
+use super::header::{CartridgeHeader, HEADER_SIZE};
+use super::ips::{apply_ips, make_ips};
+use super::mappers::{
+    default_chr_ram_size, Fds, Mapped, Mapper, MapperInfo, MapperRegistry, PpuFetch,
+};
+use super::unif::Unif;
 use crate::prelude::*;
 
 use std::cmp;
+use std::collections::HashSet;
 use std::fs;
 use std::fs::File;
-use std::path::Path;
-use std::str;
+use std::path::{Path, PathBuf};
 
 use std::io::prelude::*;
-use std::io::SeekFrom;
+use std::io::{ErrorKind, SeekFrom};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Mirror {
     Horizontal,
     Vertical,
+    OneScreenLo,
+    OneScreenHi,
+    FourScreen,
     Hardware,
 }
 
+// Emulation of bus conflicts, where the ROM drives the data bus along
+// with the CPU on a register write and the register latches the AND
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusConflicts {
+    // As the header or the board tells
+    Auto,
+    Always,
+    Never,
+}
+
 pub struct Cartridge {
     prg_mem: Vec<Byte>,
+    flash:   Flash,
+    prg_ram: Vec<Byte>,
+    trainer: Vec<Byte>,
     chr_mem: Vec<Byte>,
+    chr_ram: bool,
+    vram:    Vec<Byte>,
     mirror:  Mirror,
     mapper:  Option<Box<dyn Mapper>>,
+    mappers: MapperRegistry,
+    header:  Option<CartridgeHeader>,
+
+    bus_conflicts: BusConflicts,
+    // Bus conflicts of the loaded board
+    conflicts:     bool,
+    // Addresses of conflicting writes logged since the load
+    log_conflicts: bool,
+    logged:        HashSet<u16>,
+
+    // Battery-backed PRG RAM is persisted into the save file
+    save_dir:         Option<PathBuf>,
+    save_path:        Option<PathBuf>,
+    save_ram:         bool,
+    // Battery-backed RAM of the mapper as of the last load or flush
+    saved_mapper_ram: Vec<u8>,
+    // PRG ROM was reprogrammed, so it is persisted like battery-backed RAM
+    prg_flashed:      bool,
+
+    // Famicom Disk System. Writes to the disk are saved as an IPS patch
+    // against the image as loaded, without its fwNES header
+    fds_bios:   Option<PathBuf>,
+    disk:       Vec<u8>,
+    // Disk contents as of the last load or flush
+    saved_disk: Vec<u8>,
 }
 
 const PROGRAM_ROM_SIZE: usize = 16384; // 16 kb
-const CHARACTER_ROM_SIZE: usize = 8192; // 8 kb
 const PROGRAM_RAM_SIZE: usize = 8192; // 8 kb
+const TRAINER_SIZE: usize = 512; // 512 b
+const TRAINER_OFFSET: usize = 0x1000; // $7000 in the work RAM
+const CHARACTER_ROM_SIZE: usize = 8192; // 8 kb
 const CHARACTER_RAM_SIZE: usize = 8192; // 8 kb
-const HEADER_SIZE: usize = 16; // 2 byte
+const VIDEO_RAM_SIZE: usize = 2048; // 2 kb
+const FDS_BIOS_SIZE: usize = 8192; // 8 kb
+const FDS_PRG_RAM_SIZE: usize = 32768; // 32 kb
 
 impl Cartridge {
     pub fn new() -> Self {
         Self {
             prg_mem: vec![Byte(0); 0],
+            flash:   Flash::new(),
+            prg_ram: vec![Byte(0); 0],
+            trainer: vec![Byte(0); 0],
             chr_mem: vec![Byte(0); 0],
+            chr_ram: false,
+            vram:    vec![Byte(0); 0],
             mirror:  Mirror::Hardware,
             mapper:  None,
+            mappers: MapperRegistry::new(),
+            header:  None,
+
+            bus_conflicts: BusConflicts::Auto,
+            conflicts:     false,
+            log_conflicts: false,
+            logged:        HashSet::new(),
+
+            save_dir:  None,
+            save_path: None,
+            save_ram:  false,
+
+            saved_mapper_ram: vec![],
+            prg_flashed:      false,
+
+            fds_bios:   None,
+            disk:       vec![],
+            saved_disk: vec![],
         }
     }
 
@@ -58,299 +135,701 @@ impl Cartridge {
         }
 
         println!("[CARTGE] cartridge size (bytes): {}", len);
-        // iNES header format (16 bytes):
-        // 0-3: Constant $4E $45 $53 $1A ("NES" followed by MS-DOS end-of-file)
-        // 4: Size of PRG ROM in 16 KB units
-        // 5: Size of CHR ROM in 8 KB units (Value 0 means the board uses CHR RAM)
-        // 6: Flags 6 - Mapper, mirroring, battery, trainer
-        // 7: Flags 7 - Mapper, VS/Playchoice, NES 2.0
-        // 8: Flags 8 - PRG-RAM size (rarely used extension)
-        // 9: Flags 9 - TV system (rarely used extension)
-        // 10: Flags 10 - TV system, PRG-RAM presence (unofficial, rarely used extension)
-        // 11-15: Unused padding (should be filled with zero, but some rippers put their name across bytes 7-15)
-
-        // Flags 6
-        // 76543210
-        // ||||||||
-        // |||||||+- Mirroring: 0: horizontal (vertical arrangement) (CIRAM A10 = PPU A11)
-        // |||||||              1: vertical (horizontal arrangement) (CIRAM A10 = PPU A10)
-        // ||||||+-- 1: Cartridge contains battery-backed PRG RAM ($6000-7FFF) or other persistent memory
-        // |||||+--- 1: 512-byte trainer at $7000-$71FF (stored before PRG data)
-        // ||||+---- 1: Ignore mirroring control or above mirroring bit; instead provide four-screen VRAM
-        // ++++----- Lower nybble of mapper number
-
-        // Flags 7
-        // 76543210
-        // ||||||||
-        // |||||||+- VS Unisystem
-        // ||||||+-- PlayChoice-10 (8KB of Hint Screen data stored after CHR data)
-        // ||||++--- If equal to 2, flags 8-15 are in NES 2.0 format
-        // ++++----- Upper nybble of mapper number
-
-        // Flags 8
-        // 76543210
-        // ||||||||
-        // ++++++++- PRG RAM size
-
-        // Flags 9
-        // 76543210
-        // ||||||||
-        // |||||||+- TV system (0: NTSC; 1: PAL)
-        // +++++++-- Reserved, set to zero
-
-        // Flags 10
-        // 76543210
-        //   ||  ||
-        //   ||  ++- TV system (0: NTSC; 2: PAL; 1/3: dual compatible)
-        //   |+----- PRG RAM ($6000-$7FFF) (0: present; 1: not present)
-        //   +------ 0: Board has no bus conflicts; 1: Board has bus conflicts
 
         let mut header_buf: [u8; HEADER_SIZE] = [0; HEADER_SIZE];
-        let header_size = file.read(&mut header_buf).context(errors::ReadFile)?;
+        let header_size = Self::read_buf(file, &mut header_buf)?;
 
-        if header_size < 16 {
-            return errors::ReadCartridge {
-                detail: format!(
-                    "cannot read iNES header, file size less 16 byte, size = {}",
-                    header_size
-                ),
+        if DiskImage::detect(&header_buf[..header_size]) {
+            let mut data = header_buf[..header_size].to_vec();
+            file.read_to_end(&mut data).context(errors::ReadFile)?;
+            return self.load_disk(&data);
+        }
+
+        if Unif::detect(&header_buf[..header_size]) {
+            let mut data = header_buf[..header_size].to_vec();
+            file.read_to_end(&mut data).context(errors::ReadFile)?;
+            let unif = Unif::parse(&data)?;
+            println!("[CARTGE] header: {:?}", unif.header);
+            return self.load_rom(unif.header, vec![], unif.prg, unif.chr);
+        }
+
+        let header = CartridgeHeader::parse(&header_buf[..header_size])?;
+        println!("[CARTGE] header: {:?}", header);
+
+        // Sizes in the header are checked against the rest of the file
+        // before anything is allocated, so garbage cannot exhaust memory
+        let mut remaining = (len - old_pos) as usize - header_size;
+
+        // If a "trainer" exists it precedes the PRG ROM, it is
+        // kept to be copied into the work RAM on reset
+        let trainer = if header.trainer {
+            let mut v: [u8; TRAINER_SIZE] = [0; TRAINER_SIZE];
+            let size = Self::read_buf(file, &mut v)?;
+            if size != TRAINER_SIZE {
+                return errors::TruncatedTrainer {
+                    expected: TRAINER_SIZE,
+                    actual:   size,
+                }
+                .fail();
+            }
+            remaining -= size;
+
+            v.iter().copied().map(Byte).collect()
+        } else {
+            vec![Byte(0); 0]
+        };
+
+        if header.prg_rom_size == 0 {
+            return errors::InvalidSize {
+                detail: "PRG ROM is empty".to_string(),
             }
             .fail();
         }
 
-        let name = &header_buf[0..3];
-        let ines = str::from_utf8(&name).unwrap();
-        if ines != "NES" {
-            return errors::ReadCartridge {
-                detail: format!(
-                    "cannot read iNES header, invalid name constant, [0..3] = {}",
-                    ines
-                ),
+        if header.prg_rom_size > remaining {
+            return errors::TruncatedPrg {
+                expected: header.prg_rom_size,
+                actual:   remaining,
             }
             .fail();
         }
 
-        let prg_rom_banks = header_buf[4] as usize;
-        println!("[CARTGE] program rom banks: {}", prg_rom_banks);
+        let prg = {
+            let mut v: Vec<u8> = vec![0; header.prg_rom_size];
+            let size = Self::read_buf(file, &mut v)?;
+            if size != header.prg_rom_size {
+                return errors::TruncatedPrg {
+                    expected: header.prg_rom_size,
+                    actual:   size,
+                }
+                .fail();
+            }
+            remaining -= size;
 
-        let chr_rom_banks = header_buf[5] as usize;
-        println!("[CARTGE] character rom banks: {}", chr_rom_banks);
+            v
+        };
 
-        let flags_6 = header_buf[6];
-        println!("[CARTGE] flags 6: {0:#010b} ({0:#04x})", flags_6);
-        multiline_println!(
-            "                    ||||||||",
-            "                    |||||||+- Mirroring: 0: horizontal (vertical arrangement) (CIRAM A10 = PPU A11)",
-            "                    |||||||              1: vertical (horizontal arrangement) (CIRAM A10 = PPU A10)",
-            "                    ||||||+-- 1: Cartridge contains battery-backed PRG RAM ($6000-7FFF) or other persistent memory",
-            "                    |||||+--- 1: 512-byte trainer at $7000-$71FF (stored before PRG data)",
-            "                    ||||+---- 1: Ignore mirroring control or above mirroring bit; instead provide four-screen VRAM",
-            "                    ++++----- Lower nybble of mapper number"
-        );
-        println!();
-
-        let flags_7 = header_buf[7];
-        println!("[CARTGE] flags 7: {0:#010b} ({0:#04x})", flags_7);
-        multiline_println!(
-             "                    ||||||||",
-             "                    |||||||+- VS Unisystem",
-             "                    ||||||+-- PlayChoice-10 (8KB of Hint Screen data stored after CHR data)",
-             "                    ||||++--- If equal to 2, flags 8-15 are in NES 2.0 format",
-             "                    ++++----- Upper nybble of mapper number");
-        println!();
-
-        let flags_8 = header_buf[8] as usize;
-        println!("[CARTGE] flags 8: {0:#010b} ({0:#04x})", flags_8);
-        multiline_println!(
-            "                    ||||||||",
-            "                    ++++++++- PRG RAM size"
-        );
-        println!();
-
-        let flags_9 = header_buf[9] as usize;
-        println!("[CARTGE] flags 9: {0:#010b} ({0:#04x})", flags_9);
-        multiline_println!(
-            "                    ||||||||",
-            "                    |||||||+- TV system (0: NTSC; 1: PAL)",
-            "                    +++++++-- Reserved, set to zero"
-        );
-        println!();
-
-        let flags_10 = header_buf[10] as usize;
-        println!("[CARTGE] flags 10: {0:#010b} ({0:#04x})", flags_10);
-        multiline_println!(
-             "                      ||  ||",
-             "                      ||  ++- TV system (0: NTSC; 2: PAL; 1/3: dual compatible)",
-             "                      |+----- PRG RAM ($6000-$7FFF) (0: present; 1: not present)",
-             "                      +------ 0: Board has no bus conflicts; 1: Board has bus conflicts");
-        println!();
-
-        // If a "trainer" exists we just need to read past
-        // it before we get to the good stuff
-        if flags_6 & 0b0000_0100 != 0 {
-            file.seek(SeekFrom::Current(512));
-        }
-
-        // Determine mapper id
-        //   0bAAAAxxxx (flags_7)
-        // | 0bxxxxBBBB (flags_6)
-        // = 0bAAAABBBB
-        let mapper_id = (flags_7 & 0xF0) | ((flags_6 >> 4) & 0x0F);
-
-        let file_type = flags_7 & 0b0000_1100;
-        const NES_2_0: u8 = 0b0000_1000;
-
-        // Determine program ram size
-        let prg_ram_size = flags_8;
-
-        // Mirroring
-        let mirror = if flags_6 & 0x01 != 0x00 {
-            Mirror::Vertical
+        let chr = if header.has_chr_ram() {
+            vec![]
         } else {
-            Mirror::Horizontal
-        };
+            if header.chr_rom_size > remaining {
+                return errors::TruncatedChr {
+                    expected: header.chr_rom_size,
+                    actual:   remaining,
+                }
+                .fail();
+            }
 
-        let (prg_mem, prg_banks) = {
-            let banks = if file_type == NES_2_0 {
-                // NES 2.0 FORMAT
-                ((prg_ram_size & 0b0000_0000_0000_0111) << 8) | prg_rom_banks
-            } else {
-                // NOT NES 2.0 FORMAT
-                cmp::max(1, prg_rom_banks)
-            };
+            let mut v: Vec<u8> = vec![0; header.chr_rom_size];
+            let size = Self::read_buf(file, &mut v)?;
+            if size != header.chr_rom_size {
+                return errors::TruncatedChr {
+                    expected: header.chr_rom_size,
+                    actual:   size,
+                }
+                .fail();
+            }
 
-            // banks * 16kb
-            let s = banks * PROGRAM_ROM_SIZE;
-            let mut v: Vec<u8> = Vec::new();
-            v.resize(s, 0);
-            file.read(&mut v);
+            v
+        };
 
-            (v.into_iter().map(Byte).collect(), banks)
+        self.load_rom(header, trainer, prg, chr)
+    }
+
+    // Sets up the board a header describes with its PRG and CHR ROM,
+    // whichever file format they came from
+    fn load_rom(
+        &mut self,
+        header: CartridgeHeader,
+        trainer: Vec<Byte>,
+        prg: Vec<u8>,
+        chr: Vec<u8>,
+    ) -> Result<()> {
+        // Four-screen boards carry additional 2 KB of VRAM
+        // for the nametables the console cannot hold
+        let vram = match header.mirror {
+            Mirror::FourScreen => vec![Byte(0); VIDEO_RAM_SIZE],
+            _ => vec![Byte(0); 0],
         };
 
-        let (chr_mem, chr_banks) = {
-            let banks = if file_type == NES_2_0 {
-                // NES 2.0 FORMAT
-                ((prg_ram_size & 0b0000_0000_0011_1000) << 8) | prg_rom_banks
-            } else {
-                // NOT NES 2.0 FORMAT
-                // If banks eq 0 than chr_mem is RAM, otherwise is ROM
-                cmp::max(1, chr_rom_banks)
+        // banks * 16kb
+        let prg_banks = prg.len().div_ceil(PROGRAM_ROM_SIZE);
+        let mut prg_mem: Vec<Byte> = prg.into_iter().map(Byte).collect();
+        prg_mem.resize(prg_banks * PROGRAM_ROM_SIZE, Byte(0));
+
+        // Work RAM at $6000-$7FFF, the battery keeps the non-volatile part of it
+        // The trainer lands at $7000, so the RAM must reach that far
+        let prg_ram_size = header.prg_ram_size + header.prg_nvram_size;
+        let prg_ram_size = if trainer.is_empty() {
+            prg_ram_size
+        } else {
+            cmp::max(PROGRAM_RAM_SIZE, prg_ram_size)
+        };
+        let prg_ram = vec![Byte(0); prg_ram_size];
+
+        let (chr_mem, chr_banks) = if header.has_chr_ram() {
+            // Without CHR ROM the board carries writable CHR RAM which is
+            // never stored in the file, at least one 8 KB bank is mapped.
+            // Without a size in the header the board default applies
+            let s = match header.chr_ram_size + header.chr_nvram_size {
+                0 => default_chr_ram_size(header.mapper),
+                s => s,
             };
+            let banks = cmp::max(1, s.div_ceil(CHARACTER_RAM_SIZE));
 
+            (vec![Byte(0); banks * CHARACTER_RAM_SIZE], banks)
+        } else {
             // banks * 8kb
-            let s = banks * CHARACTER_ROM_SIZE;
-            let mut v: Vec<u8> = Vec::new();
-            v.resize(s, 0);
-            file.read(&mut v);
+            let banks = chr.len().div_ceil(CHARACTER_ROM_SIZE);
+            let mut v: Vec<Byte> = chr.into_iter().map(Byte).collect();
+            v.resize(banks * CHARACTER_ROM_SIZE, Byte(0));
 
-            (v.into_iter().map(Byte).collect(), banks)
+            (v, banks)
         };
 
-        let mapper: Box<dyn Mapper> = match mapper_id {
-            0 => Box::new(Mapper000::new(prg_banks, chr_banks)),
-            _ => {
-                return errors::ReadCartridge {
-                    detail: format!("unknown mapper id = {}", mapper_id),
+        let info = MapperInfo {
+            header: &header,
+            prg_banks,
+            chr_banks,
+            chr_ram: header.has_chr_ram(),
+            prg_ram_size: prg_ram.len(),
+        };
+        let mapper = match self.mappers.create(&info) {
+            Some(m) => m,
+            None => {
+                return errors::UnsupportedMapper {
+                    id:        header.mapper,
+                    submapper: header.submapper,
                 }
                 .fail();
             }
         };
 
         self.prg_mem = prg_mem;
+        self.prg_ram = prg_ram;
+        self.trainer = trainer;
         self.chr_mem = chr_mem;
-        self.mirror = mirror;
+        self.chr_ram = header.has_chr_ram();
+        self.vram = vram;
+        self.mirror = header.mirror;
+        self.conflicts = Self::has_bus_conflicts(&header, mapper.as_ref());
+        self.logged.clear();
         self.mapper = Some(mapper);
+        self.header = Some(header);
+        self.save_path = None;
+        self.save_ram = false;
+        self.saved_mapper_ram = self.mapper_battery_ram().to_vec();
+        self.prg_flashed = false;
+        self.flash.reset();
+        self.disk = vec![];
+        self.saved_disk = vec![];
+
+        Ok(())
+    }
+
+    // Disk images run on the BIOS of the RAM adapter, which has
+    // 32 KB of PRG RAM and 8 KB of CHR RAM
+    fn load_disk(&mut self, data: &[u8]) -> Result<()> {
+        let image = DiskImage::parse(data)?;
+        println!(
+            "[CARTGE] disk image: {:?}, sides: {}",
+            image.format(),
+            image.sides()
+        );
+
+        let bios_path = match self.fds_bios {
+            Some(ref p) => p,
+            None => return errors::MissingFdsBios.fail(),
+        };
+        println!("[CARTGE] FDS BIOS: {}", bios_path.display());
+
+        let bios = fs::read(bios_path).context(errors::ReadFile)?;
+        if bios.len() != FDS_BIOS_SIZE {
+            return errors::InvalidSize {
+                detail: format!(
+                    "FDS BIOS has {} bytes instead of {}",
+                    bios.len(),
+                    FDS_BIOS_SIZE
+                ),
+            }
+            .fail();
+        }
+
+        self.prg_mem = bios.into_iter().map(Byte).collect();
+        self.prg_ram = vec![Byte(0); FDS_PRG_RAM_SIZE];
+        self.trainer = vec![];
+        self.chr_mem = vec![Byte(0); CHARACTER_RAM_SIZE];
+        self.chr_ram = true;
+        self.vram = vec![];
+        self.mirror = Mirror::Hardware;
+        self.conflicts = false;
+        self.logged.clear();
+        self.header = None;
+        self.save_path = None;
+        self.save_ram = false;
+        self.saved_mapper_ram = vec![];
+        self.prg_flashed = false;
+        self.flash.reset();
+        self.disk = image.to_bytes();
+        self.insert_image(image);
 
         Ok(())
     }
 
+    fn insert_image(&mut self, image: DiskImage) {
+        let mapper = Fds::new(image);
+        self.saved_disk = mapper.disk_image().unwrap_or_default();
+        self.mapper = Some(Box::new(mapper));
+    }
+
+    // BIOS of the Famicom Disk System, required to load disk images.
+    // Takes effect on the next load
+    pub fn set_fds_bios<P: AsRef<Path>>(&mut self, path: P) {
+        self.fds_bios = Some(path.as_ref().to_path_buf());
+    }
+
+    pub fn is_disk(&self) -> bool {
+        !self.disk.is_empty()
+    }
+
+    // Count of disk sides, zero for cartridges
+    pub fn disk_sides(&self) -> usize {
+        match self.mapper {
+            Some(ref m) => m.disk_sides(),
+            _ => 0,
+        }
+    }
+
+    pub fn disk_side(&self) -> Option<usize> {
+        match self.mapper {
+            Some(ref m) => m.disk_side(),
+            _ => None,
+        }
+    }
+
+    // Puts a side into the drive, `None` ejects the disk
+    pub fn insert_disk(&mut self, side: Option<usize>) {
+        if let Some(ref mut m) = self.mapper {
+            m.insert_disk(side);
+        }
+    }
+
+    // Discrete boards without a submapper fall back to the header flag
+    // and the board default. Submapper 1 of UxROM, CNROM and AxROM rules
+    // them out, submapper 2 has them
+    fn has_bus_conflicts(header: &CartridgeHeader, mapper: &dyn Mapper) -> bool {
+        match (header.mapper, header.submapper) {
+            (2, 1) | (3, 1) | (7, 1) => false,
+            (2, 2) | (3, 2) | (7, 2) => true,
+            _ => header.bus_conflicts || mapper.bus_conflicts(),
+        }
+    }
+
+    // Whether register writes are ANDed with the ROM, `Auto` follows the
+    // header and the board. Takes effect on the next load
+    pub fn set_bus_conflicts(&mut self, bus_conflicts: BusConflicts) {
+        self.bus_conflicts = bus_conflicts;
+    }
+
+    // Whether writes changed by a bus conflict are logged, once for each address
+    pub fn set_bus_conflict_log(&mut self, enabled: bool) {
+        self.log_conflicts = enabled;
+    }
+
+    // Whether bus conflicts are applied to the loaded cartridge
+    pub fn bus_conflicts_enabled(&self) -> bool {
+        match self.bus_conflicts {
+            BusConflicts::Auto => self.conflicts,
+            BusConflicts::Always => true,
+            BusConflicts::Never => false,
+        }
+    }
+
+    // Reads until the buffer is full or the stream ends, returns count of read bytes
+    fn read_buf<F: Read>(file: &mut F, buf: &mut [u8]) -> Result<usize> {
+        let mut size = 0;
+        while size < buf.len() {
+            match file.read(&mut buf[size..]) {
+                Ok(0) => break,
+                Ok(n) => size += n,
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e).context(errors::ReadFile),
+            }
+        }
+
+        Ok(size)
+    }
+
     pub fn load_from_file<P: AsRef<Path>>(&mut self, file_path: P) -> Result<()> {
         println!("[CARTGE] start read file: {}", file_path.as_ref().display());
 
-        let mut file = File::open(file_path).context(errors::OpenFile)?;
-        self.load(&mut file)
+        let mut file = File::open(file_path.as_ref()).context(errors::OpenFile)?;
+        self.load(&mut file)?;
+
+        let battery = match self.header {
+            Some(ref h) => h.battery,
+            _ => false,
+        };
+
+        if self.is_disk() {
+            let save_path = self.save_path_of(file_path.as_ref(), "ips");
+            self.load_disk_patch(&save_path)?;
+            self.save_path = Some(save_path);
+        } else if battery {
+            let save_path = self.save_path_of(file_path.as_ref(), "sav");
+            self.load_save(&save_path)?;
+            self.save_path = Some(save_path);
+        }
+
+        Ok(())
     }
 
-    pub fn read(&mut self, addr: Addr) -> Byte {
-        let mut mapped_addr = ExtAddr(0xFFFF_FFFF);
-        let mut value = Byte(0);
+    // The save file is named after the ROM and lives either
+    // next to it or in the configured directory
+    fn save_path_of(&self, file_path: &Path, extension: &str) -> PathBuf {
+        let save_path = file_path.with_extension(extension);
+        match (&self.save_dir, save_path.file_name()) {
+            (Some(dir), Some(name)) => dir.join(name),
+            _ => save_path,
+        }
+    }
 
-        match self.mapper {
-            Some(ref mut m) => {
-                if m.map_read(addr, &mut mapped_addr, &mut value) {
-                    if mapped_addr == 0xFFFF_FFFF.into() {
-                        // Mapper has actually set the data value, for example cartridge based RAM
-                        // Do nothing
-                    } else {
-                        // Mapper has produced an offset into cartridge bank memory
-                        value = self.prg_mem[mapped_addr.as_usize()];
-                    }
+    fn load_disk_patch(&mut self, save_path: &Path) -> Result<()> {
+        if !save_path.exists() {
+            return Ok(());
+        }
+
+        println!("[CARTGE] load disk patch: {}", save_path.display());
+
+        let patch = fs::read(save_path).context(errors::ReadFile)?;
+        let mut data = self.disk.clone();
+        apply_ips(&mut data, &patch)?;
+        self.insert_image(DiskImage::parse(&data)?);
+
+        Ok(())
+    }
+
+    fn load_save(&mut self, save_path: &Path) -> Result<()> {
+        if !save_path.exists() {
+            return Ok(());
+        }
+
+        println!("[CARTGE] load save file: {}", save_path.display());
+
+        let data = fs::read(save_path).context(errors::ReadFile)?;
+        for (cell, v) in self.prg_ram.iter_mut().zip(&data) {
+            *cell = Byte(*v);
+        }
+
+        // RAM of the mapper follows PRG RAM, reprogrammed PRG ROM comes last
+        let rest = data.get(self.prg_ram.len()..).unwrap_or(&[]);
+        let mapper_ram_size = self.mapper_battery_ram().len();
+        if let Some(ref mut m) = self.mapper {
+            m.load_battery_ram(&rest[..rest.len().min(mapper_ram_size)]);
+        }
+        self.saved_mapper_ram = self.mapper_battery_ram().to_vec();
+
+        if let Some(prg) = rest.get(mapper_ram_size..) {
+            if prg.len() == self.prg_mem.len() {
+                for (cell, v) in self.prg_mem.iter_mut().zip(prg) {
+                    *cell = Byte(*v);
                 }
+                self.prg_flashed = true;
             }
-            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn mapper_battery_ram(&self) -> &[u8] {
+        match self.mapper {
+            Some(ref m) => m.battery_ram(),
+            _ => &[],
+        }
+    }
+
+    // Directory for save files, by default they are placed next to the ROM.
+    // Takes effect on the next load
+    pub fn set_save_dir<P: AsRef<Path>>(&mut self, dir: P) {
+        self.save_dir = Some(dir.as_ref().to_path_buf());
+    }
+
+    pub fn save_path(&self) -> Option<&Path> {
+        self.save_path.as_deref()
+    }
+
+    // Flushes battery-backed RAM to the save file if it was written since the last flush
+    pub fn save(&mut self) -> Result<()> {
+        if self.is_disk() {
+            return self.save_disk();
+        }
+
+        // The mapper does not report writes to its RAM, so it is compared instead
+        let mapper_ram = self.mapper_battery_ram();
+        let dirty = self.save_ram || mapper_ram != &self.saved_mapper_ram[..];
+
+        let save_path = match self.save_path {
+            Some(ref p) if dirty => p,
+            _ => return Ok(()),
         };
 
-        value
+        if let Some(dir) = save_path.parent() {
+            fs::create_dir_all(dir).context(errors::WriteFile)?;
+        }
+
+        let mut data: Vec<u8> = self.prg_ram.iter().map(|v| v.0).collect();
+        data.extend_from_slice(mapper_ram);
+        if self.prg_flashed {
+            data.extend(self.prg_mem.iter().map(|v| v.0));
+        }
+        fs::write(save_path, data).context(errors::WriteFile)?;
+        self.save_ram = false;
+        self.saved_mapper_ram = self.mapper_battery_ram().to_vec();
+
+        Ok(())
+    }
+
+    // Rewrites the patch against the loaded image when the disk has changed
+    fn save_disk(&mut self) -> Result<()> {
+        let disk = match self.mapper {
+            Some(ref m) => m.disk_image().unwrap_or_default(),
+            _ => return Ok(()),
+        };
+
+        let save_path = match self.save_path {
+            Some(ref p) if disk != self.saved_disk => p,
+            _ => return Ok(()),
+        };
+
+        if let Some(dir) = save_path.parent() {
+            fs::create_dir_all(dir).context(errors::WriteFile)?;
+        }
+
+        fs::write(save_path, make_ips(&self.disk, &disk)).context(errors::WriteFile)?;
+        self.saved_disk = disk;
+
+        Ok(())
+    }
+
+    pub fn trainer(&self) -> &[Byte] {
+        &self.trainer
+    }
+
+    // Mappers available to the next load
+    pub fn mappers(&self) -> &MapperRegistry {
+        &self.mappers
+    }
+
+    pub fn mappers_mut(&mut self) -> &mut MapperRegistry {
+        &mut self.mappers
+    }
+
+    pub fn header(&self) -> Option<&CartridgeHeader> {
+        self.header.as_ref()
+    }
+
+    // Whether pattern tables are writable RAM rather than ROM
+    pub fn has_chr_ram(&self) -> bool {
+        self.chr_ram
+    }
+
+    pub fn read(&mut self, addr: Addr) -> Byte {
+        let mapped = match self.mapper {
+            Some(ref mut m) => m.read(addr),
+            _ => Mapped::Unmapped,
+        };
+
+        match mapped {
+            // Mapper has produced an offset into cartridge bank memory,
+            // a ROM smaller than the bank size is mirrored
+            Mapped::Prg(i) => self.prg_mem[i % self.prg_mem.len()],
+            Mapped::Flash(i) => self.flash.read(&self.prg_mem, i),
+            // Without RAM chip the value is an open bus
+            Mapped::PrgRam(i) if !self.prg_ram.is_empty() => self.prg_ram[i % self.prg_ram.len()],
+            // Mapper has actually set the data value, for example mapper registers
+            Mapped::Data(v) => v,
+            _ => Byte(0),
+        }
     }
 
     pub fn write(&mut self, addr: Addr, v: Byte) {
-        let mut mapped_addr = ExtAddr(0xFFFF_FFFF);
+        let v = match addr {
+            Addr(0x8000..=0xFFFF) if self.bus_conflicts_enabled() => self.bus_conflict(addr, v),
+            _ => v,
+        };
 
-        match self.mapper {
-            Some(ref mut m) => {
-                if m.map_write(addr, &mut mapped_addr, v) {
-                    if mapped_addr == 0xFFFF_FFFF.into() {
-                        // Mapper has actually set the data value, for example cartridge based RAM
-                        // Do nothing
-                    } else {
-                        // Mapper has produced an offset into cartridge bank memory
-                        self.prg_mem[mapped_addr.as_usize()] = v;
-                    }
-                }
+        let mapped = match self.mapper {
+            Some(ref mut m) => m.write(addr, v),
+            _ => Mapped::Unmapped,
+        };
+
+        match mapped {
+            // Mapper has produced an offset into cartridge bank memory
+            Mapped::Prg(i) => {
+                let len = self.prg_mem.len();
+                self.prg_mem[i % len] = v;
+            }
+            // Flash chip decodes the write as part of a command
+            Mapped::Flash(i) if self.flash.write(&mut self.prg_mem, i, v) => {
+                self.prg_flashed = true;
+                self.save_ram = true;
+            }
+            // Mapper has produced an offset into cartridge RAM
+            Mapped::PrgRam(i) if !self.prg_ram.is_empty() => {
+                let len = self.prg_ram.len();
+                self.prg_ram[i % len] = v;
+                self.save_ram = true;
             }
             _ => {}
         }
     }
 
-    pub fn read_chr(&mut self, addr: Addr) -> (Byte, bool) {
-        let mut mapped_addr = ExtAddr(0xFFFF_FFFF);
-        let mut v = Byte(0x00);
-        let mut mapped = false;
+    // The written value loses the bits the ROM byte at the address holds low.
+    // Writes changed that way can be logged, as the game relies on them
+    fn bus_conflict(&mut self, addr: Addr, v: Byte) -> Byte {
+        let rom = match self.mapper {
+            Some(ref mut m) => match m.read(addr) {
+                Mapped::Prg(i) => self.prg_mem[i % self.prg_mem.len()],
+                _ => return v,
+            },
+            _ => return v,
+        };
 
-        match self.mapper {
-            Some(ref mut m) => {
-                if m.map_read_chr(addr, &mut mapped_addr) {
-                    // Mapper has produced an offset into cartridge bank memory
-                    v = self.chr_mem[mapped_addr.as_usize()];
-                    mapped = true;
-                }
+        if self.log_conflicts && v & rom != v && self.logged.insert(addr.0) {
+            println!(
+                "[CARTGE] bus conflict at {:#06X}: wrote {:#04X}, ROM holds {:#04X}",
+                addr, v.0, rom.0
+            );
+        }
+
+        v & rom
+    }
+
+    // Pattern table access. Unmapped and CIRAM offsets are left
+    // to the PPU, everything else is resolved to data
+    pub fn read_chr(&mut self, addr: Addr) -> Mapped {
+        let mapped = match self.mapper {
+            Some(ref mut m) => m.read_chr(addr),
+            _ => Mapped::Unmapped,
+        };
+
+        match mapped {
+            Mapped::Unmapped | Mapped::CiRam(_) => mapped,
+            mapped => Mapped::Data(self.read_mapped_ppu(mapped)),
+        }
+    }
+
+    pub fn write_chr(&mut self, addr: Addr, v: Byte) -> Mapped {
+        let mapped = match self.mapper {
+            Some(ref mut m) => m.write_chr(addr, v),
+            _ => Mapped::Unmapped,
+        };
+
+        match mapped {
+            Mapped::Unmapped | Mapped::CiRam(_) => mapped,
+            mapped => {
+                self.write_mapped_ppu(mapped, v);
+                Mapped::Data(v)
             }
-            _ => {}
+        }
+    }
+
+    // Nametable access the mapper has taken over. Unmapped and CIRAM
+    // offsets are left to the PPU, everything else is resolved to data
+    pub fn read_nametable(&mut self, addr: Addr) -> Mapped {
+        let mapped = match self.mapper {
+            Some(ref mut m) => m.read_nametable(addr),
+            _ => Mapped::Unmapped,
         };
 
-        (v, mapped)
+        match mapped {
+            Mapped::Unmapped | Mapped::CiRam(_) => mapped,
+            mapped => Mapped::Data(self.read_mapped_ppu(mapped)),
+        }
     }
 
-    pub fn write_chr(&mut self, addr: Addr, v: Byte) -> bool {
-        let mut mapped_addr = ExtAddr(0xFFFF_FFFF);
-        let mut mapped = false;
+    pub fn write_nametable(&mut self, addr: Addr, v: Byte) -> Mapped {
+        let mapped = match self.mapper {
+            Some(ref mut m) => m.write_nametable(addr, v),
+            _ => Mapped::Unmapped,
+        };
 
-        match self.mapper {
-            Some(ref mut m) => {
-                if m.map_write_chr(addr, &mut mapped_addr) {
-                    // Mapper has produced an offset into cartridge bank memory
-                    self.chr_mem[mapped_addr.as_usize()] = v;
-                    mapped = true;
-                }
+        match mapped {
+            Mapped::Unmapped | Mapped::CiRam(_) => mapped,
+            mapped => {
+                self.write_mapped_ppu(mapped, v);
+                Mapped::Data(v)
             }
+        }
+    }
+
+    fn read_mapped_ppu(&self, mapped: Mapped) -> Byte {
+        match mapped {
+            // Mapper has produced an offset into cartridge bank memory
+            Mapped::Chr(i) => self.chr_mem[i % self.chr_mem.len()],
+            Mapped::Vram(i) => self.read_vram(Addr(i as u16)),
+            Mapped::Data(v) => v,
+            _ => Byte(0x00),
+        }
+    }
+
+    fn write_mapped_ppu(&mut self, mapped: Mapped, v: Byte) {
+        match mapped {
+            // CHR ROM silently ignores the write
+            Mapped::Chr(i) if self.chr_ram => {
+                let len = self.chr_mem.len();
+                self.chr_mem[i % len] = v;
+            }
+            Mapped::Vram(i) => self.write_vram(Addr(i as u16), v),
             _ => {}
         }
+    }
+
+    // Lets the mapper watch the PPU address bus
+    pub fn ppu_bus(&mut self, addr: Addr) {
+        if let Some(ref mut m) = self.mapper {
+            m.ppu_bus(addr);
+        }
+    }
+
+    pub fn ppu_fetch(&mut self, fetch: PpuFetch, tall_sprites: bool) {
+        if let Some(ref mut m) = self.mapper {
+            m.ppu_fetch(fetch, tall_sprites);
+        }
+    }
+
+    pub fn cpu_tick(&mut self) {
+        if let Some(ref mut m) = self.mapper {
+            m.cpu_tick();
+        }
+    }
+
+    pub fn audio(&self) -> f32 {
+        match self.mapper {
+            Some(ref m) => m.audio(),
+            _ => 0.0,
+        }
+    }
 
-        mapped
+    pub fn read_vram(&self, addr: Addr) -> Byte {
+        match self.vram.get(addr.as_usize()) {
+            Some(v) => *v,
+            None => Byte(0),
+        }
+    }
+
+    pub fn write_vram(&mut self, addr: Addr, v: Byte) {
+        if let Some(cell) = self.vram.get_mut(addr.as_usize()) {
+            *cell = v;
+        }
     }
 
     pub fn mirror(&self) -> Mirror {
+        // Four-screen VRAM ignores mirroring control of the mapper
+        if let Mirror::FourScreen = self.mirror {
+            return Mirror::FourScreen;
+        }
+
         let mapper_mirror = match self.mapper {
             Some(ref m) => m.mirror(),
             _ => Mirror::Hardware,
@@ -382,6 +861,20 @@ impl Cartridge {
             _ => {}
         };
     }
+
+    pub fn reset(&mut self) {
+        match self.mapper {
+            Some(ref mut m) => m.reset(),
+            _ => {}
+        };
+        self.flash.reset();
+
+        // Some ROMs expect the trainer in the work RAM before they start
+        if !self.trainer.is_empty() {
+            let end = TRAINER_OFFSET + self.trainer.len();
+            self.prg_ram[TRAINER_OFFSET..end].copy_from_slice(&self.trainer);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,227 @@
+use crate::prelude::*;
+
+// Famicom Disk System images
+//
+// A .fds image holds the blocks of each disk side back to back, without
+// the gaps and CRCs the drive sees, padded to 65500 bytes. It may start
+// with the 16 byte fwNES header ("FDS" $1A, count of sides). A QD image
+// is a dump of the Quick Disk itself, 65536 bytes per side, which keeps
+// the CRC after each block
+//
+// Block  Length          Meaning
+// 1      56              Disk info, starts with "*NINTENDO-HVC*"
+// 2      2               Count of files
+// 3      16              File header, file size at +13 (low) and +14 (high)
+// 4      1 + file size   File data
+pub const FDS_SIDE_SIZE: usize = 65500;
+pub const QD_SIDE_SIZE: usize = 65536;
+
+const FWNES_HEADER_SIZE: usize = 16;
+const FWNES_MAGIC: &[u8] = b"FDS\x1A";
+const DISK_INFO_MAGIC: &[u8] = b"\x01*NINTENDO-HVC*";
+
+// The drive reads 28300 bits of gap before the first block
+// and 976 bits between blocks
+const LEAD_IN_SIZE: usize = 28300 / 8;
+const BLOCK_GAP_SIZE: usize = 976 / 8;
+// First non-zero byte after a gap, the block starts right after it
+pub const GAP_END: u8 = 0x80;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiskFormat {
+    Fds,
+    Qd,
+}
+
+impl DiskFormat {
+    fn side_size(self) -> usize {
+        match self {
+            DiskFormat::Fds => FDS_SIDE_SIZE,
+            DiskFormat::Qd => QD_SIDE_SIZE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskImage {
+    format: DiskFormat,
+    sides:  Vec<Vec<u8>>,
+}
+
+impl DiskImage {
+    // Whether the start of a file looks like a disk image rather than iNES
+    pub fn detect(data: &[u8]) -> bool {
+        data.starts_with(FWNES_MAGIC) || data.starts_with(DISK_INFO_MAGIC)
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.starts_with(FWNES_MAGIC) && data.len() < FWNES_HEADER_SIZE {
+            return errors::TruncatedHeader { size: data.len() }.fail();
+        }
+
+        let data = Self::strip_header(data);
+        if data.is_empty() {
+            return errors::InvalidSize {
+                detail: "disk image holds no side".to_string(),
+            }
+            .fail();
+        }
+        if !data.starts_with(DISK_INFO_MAGIC) {
+            let mut magic = [0; 4];
+            let len = data.len().min(magic.len());
+            magic[..len].copy_from_slice(&data[..len]);
+            return errors::BadMagic { magic }.fail();
+        }
+
+        // Lengths of both formats are multiples of their side size,
+        // anything else is a truncated .fds image
+        let format = if data.len().is_multiple_of(QD_SIDE_SIZE)
+            && !data.len().is_multiple_of(FDS_SIDE_SIZE)
+        {
+            DiskFormat::Qd
+        } else {
+            DiskFormat::Fds
+        };
+        let size = format.side_size();
+
+        let sides = data
+            .chunks(size)
+            .map(|c| {
+                let mut side = c.to_vec();
+                side.resize(size, 0);
+                side
+            })
+            .collect();
+
+        Ok(Self { format, sides })
+    }
+
+    // The image without the fwNES header, patches apply to this data
+    pub fn strip_header(data: &[u8]) -> &[u8] {
+        if data.starts_with(FWNES_MAGIC) {
+            data.get(FWNES_HEADER_SIZE..).unwrap_or(&[])
+        } else {
+            data
+        }
+    }
+
+    pub fn format(&self) -> DiskFormat {
+        self.format
+    }
+
+    pub fn sides(&self) -> usize {
+        self.sides.len()
+    }
+
+    // Sides back to back in the format of the image, without fwNES header
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.sides.concat()
+    }
+
+    // A side as the drive reads it: a gap and its end mark before each
+    // block and the CRC after it. Free space follows the last block
+    pub fn raw_side(&self, side: usize) -> Vec<u8> {
+        let data = &self.sides[side];
+        let mut raw = vec![0; LEAD_IN_SIZE];
+
+        let mut i = 0;
+        let mut file_size = 0;
+        while let Some(len) = Self::block_len(data.get(i).copied(), file_size) {
+            let end = (i + len).min(data.len());
+            let block = &data[i..end];
+            if block[0] == 3 && block.len() == 16 {
+                file_size = block[13] as usize | (block[14] as usize) << 8;
+            }
+
+            raw.push(GAP_END);
+            raw.extend_from_slice(block);
+            i = end;
+
+            match self.format {
+                DiskFormat::Fds => {
+                    let crc = Self::block_crc(block);
+                    raw.extend_from_slice(&crc.to_le_bytes());
+                }
+                DiskFormat::Qd => {
+                    let end = (i + 2).min(data.len());
+                    raw.extend_from_slice(&data[i..end]);
+                    i = end;
+                }
+            }
+
+            raw.extend_from_slice(&[0; BLOCK_GAP_SIZE]);
+        }
+
+        let size = LEAD_IN_SIZE + self.format.side_size();
+        raw.resize(raw.len().max(size), 0);
+        raw
+    }
+
+    // Takes the blocks back from a side the drive has written to, gaps
+    // are dropped and so are CRCs unless the format keeps them
+    pub fn set_raw_side(&mut self, side: usize, raw: &[u8]) {
+        let size = self.format.side_size();
+        let mut data = Vec::with_capacity(size);
+
+        let mut i = 0;
+        let mut file_size = 0;
+        // Each block follows the end mark of a gap
+        while let Some(n) = raw[i.min(raw.len())..].iter().position(|v| *v == GAP_END) {
+            i += n + 1;
+
+            let len = match Self::block_len(raw.get(i).copied(), file_size) {
+                Some(len) => len,
+                None => break,
+            };
+            let end = (i + len).min(raw.len());
+            let block = &raw[i..end];
+            if block[0] == 3 && block.len() == 16 {
+                file_size = block[13] as usize | (block[14] as usize) << 8;
+            }
+            data.extend_from_slice(block);
+
+            let crc_end = (end + 2).min(raw.len());
+            if self.format == DiskFormat::Qd {
+                data.extend_from_slice(&raw[end..crc_end]);
+            }
+            i = crc_end;
+        }
+
+        data.resize(size, 0);
+        self.sides[side] = data;
+    }
+
+    fn block_len(kind: Option<u8>, file_size: usize) -> Option<usize> {
+        match kind? {
+            1 => Some(56),
+            2 => Some(2),
+            3 => Some(16),
+            4 => Some(1 + file_size),
+            _ => None,
+        }
+    }
+
+    // CRC the drive writes after a block, it covers the gap end mark,
+    // the block and two zero bytes
+    fn block_crc(block: &[u8]) -> u16 {
+        std::iter::once(&GAP_END)
+            .chain(block)
+            .chain(&[0, 0])
+            .fold(0, |crc, v| update_crc(crc, *v))
+    }
+}
+
+// Shifts a byte into the CRC-16 of the drive (polynomial $8408, LSB first)
+pub fn update_crc(crc: u16, v: u8) -> u16 {
+    (0..8).fold(crc, |crc, bit| {
+        let carry = crc & 0x0001 != 0;
+        let mut crc = crc >> 1;
+        if carry {
+            crc ^= 0x8408;
+        }
+        if v & (1 << bit) != 0 {
+            crc ^= 0x8000;
+        }
+        crc
+    })
+}
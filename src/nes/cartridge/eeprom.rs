@@ -0,0 +1,217 @@
+// Serial EEPROM on the I2C bus, as found on Bandai boards
+//
+// The CPU bit-bangs the clock (SCL) and data (SDA) lines. A transfer
+// begins with a start condition (SDA falls while SCL is high) and ends
+// with a stop condition (SDA rises while SCL is high). Bits are sampled
+// on the rising edge of SCL and each byte is followed by an acknowledge
+// bit, driven low by the receiver.
+//
+// 24C02 (256 bytes) expects a device address byte (1010xxxR), then for
+// writes a word address and data, for reads it sends data from the
+// current address. 24C01 (128 bytes) skips the device address and takes
+// a 7 bit word address with the read flag in the lowest bit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EepromChip {
+    C24C01,
+    C24C02,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EepromState {
+    Idle,
+    Device,
+    Address,
+    Write,
+    Read,
+    // The EEPROM acknowledges the received byte
+    Ack,
+    // The master acknowledges the sent byte
+    MasterAck,
+}
+
+pub struct Eeprom {
+    chip: EepromChip,
+    data: Vec<u8>,
+
+    state:   EepromState,
+    next:    EepromState,
+    acked:   bool,
+    shift:   u8,
+    bits:    u8,
+    address: usize,
+
+    scl: bool,
+    sda: bool,
+    out: bool,
+}
+
+impl Eeprom {
+    pub fn new(chip: EepromChip) -> Self {
+        let size = match chip {
+            EepromChip::C24C01 => 128,
+            EepromChip::C24C02 => 256,
+        };
+
+        let mut s = Self {
+            chip,
+            data: vec![0xFF; size],
+            state: EepromState::Idle,
+            next: EepromState::Idle,
+            acked: false,
+            shift: 0,
+            bits: 0,
+            address: 0,
+            scl: true,
+            sda: true,
+            out: true,
+        };
+        s.reset();
+        s
+    }
+
+    // Stored data survives the reset
+    pub fn reset(&mut self) {
+        self.state = EepromState::Idle;
+        self.next = EepromState::Idle;
+        self.acked = false;
+        self.shift = 0;
+        self.bits = 0;
+        self.address = 0;
+        self.scl = true;
+        self.sda = true;
+        self.out = true;
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn load(&mut self, data: &[u8]) {
+        for (cell, v) in self.data.iter_mut().zip(data) {
+            *cell = *v;
+        }
+    }
+
+    // Level the EEPROM drives on SDA, high when it is released
+    pub fn sda_out(&self) -> bool {
+        self.out
+    }
+
+    // Sets both lines driven by the master
+    pub fn write(&mut self, scl: bool, sda: bool) {
+        if self.scl && scl && self.sda && !sda {
+            self.start();
+        } else if self.scl && scl && !self.sda && sda {
+            self.state = EepromState::Idle;
+            self.out = true;
+        } else if !self.scl && scl {
+            self.rise(sda);
+        } else if self.scl && !scl {
+            self.fall();
+        }
+
+        self.scl = scl;
+        self.sda = sda;
+    }
+
+    fn start(&mut self) {
+        self.state = match self.chip {
+            EepromChip::C24C01 => EepromState::Address,
+            EepromChip::C24C02 => EepromState::Device,
+        };
+        self.shift = 0;
+        self.bits = 0;
+        self.out = true;
+    }
+
+    // Rising edge of SCL, the receiver samples SDA
+    fn rise(&mut self, sda: bool) {
+        match self.state {
+            EepromState::Device | EepromState::Address | EepromState::Write => {
+                self.shift = (self.shift << 1) | sda as u8;
+                self.bits += 1;
+                if self.bits == 8 {
+                    self.receive();
+                }
+            }
+            EepromState::Read => {
+                self.bits += 1;
+                if self.bits == 8 {
+                    self.state = EepromState::MasterAck;
+                }
+            }
+            EepromState::MasterAck => {
+                if sda {
+                    // No acknowledge ends the read
+                    self.state = EepromState::Idle;
+                } else {
+                    self.address = (self.address + 1) % self.data.len();
+                    self.state = EepromState::Read;
+                    self.bits = 0;
+                }
+            }
+            EepromState::Ack => self.acked = true,
+            EepromState::Idle => {}
+        }
+    }
+
+    // Falling edge of SCL, the transmitter changes SDA
+    fn fall(&mut self) {
+        match self.state {
+            EepromState::Ack if !self.acked => self.out = false,
+            EepromState::Ack => {
+                self.state = self.next;
+                self.shift = 0;
+                self.bits = 0;
+                self.out = match self.state {
+                    EepromState::Read => self.data[self.address] & 0x80 != 0,
+                    _ => true,
+                };
+            }
+            EepromState::Read => self.out = (self.data[self.address] << self.bits) & 0x80 != 0,
+            _ => self.out = true,
+        }
+    }
+
+    // A whole byte has arrived
+    fn receive(&mut self) {
+        let v = self.shift;
+        let read = v & 0x01 != 0;
+
+        self.next = match (self.state, self.chip) {
+            (EepromState::Device, _) if v & 0xF0 != 0xA0 => EepromState::Idle,
+            (EepromState::Device, _) if read => EepromState::Read,
+            (EepromState::Device, _) => EepromState::Address,
+            (EepromState::Address, EepromChip::C24C01) => {
+                self.address = (v >> 1) as usize;
+                if read {
+                    EepromState::Read
+                } else {
+                    EepromState::Write
+                }
+            }
+            (EepromState::Address, EepromChip::C24C02) => {
+                self.address = v as usize;
+                EepromState::Write
+            }
+            _ => {
+                self.data[self.address] = v;
+                // Writes wrap around within a page
+                let page = match self.chip {
+                    EepromChip::C24C01 => 0x03,
+                    EepromChip::C24C02 => 0x07,
+                };
+                self.address = (self.address & !page) | ((self.address + 1) & page);
+                EepromState::Write
+            }
+        };
+
+        // A foreign device address is not acknowledged
+        if self.next == EepromState::Idle {
+            self.state = EepromState::Idle;
+        } else {
+            self.state = EepromState::Ack;
+            self.acked = false;
+        }
+    }
+}
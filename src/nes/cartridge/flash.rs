@@ -0,0 +1,123 @@
+use crate::prelude::*;
+
+// Manufacturer and device ID of the SST39SF040
+const MANUFACTURER_ID: u8 = 0xBF;
+const DEVICE_ID: u8 = 0xB7;
+// Smallest erasable unit
+const SECTOR_SIZE: usize = 0x1000;
+
+// Self-flashable PRG ROM, an SST39SF040 or compatible chip
+//
+// Commands are sequences of writes to chip addresses $5555 and $2AAA,
+// only the lower 15 bits of the address are decoded
+// Sequence                                         Command
+// $5555=$AA $2AAA=$55 $5555=$A0 addr=data          Program a byte
+// $5555=$AA $2AAA=$55 $5555=$80
+//     $5555=$AA $2AAA=$55 sector=$30               Erase a 4 KB sector
+//     $5555=$AA $2AAA=$55 $5555=$10                Erase the whole chip
+// $5555=$AA $2AAA=$55 $5555=$90                    Enter software ID mode
+// $5555=$AA $2AAA=$55 $5555=$F0 or any addr=$F0    Leave software ID mode
+//
+// Programming can only clear bits. The chip completes every operation
+// at once, so a game polling for completion sees the result right away
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FlashState {
+    Ready,
+    Unlock1,
+    Unlock2,
+    Program,
+    Erase,
+    EraseUnlock1,
+    EraseUnlock2,
+}
+
+pub struct Flash {
+    state:   FlashState,
+    id_mode: bool,
+}
+
+impl Default for Flash {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Flash {
+    pub fn new() -> Self {
+        Self {
+            state:   FlashState::Ready,
+            id_mode: false,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.state = FlashState::Ready;
+        self.id_mode = false;
+    }
+
+    // Reads `mem` at offset `i` unless the chip answers with its ID
+    pub fn read(&self, mem: &[Byte], i: usize) -> Byte {
+        if self.id_mode {
+            return match i & 0x01 {
+                0 => Byte(MANUFACTURER_ID),
+                _ => Byte(DEVICE_ID),
+            };
+        }
+
+        mem[i % mem.len()]
+    }
+
+    // Feeds a write at offset `i` to the command decoder, returns
+    // whether the contents of `mem` have changed
+    pub fn write(&mut self, mem: &mut [Byte], i: usize, v: Byte) -> bool {
+        let cmd = i & 0x7FFF;
+        let mut changed = false;
+
+        self.state = match (self.state, cmd, v.0) {
+            // The data byte of a program command may be any value, $F0 included
+            (FlashState::Program, _, _) => {
+                let len = mem.len();
+                let cell = &mut mem[i % len];
+                changed = *cell & v != *cell;
+                *cell &= v;
+                FlashState::Ready
+            }
+            (_, _, 0xF0) => {
+                self.id_mode = false;
+                FlashState::Ready
+            }
+            (FlashState::Ready, 0x5555, 0xAA) => FlashState::Unlock1,
+            (FlashState::Unlock1, 0x2AAA, 0x55) => FlashState::Unlock2,
+            (FlashState::Unlock2, 0x5555, 0xA0) => FlashState::Program,
+            (FlashState::Unlock2, 0x5555, 0x80) => FlashState::Erase,
+            (FlashState::Unlock2, 0x5555, 0x90) => {
+                self.id_mode = true;
+                FlashState::Ready
+            }
+            (FlashState::Erase, 0x5555, 0xAA) => FlashState::EraseUnlock1,
+            (FlashState::EraseUnlock1, 0x2AAA, 0x55) => FlashState::EraseUnlock2,
+            (FlashState::EraseUnlock2, 0x5555, 0x10) => {
+                changed = Self::erase(mem);
+                FlashState::Ready
+            }
+            (FlashState::EraseUnlock2, _, 0x30) => {
+                let start = (i % mem.len()) & !(SECTOR_SIZE - 1);
+                let end = (start + SECTOR_SIZE).min(mem.len());
+                changed = Self::erase(&mut mem[start..end]);
+                FlashState::Ready
+            }
+            // Anything else aborts the sequence
+            _ => FlashState::Ready,
+        };
+
+        changed
+    }
+
+    fn erase(mem: &mut [Byte]) -> bool {
+        let changed = mem.iter().any(|v| *v != Byte(0xFF));
+        for cell in mem.iter_mut() {
+            *cell = Byte(0xFF);
+        }
+        changed
+    }
+}
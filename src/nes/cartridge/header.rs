@@ -0,0 +1,504 @@
+use super::cartridge::Mirror;
+use crate::prelude::*;
+
+pub const HEADER_SIZE: usize = 16; // 16 byte
+
+const PROGRAM_ROM_UNIT: usize = 16384; // 16 kb
+const CHARACTER_ROM_UNIT: usize = 8192; // 8 kb
+const PROGRAM_RAM_UNIT: usize = 8192; // 8 kb
+
+// iNES header format (16 bytes):
+// 0-3: Constant $4E $45 $53 $1A ("NES" followed by MS-DOS end-of-file)
+// 4: Size of PRG ROM in 16 KB units
+// 5: Size of CHR ROM in 8 KB units (Value 0 means the board uses CHR RAM)
+// 6: Flags 6 - Mapper, mirroring, battery, trainer
+// 7: Flags 7 - Mapper, VS/Playchoice, NES 2.0
+// 8: Flags 8 - PRG-RAM size (rarely used extension)
+// 9: Flags 9 - TV system (rarely used extension)
+// 10: Flags 10 - TV system, PRG-RAM presence (unofficial, rarely used extension)
+// 11-15: Unused padding (should be filled with zero, but some rippers put their name across bytes 7-15)
+//
+// Flags 6
+// 76543210
+// ||||||||
+// |||||||+- Mirroring: 0: horizontal (vertical arrangement) (CIRAM A10 = PPU A11)
+// |||||||              1: vertical (horizontal arrangement) (CIRAM A10 = PPU A10)
+// ||||||+-- 1: Cartridge contains battery-backed PRG RAM ($6000-7FFF) or other persistent memory
+// |||||+--- 1: 512-byte trainer at $7000-$71FF (stored before PRG data)
+// ||||+---- 1: Ignore mirroring control or above mirroring bit; instead provide four-screen VRAM
+// ++++----- Lower nybble of mapper number
+//
+// Flags 7
+// 76543210
+// ||||||||
+// |||||||+- VS Unisystem
+// ||||||+-- PlayChoice-10 (8KB of Hint Screen data stored after CHR data)
+// ||||++--- If equal to 2, flags 8-15 are in NES 2.0 format
+// ++++----- Upper nybble of mapper number
+//
+// Flags 8
+// 76543210
+// ||||||||
+// ++++++++- PRG RAM size
+//
+// Flags 9
+// 76543210
+// ||||||||
+// |||||||+- TV system (0: NTSC; 1: PAL)
+// +++++++-- Reserved, set to zero
+//
+// Flags 10
+// 76543210
+//   ||  ||
+//   ||  ++- TV system (0: NTSC; 2: PAL; 1/3: dual compatible)
+//   |+----- PRG RAM ($6000-$7FFF) (0: present; 1: not present)
+//   +------ 0: Board has no bus conflicts; 1: Board has bus conflicts
+//
+// NES 2.0 reuses bytes 0-7 and redefines the rest:
+// 8: Mapper MSB/Submapper
+//    7654 3210
+//    ---------
+//    SSSS NNNN
+//    |||| ++++- Mapper number D8..D11
+//    ++++------ Submapper number
+// 9: PRG-ROM/CHR-ROM size MSB
+//    7654 3210
+//    ---------
+//    CCCC PPPP
+//    |||| ++++- PRG-ROM size MSB
+//    ++++------ CHR-ROM size MSB
+// 10: PRG-RAM/EEPROM size
+//    7654 3210
+//    ---------
+//    pppp PPPP
+//    |||| ++++- PRG-RAM (volatile) shift count
+//    ++++------ PRG-NVRAM/EEPROM (non-volatile) shift count
+//    If the shift count is zero, there is no PRG-(NV)RAM.
+//    If the shift count is non-zero, the actual size is
+//    "64 << shift count" bytes, i.e. 8192 bytes for a shift count of 7.
+// 11: CHR-RAM size
+//    7654 3210
+//    ---------
+//    cccc CCCC
+//    |||| ++++- CHR-RAM size (volatile) shift count
+//    ++++------ CHR-NVRAM size (non-volatile) shift count
+// 12: CPU/PPU Timing
+//    7654 3210
+//    ---------
+//    .... ..VV
+//           ++- CPU/PPU timing mode (0: NTSC; 1: PAL; 2: multiple-region; 3: Dendy)
+// 13: When Byte 7 AND 3 =1: Vs. System Type
+//    7654 3210
+//    ---------
+//    MMMM PPPP
+//    |||| ++++- Vs. PPU Type
+//    ++++------ Vs. Hardware Type
+//    When Byte 7 AND 3 =3: Extended Console Type
+//    7654 3210
+//    ---------
+//    .... CCCC
+//         ++++- Extended Console Type
+// 14: Miscellaneous ROMs
+//    7654 3210
+//    ---------
+//    .... ..RR
+//           ++- Number of miscellaneous ROMs present
+// 15: Default Expansion Device
+//    7654 3210
+//    ---------
+//    ..DD DDDD
+//      ++-++++- Default Expansion Device
+//
+// If the MSB nybble of a ROM size is $F, the LSB byte uses an
+// exponent-multiplier notation instead:
+//    7654 3210
+//    ---------
+//    EEEE EEMM
+//    |||| ||++- Multiplier, actual value is MM*2+1 (1,3,5,7)
+//    ++++-++--- Exponent (2^E), 0-63
+//    The actual size is "2^E * (MM*2+1)" bytes.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderFormat {
+    // Pre-1.0 iNES, bytes 7-15 may hold garbage such as the ripper's name
+    Archaic,
+    INes,
+    Nes20,
+    // Built from the chunks of a UNIF file
+    Unif,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timing {
+    Ntsc,
+    Pal,
+    Multi,
+    Dendy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleType {
+    Nes,
+    VsSystem,
+    Playchoice10,
+    // Extended console type from NES 2.0 byte 13
+    Extended(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VsPpuType {
+    Rp2c03b,
+    Rp2c03g,
+    Rp2c04_0001,
+    Rp2c04_0002,
+    Rp2c04_0003,
+    Rp2c04_0004,
+    Rc2c03b,
+    Rc2c03c,
+    Rc2c05_01,
+    Rc2c05_02,
+    Rc2c05_03,
+    Rc2c05_04,
+    Rc2c05_05,
+    Unknown(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VsHardwareType {
+    Unisystem,
+    UnisystemRbiBaseball,
+    UnisystemTkoBoxing,
+    UnisystemSuperXevious,
+    UnisystemIceClimberJapan,
+    DualSystem,
+    DualSystemRaidOnBungelingBay,
+    Unknown(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VsSystemType {
+    pub ppu:      VsPpuType,
+    pub hardware: VsHardwareType,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpansionDevice {
+    Unspecified,
+    StandardControllers,
+    FourScore,
+    FamicomFourPlayersAdapter,
+    VsSystem,
+    VsSystemReversed,
+    VsPinball,
+    VsZapper,
+    Zapper,
+    TwoZappers,
+    BandaiHyperShot,
+    PowerPadSideA,
+    PowerPadSideB,
+    FamilyTrainerSideA,
+    FamilyTrainerSideB,
+    ArkanoidVausNes,
+    ArkanoidVausFamicom,
+    TwoVausAndDataRecorder,
+    KonamiHyperShot,
+    // Any other device from the NES 2.0 list
+    Other(u8),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CartridgeHeader {
+    pub format:    HeaderFormat,
+    pub mapper:    u16,
+    pub submapper: u8,
+
+    // Sizes in bytes
+    pub prg_rom_size:   usize,
+    pub chr_rom_size:   usize,
+    pub prg_ram_size:   usize,
+    pub prg_nvram_size: usize,
+    pub chr_ram_size:   usize,
+    pub chr_nvram_size: usize,
+
+    // Horizontal, vertical or four-screen, UNIF may also tell one-screen
+    pub mirror:        Mirror,
+    // Bit 0 of flags 6 as is, some boards give it a meaning of their
+    // own when four-screen is set
+    pub mirror_bit:    bool,
+    pub battery:       bool,
+    pub trainer:       bool,
+    pub bus_conflicts: bool,
+
+    pub timing:                   Timing,
+    pub console_type:             ConsoleType,
+    pub vs_system:                Option<VsSystemType>,
+    pub misc_roms:                u8,
+    pub default_expansion_device: ExpansionDevice,
+}
+
+impl CartridgeHeader {
+    pub fn parse(buf: &[u8]) -> Result<Self> {
+        if buf.len() < HEADER_SIZE {
+            return errors::TruncatedHeader { size: buf.len() }.fail();
+        }
+
+        if buf[0..4] != [0x4E, 0x45, 0x53, 0x1A] {
+            return errors::BadMagic {
+                magic: [buf[0], buf[1], buf[2], buf[3]],
+            }
+            .fail();
+        }
+
+        let flags_6 = buf[6];
+        let flags_7 = buf[7];
+
+        let format = match flags_7 & 0x0C {
+            0x08 => HeaderFormat::Nes20,
+            0x00 if buf[12..16].iter().all(|b| *b == 0) => HeaderFormat::INes,
+            _ => HeaderFormat::Archaic,
+        };
+
+        let mirror = if flags_6 & 0x08 != 0x00 {
+            Mirror::FourScreen
+        } else if flags_6 & 0x01 != 0x00 {
+            Mirror::Vertical
+        } else {
+            Mirror::Horizontal
+        };
+        let battery = flags_6 & 0x02 != 0x00;
+        let trainer = flags_6 & 0x04 != 0x00;
+
+        match format {
+            HeaderFormat::Nes20 => Self::parse_nes_2_0(buf, mirror, battery, trainer),
+            _ => Self::parse_ines(buf, format, mirror, battery, trainer),
+        }
+    }
+
+    fn parse_ines(
+        buf: &[u8],
+        format: HeaderFormat,
+        mirror: Mirror,
+        battery: bool,
+        trainer: bool,
+    ) -> Result<Self> {
+        let flags_6 = buf[6];
+        let flags_7 = buf[7];
+
+        // Archaic headers often carry garbage in bytes 7-15, so only
+        // the lower nybble of the mapper number can be trusted
+        let (mapper, flags_7, flags_8, flags_9, flags_10) = match format {
+            HeaderFormat::Archaic => ((flags_6 >> 4) as u16, 0, 0, 0, 0),
+            _ => (
+                ((flags_7 & 0xF0) | (flags_6 >> 4)) as u16,
+                flags_7,
+                buf[8],
+                buf[9],
+                buf[10],
+            ),
+        };
+
+        let chr_rom_size = buf[5] as usize * CHARACTER_ROM_UNIT;
+
+        // Value 0 infers 8 KB for compatibility
+        let ram_size = (flags_8 as usize).max(1) * PROGRAM_RAM_UNIT;
+        let (prg_ram_size, prg_nvram_size) = if battery {
+            (0, ram_size)
+        } else {
+            (ram_size, 0)
+        };
+
+        let console_type = match flags_7 & 0x03 {
+            0x01 => ConsoleType::VsSystem,
+            0x02 => ConsoleType::Playchoice10,
+            _ => ConsoleType::Nes,
+        };
+
+        Ok(Self {
+            format,
+            mapper,
+            submapper: 0,
+            prg_rom_size: buf[4] as usize * PROGRAM_ROM_UNIT,
+            chr_rom_size,
+            prg_ram_size,
+            prg_nvram_size,
+            // iNES cannot tell the size of CHR RAM, the board default applies
+            chr_ram_size: 0,
+            chr_nvram_size: 0,
+            mirror,
+            mirror_bit: flags_6 & 0x01 != 0x00,
+            battery,
+            trainer,
+            bus_conflicts: flags_10 & 0x20 != 0x00,
+            timing: if flags_9 & 0x01 != 0x00 {
+                Timing::Pal
+            } else {
+                Timing::Ntsc
+            },
+            console_type,
+            vs_system: None,
+            misc_roms: 0,
+            default_expansion_device: ExpansionDevice::Unspecified,
+        })
+    }
+
+    fn parse_nes_2_0(buf: &[u8], mirror: Mirror, battery: bool, trainer: bool) -> Result<Self> {
+        let flags_6 = buf[6];
+        let flags_7 = buf[7];
+
+        // Determine mapper id
+        //   0bxxxxCCCC (byte 8)
+        // | 0bBBBBxxxx (flags 7)
+        // | 0bxxxxAAAA (flags 6)
+        // = 0bCCCCBBBBAAAA
+        let mapper =
+            ((buf[8] as u16 & 0x0F) << 8) | (flags_7 & 0xF0) as u16 | (flags_6 >> 4) as u16;
+        let submapper = buf[8] >> 4;
+
+        let prg_rom_size = Self::rom_size(buf[4], buf[9] & 0x0F, PROGRAM_ROM_UNIT)?;
+        let chr_rom_size = Self::rom_size(buf[5], buf[9] >> 4, CHARACTER_ROM_UNIT)?;
+
+        let console_type = match flags_7 & 0x03 {
+            0x00 => ConsoleType::Nes,
+            0x01 => ConsoleType::VsSystem,
+            0x02 => ConsoleType::Playchoice10,
+            _ => ConsoleType::Extended(buf[13] & 0x0F),
+        };
+
+        let vs_system = match console_type {
+            ConsoleType::VsSystem => Some(VsSystemType {
+                ppu:      VsPpuType::from(buf[13] & 0x0F),
+                hardware: VsHardwareType::from(buf[13] >> 4),
+            }),
+            _ => None,
+        };
+
+        Ok(Self {
+            format: HeaderFormat::Nes20,
+            mapper,
+            submapper,
+            prg_rom_size,
+            chr_rom_size,
+            prg_ram_size: Self::ram_size(buf[10] & 0x0F),
+            prg_nvram_size: Self::ram_size(buf[10] >> 4),
+            chr_ram_size: Self::ram_size(buf[11] & 0x0F),
+            chr_nvram_size: Self::ram_size(buf[11] >> 4),
+            mirror,
+            mirror_bit: flags_6 & 0x01 != 0x00,
+            battery,
+            trainer,
+            // NES 2.0 tells bus conflicts apart by the submapper
+            bus_conflicts: false,
+            timing: match buf[12] & 0x03 {
+                0x00 => Timing::Ntsc,
+                0x01 => Timing::Pal,
+                0x02 => Timing::Multi,
+                _ => Timing::Dendy,
+            },
+            console_type,
+            vs_system,
+            misc_roms: buf[14] & 0x03,
+            default_expansion_device: ExpansionDevice::from(buf[15] & 0x3F),
+        })
+    }
+
+    // Size of PRG or CHR ROM in bytes from its LSB byte and MSB nybble
+    fn rom_size(lsb: u8, msb: u8, unit: usize) -> Result<usize> {
+        if msb == 0x0F {
+            // Exponent-multiplier notation
+            let exponent = (lsb >> 2) as u32;
+            let multiplier = (lsb & 0x03) as usize * 2 + 1;
+            let size = 1usize
+                .checked_shl(exponent)
+                .and_then(|v| v.checked_mul(multiplier));
+
+            match size {
+                Some(size) => Ok(size),
+                None => errors::InvalidSize {
+                    detail: format!("rom size overflow, 2^{} * {}", exponent, multiplier),
+                }
+                .fail(),
+            }
+        } else {
+            Ok((((msb as usize) << 8) | lsb as usize) * unit)
+        }
+    }
+
+    // Size of RAM in bytes from its shift count
+    fn ram_size(shift: u8) -> usize {
+        match shift {
+            0 => 0,
+            _ => 64 << shift,
+        }
+    }
+
+    pub fn is_nes_2_0(&self) -> bool {
+        self.format == HeaderFormat::Nes20
+    }
+
+    pub fn has_chr_ram(&self) -> bool {
+        self.chr_rom_size == 0
+    }
+}
+
+impl From<u8> for VsPpuType {
+    fn from(v: u8) -> Self {
+        match v {
+            0x00 => VsPpuType::Rp2c03b,
+            0x01 => VsPpuType::Rp2c03g,
+            0x02 => VsPpuType::Rp2c04_0001,
+            0x03 => VsPpuType::Rp2c04_0002,
+            0x04 => VsPpuType::Rp2c04_0003,
+            0x05 => VsPpuType::Rp2c04_0004,
+            0x06 => VsPpuType::Rc2c03b,
+            0x07 => VsPpuType::Rc2c03c,
+            0x08 => VsPpuType::Rc2c05_01,
+            0x09 => VsPpuType::Rc2c05_02,
+            0x0A => VsPpuType::Rc2c05_03,
+            0x0B => VsPpuType::Rc2c05_04,
+            0x0C => VsPpuType::Rc2c05_05,
+            _ => VsPpuType::Unknown(v),
+        }
+    }
+}
+
+impl From<u8> for VsHardwareType {
+    fn from(v: u8) -> Self {
+        match v {
+            0x00 => VsHardwareType::Unisystem,
+            0x01 => VsHardwareType::UnisystemRbiBaseball,
+            0x02 => VsHardwareType::UnisystemTkoBoxing,
+            0x03 => VsHardwareType::UnisystemSuperXevious,
+            0x04 => VsHardwareType::UnisystemIceClimberJapan,
+            0x05 => VsHardwareType::DualSystem,
+            0x06 => VsHardwareType::DualSystemRaidOnBungelingBay,
+            _ => VsHardwareType::Unknown(v),
+        }
+    }
+}
+
+impl From<u8> for ExpansionDevice {
+    fn from(v: u8) -> Self {
+        match v {
+            0x00 => ExpansionDevice::Unspecified,
+            0x01 => ExpansionDevice::StandardControllers,
+            0x02 => ExpansionDevice::FourScore,
+            0x03 => ExpansionDevice::FamicomFourPlayersAdapter,
+            0x04 => ExpansionDevice::VsSystem,
+            0x05 => ExpansionDevice::VsSystemReversed,
+            0x06 => ExpansionDevice::VsPinball,
+            0x07 => ExpansionDevice::VsZapper,
+            0x08 => ExpansionDevice::Zapper,
+            0x09 => ExpansionDevice::TwoZappers,
+            0x0A => ExpansionDevice::BandaiHyperShot,
+            0x0B => ExpansionDevice::PowerPadSideA,
+            0x0C => ExpansionDevice::PowerPadSideB,
+            0x0D => ExpansionDevice::FamilyTrainerSideA,
+            0x0E => ExpansionDevice::FamilyTrainerSideB,
+            0x0F => ExpansionDevice::ArkanoidVausNes,
+            0x10 => ExpansionDevice::ArkanoidVausFamicom,
+            0x11 => ExpansionDevice::TwoVausAndDataRecorder,
+            0x12 => ExpansionDevice::KonamiHyperShot,
+            _ => ExpansionDevice::Other(v),
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use crate::prelude::*;
+
+// IPS patches
+//
+// "PATCH", then records of a 3 byte offset and a 2 byte size followed by
+// the data, all big endian. A size of zero marks a run: a 2 byte count
+// and the byte to repeat. "EOF" ends the patch
+const IPS_MAGIC: &[u8] = b"PATCH";
+const IPS_EOF: &[u8] = b"EOF";
+const MAX_RECORD_SIZE: usize = 0xFFFF;
+// Offsets are limited to 24 bits
+const MAX_OFFSET: usize = 0xFFFFFF;
+
+// Records of the bytes that differ, `modified` is as long as `original`
+pub fn make_ips(original: &[u8], modified: &[u8]) -> Vec<u8> {
+    let mut patch = IPS_MAGIC.to_vec();
+
+    let mut i = 0;
+    while i < modified.len().min(MAX_OFFSET) {
+        if original.get(i) == Some(&modified[i]) {
+            i += 1;
+            continue;
+        }
+
+        // An offset reading as "EOF" would end the patch early,
+        // the record starts one byte before instead
+        let start = if i == 0x454F46 { i - 1 } else { i };
+        let mut end = i + 1;
+        while end < modified.len() && end - start < MAX_RECORD_SIZE {
+            if original.get(end) == Some(&modified[end]) {
+                break;
+            }
+            end += 1;
+        }
+
+        patch.extend_from_slice(&(start as u32).to_be_bytes()[1..]);
+        patch.extend_from_slice(&((end - start) as u16).to_be_bytes());
+        patch.extend_from_slice(&modified[start..end]);
+        i = end;
+    }
+
+    patch.extend_from_slice(IPS_EOF);
+    patch
+}
+
+pub fn apply_ips(data: &mut Vec<u8>, patch: &[u8]) -> Result<()> {
+    if !patch.starts_with(IPS_MAGIC) {
+        return errors::InvalidPatch {
+            detail: "missing PATCH magic".to_string(),
+        }
+        .fail();
+    }
+
+    let truncated = || {
+        errors::InvalidPatch {
+            detail: "truncated record".to_string(),
+        }
+        .fail()
+    };
+
+    let mut i = IPS_MAGIC.len();
+    loop {
+        let record = match patch.get(i..i + 3) {
+            Some(IPS_EOF) => return Ok(()),
+            Some(r) => r,
+            None => return truncated(),
+        };
+        let offset = (record[0] as usize) << 16 | (record[1] as usize) << 8 | record[2] as usize;
+
+        let size = match patch.get(i + 3..i + 5) {
+            Some(s) => (s[0] as usize) << 8 | s[1] as usize,
+            None => return truncated(),
+        };
+        i += 5;
+
+        let (bytes, len) = if size == 0 {
+            match patch.get(i..i + 3) {
+                Some(r) => (vec![r[2]; (r[0] as usize) << 8 | r[1] as usize], 3),
+                None => return truncated(),
+            }
+        } else {
+            match patch.get(i..i + size) {
+                Some(r) => (r.to_vec(), size),
+                None => return truncated(),
+            }
+        };
+        i += len;
+
+        let end = offset + bytes.len();
+        if data.len() < end {
+            data.resize(end, 0);
+        }
+        data[offset..end].copy_from_slice(&bytes);
+    }
+}
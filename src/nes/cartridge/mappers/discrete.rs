@@ -0,0 +1,23 @@
+use crate::prelude::*;
+
+// Banking shared by the discrete logic boards. Such a board only latches
+// bank numbers, these turn a bank number and an address into an offset
+// into PRG or CHR memory. Bank numbers wrap around the size of the memory
+// and a memory smaller than the bank is mirrored
+
+// Offset into PRG ROM of `addr` in the `size` byte `bank`,
+// `prg_banks` is the count of 16 KB banks
+pub(super) fn map_prg(prg_banks: usize, size: usize, bank: usize, addr: Addr) -> usize {
+    map_bank(prg_banks.max(1) * 0x4000, size, bank, addr)
+}
+
+// Offset into CHR memory of `addr` in the `size` byte `bank`,
+// `chr_banks` is the count of 8 KB banks
+pub(super) fn map_chr(chr_banks: usize, size: usize, bank: usize, addr: Addr) -> usize {
+    map_bank(chr_banks.max(1) * 0x2000, size, bank, addr)
+}
+
+fn map_bank(total: usize, size: usize, bank: usize, addr: Addr) -> usize {
+    let count = (total / size).max(1);
+    (bank % count) * size + addr.as_usize() % size
+}
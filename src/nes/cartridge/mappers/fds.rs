@@ -0,0 +1,411 @@
+use super::mapper::{Mapped, Mapper};
+use crate::cartridge::{update_crc, DiskImage, Mirror, GAP_END};
+use crate::prelude::*;
+
+// CPU cycles the drive takes for a byte, about 96.4 kbit/s
+const BYTE_CYCLES: u32 = 150;
+// CPU cycles from the head reaching the start of the disk to the first byte
+const HEAD_RETURN_CYCLES: u32 = 50000;
+
+// Famicom Disk System RAM adapter
+//
+// The BIOS is mapped at $E000-$FFFF and 32 KB of PRG RAM below it, the
+// pattern tables are 8 KB of CHR RAM
+//
+// CPU Address Bus  Register
+// $4020            Timer IRQ reload, low 8 bits
+// $4021            Timer IRQ reload, high 8 bits
+// $4022            Timer IRQ control, copies the reload into the counter
+// 76543210
+//       ||
+//       |+- Repeat
+//       +-- Enable, only while disk registers are enabled
+// $4023            Master I/O enable
+// 76543210
+//       ||
+//       |+- Enable disk registers, disabling acknowledges the IRQs
+//       +-- Enable sound registers
+// $4024            Data to write, acknowledges the disk IRQ
+// $4025            Drive control, acknowledges the disk IRQ
+// 76543210
+// || ||||+- Turn on the motor
+// || |||+-- Reset transfer, the head stays at the start of the disk
+// || ||+--- Read (1) or write (0)
+// || |+---- Mirroring (0: vertical; 1: horizontal)
+// || +----- Write the CRC
+// |+------- Transfer the data, the drive waits for a gap end before it
+// +-------- Raise the disk IRQ once a byte is transferred
+// $4030            Status, acknowledges the IRQs
+// 76543210
+//    |  ||
+//    |  |+- Timer IRQ
+//    |  +-- Byte transferred
+//    +----- CRC error
+// $4031            Read data, acknowledges the disk IRQ
+// $4032            Drive status, a set bit means: no disk (bit 0), disk
+//                  not ready (bit 1), write protected (bit 2)
+// $4033            External connector, bit 7 is set for a good battery
+//
+// The timer counts down every CPU cycle while enabled. Below zero it raises
+// the IRQ, reloads, and stops unless repeat is set. The drive moves the head
+// over the disk one byte at a time while the motor is on and turns the
+// motor off at the end of the side. Sound registers are not emulated
+pub struct Fds {
+    image: DiskImage,
+    // Sides as the drive sees them, with gaps and CRCs
+    sides: Vec<Vec<u8>>,
+    side:  Option<usize>,
+
+    disk_enabled: bool,
+    mirror:       Mirror,
+
+    irq_reload:  u16,
+    irq_counter: u16,
+    irq_repeat:  bool,
+    irq_enabled: bool,
+    timer_irq:   bool,
+
+    motor_on:          bool,
+    reset_transfer:    bool,
+    read_mode:         bool,
+    crc_control:       bool,
+    transfer:          bool,
+    disk_irq_enabled:  bool,
+    disk_irq:          bool,
+    transfer_complete: bool,
+    read_data:         u8,
+    write_data:        u8,
+
+    position:     usize,
+    delay:        u32,
+    end_of_head:  bool,
+    scanning:     bool,
+    gap_ended:    bool,
+    previous_crc: bool,
+    crc:          u16,
+}
+
+impl Mapper for Fds {
+    // CPU Address Bus          PRG RAM
+    // 0x6000 -> 0xDFFF: Map    0x0000 -> 0x7FFF
+    // CPU Address Bus          BIOS
+    // 0xE000 -> 0xFFFF: Map    0x0000 -> 0x1FFF
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x4030..=0x4033) if self.disk_enabled => {
+                Mapped::Data(Byte(self.read_register(addr)))
+            }
+            Addr(0x6000..=0xDFFF) => Mapped::PrgRam((addr - Addr(0x6000)).as_usize()),
+            Addr(0xE000..=0xFFFF) => Mapped::Prg((addr & Addr(0x1FFF)).as_usize()),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        match addr {
+            Addr(0x4020..=0x4026) => {
+                self.write_register(addr, v.0);
+                Mapped::Data(v)
+            }
+            Addr(0x6000..=0xDFFF) => Mapped::PrgRam((addr - Addr(0x6000)).as_usize()),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // PPU Address Bus          CHR RAM
+    // 0x0000 -> 0x1FFF: Map    0x0000 -> 0x1FFF
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) => Mapped::Chr(addr.as_usize()),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
+    }
+
+    fn cpu_tick(&mut self) {
+        self.clock_timer();
+        self.clock_drive();
+    }
+
+    fn mirror(&self) -> Mirror {
+        self.mirror
+    }
+
+    fn has_irq(&self) -> bool {
+        self.timer_irq || self.disk_irq
+    }
+
+    fn clear_irq(&mut self) {
+        self.timer_irq = false;
+        self.disk_irq = false;
+    }
+
+    fn disk_sides(&self) -> usize {
+        self.sides.len()
+    }
+
+    fn disk_side(&self) -> Option<usize> {
+        self.side
+    }
+
+    fn insert_disk(&mut self, side: Option<usize>) {
+        self.side = side.filter(|s| *s < self.sides.len());
+    }
+
+    fn disk_image(&self) -> Option<Vec<u8>> {
+        let mut image = self.image.clone();
+        for (i, raw) in self.sides.iter().enumerate() {
+            image.set_raw_side(i, raw);
+        }
+        Some(image.to_bytes())
+    }
+
+    // The disk stays in the drive
+    fn reset(&mut self) {
+        self.disk_enabled = false;
+        self.mirror = Mirror::Vertical;
+
+        self.irq_reload = 0;
+        self.irq_counter = 0;
+        self.irq_repeat = false;
+        self.irq_enabled = false;
+        self.timer_irq = false;
+
+        self.motor_on = false;
+        self.reset_transfer = false;
+        self.read_mode = false;
+        self.crc_control = false;
+        self.transfer = false;
+        self.disk_irq_enabled = false;
+        self.disk_irq = false;
+        self.transfer_complete = false;
+        self.read_data = 0;
+        self.write_data = 0;
+
+        self.position = 0;
+        self.delay = 0;
+        self.end_of_head = true;
+        self.scanning = false;
+        self.gap_ended = false;
+        self.previous_crc = false;
+        self.crc = 0;
+    }
+}
+
+impl Fds {
+    // Side A of the first disk is inserted
+    pub fn new(image: DiskImage) -> Self {
+        let sides = (0..image.sides()).map(|i| image.raw_side(i)).collect();
+        let mut s = Self {
+            image,
+            sides,
+            side: Some(0),
+            disk_enabled: false,
+            mirror: Mirror::Vertical,
+            irq_reload: 0,
+            irq_counter: 0,
+            irq_repeat: false,
+            irq_enabled: false,
+            timer_irq: false,
+            motor_on: false,
+            reset_transfer: false,
+            read_mode: false,
+            crc_control: false,
+            transfer: false,
+            disk_irq_enabled: false,
+            disk_irq: false,
+            transfer_complete: false,
+            read_data: 0,
+            write_data: 0,
+            position: 0,
+            delay: 0,
+            end_of_head: true,
+            scanning: false,
+            gap_ended: false,
+            previous_crc: false,
+            crc: 0,
+        };
+        s.reset();
+        s
+    }
+
+    fn read_register(&mut self, addr: Addr) -> u8 {
+        match addr {
+            Addr(0x4030) => {
+                let v = self.timer_irq as u8 | (self.transfer_complete as u8) << 1;
+                self.transfer_complete = false;
+                self.timer_irq = false;
+                self.disk_irq = false;
+                v
+            }
+            Addr(0x4031) => {
+                self.transfer_complete = false;
+                self.disk_irq = false;
+                self.read_data
+            }
+            Addr(0x4032) => {
+                let inserted = self.side.is_some();
+                let v = !inserted as u8 | ((!inserted || !self.scanning) as u8) << 1;
+                // Swapped disks are not protected, bit 6 is an open bus
+                v | (!inserted as u8) << 2 | 0x40
+            }
+            _ => 0x80,
+        }
+    }
+
+    fn write_register(&mut self, addr: Addr, v: u8) {
+        match addr {
+            Addr(0x4020) => self.irq_reload = (self.irq_reload & 0xFF00) | v as u16,
+            Addr(0x4021) => self.irq_reload = (self.irq_reload & 0x00FF) | (v as u16) << 8,
+            Addr(0x4022) => {
+                self.irq_repeat = v & 0x01 != 0;
+                self.irq_enabled = v & 0x02 != 0 && self.disk_enabled;
+                if self.irq_enabled {
+                    self.irq_counter = self.irq_reload;
+                } else {
+                    self.timer_irq = false;
+                }
+            }
+            Addr(0x4023) => {
+                self.disk_enabled = v & 0x01 != 0;
+                if !self.disk_enabled {
+                    self.irq_enabled = false;
+                    self.timer_irq = false;
+                    self.disk_irq = false;
+                }
+            }
+            Addr(0x4024) if self.disk_enabled => {
+                self.write_data = v;
+                self.transfer_complete = false;
+                self.disk_irq = false;
+            }
+            Addr(0x4025) if self.disk_enabled => {
+                self.motor_on = v & 0x01 != 0;
+                self.reset_transfer = v & 0x02 != 0;
+                self.read_mode = v & 0x04 != 0;
+                self.mirror = if v & 0x08 != 0 {
+                    Mirror::Horizontal
+                } else {
+                    Mirror::Vertical
+                };
+                self.crc_control = v & 0x10 != 0;
+                self.transfer = v & 0x40 != 0;
+                self.disk_irq_enabled = v & 0x80 != 0;
+                self.disk_irq = false;
+            }
+            _ => {}
+        }
+    }
+
+    fn clock_timer(&mut self) {
+        if !self.irq_enabled {
+            return;
+        }
+
+        if self.irq_counter == 0 {
+            self.timer_irq = true;
+            self.irq_counter = self.irq_reload;
+            self.irq_enabled = self.irq_repeat;
+        } else {
+            self.irq_counter -= 1;
+        }
+    }
+
+    fn clock_drive(&mut self) {
+        let side = match self.side {
+            Some(side) if self.motor_on => side,
+            _ => {
+                self.end_of_head = true;
+                self.scanning = false;
+                return;
+            }
+        };
+
+        if self.reset_transfer && !self.scanning {
+            return;
+        }
+
+        if self.end_of_head {
+            self.delay = HEAD_RETURN_CYCLES;
+            self.end_of_head = false;
+            self.position = 0;
+            self.gap_ended = false;
+            return;
+        }
+
+        if self.delay > 0 {
+            self.delay -= 1;
+            return;
+        }
+
+        self.scanning = true;
+        if self.read_mode {
+            self.read_byte(side);
+        } else {
+            self.write_byte(side);
+        }
+        self.previous_crc = self.crc_control;
+
+        self.position += 1;
+        if self.position >= self.sides[side].len() {
+            self.motor_on = false;
+        } else {
+            self.delay = BYTE_CYCLES;
+        }
+    }
+
+    // Bytes are passed on from the first gap end mark after the
+    // transfer was enabled, which itself is skipped
+    fn read_byte(&mut self, side: usize) {
+        let v = self.sides[side][self.position];
+
+        let mut irq = self.disk_irq_enabled;
+        if !self.transfer {
+            self.gap_ended = false;
+        } else if v == GAP_END && !self.gap_ended {
+            self.gap_ended = true;
+            irq = false;
+        }
+
+        if self.gap_ended {
+            self.transfer_complete = true;
+            self.read_data = v;
+            if irq {
+                self.disk_irq = true;
+            }
+        }
+    }
+
+    // Without transfer the drive writes the gap, with CRC control it
+    // writes the CRC of everything since the gap ended
+    fn write_byte(&mut self, side: usize) {
+        let mut v = self.write_data;
+        if !self.crc_control {
+            self.transfer_complete = true;
+            if self.disk_irq_enabled {
+                self.disk_irq = true;
+            }
+        }
+
+        if !self.transfer {
+            v = 0x00;
+            self.crc = 0;
+        }
+
+        if !self.crc_control {
+            self.crc = update_crc(self.crc, v);
+        } else {
+            if !self.previous_crc {
+                self.crc = update_crc(update_crc(self.crc, 0x00), 0x00);
+            }
+            v = self.crc as u8;
+            self.crc >>= 8;
+        }
+
+        self.sides[side][self.position] = v;
+        self.gap_ended = false;
+    }
+}
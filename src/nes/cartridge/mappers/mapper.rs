@@ -1,17 +1,101 @@
 use crate::cartridge::Mirror;
 use crate::prelude::*;
 
+// Where a mapper has routed a bus access
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mapped {
+    // Offset into PRG ROM
+    Prg(usize),
+    // Offset into PRG RAM
+    PrgRam(usize),
+    // Offset into PRG ROM on a flash chip the game can reprogram
+    Flash(usize),
+    // Offset into CHR ROM or RAM
+    Chr(usize),
+    // Offset into additional VRAM on the cartridge
+    Vram(usize),
+    // Offset into the 2 KB of VRAM inside the console (CIRAM)
+    CiRam(usize),
+    // The mapper has supplied the value on read or has taken it on write
+    Data(Byte),
+    // Nothing responds at the address
+    Unmapped,
+}
+
+// What the PPU is fetching from the cartridge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PpuFetch {
+    // Nametables, attributes and patterns of the background
+    Background,
+    // Patterns of sprites for the next scanline
+    Sprites,
+    // Rendering is disabled or the frame is over, so the bus
+    // is only driven through PPUDATA
+    Idle,
+}
+
 pub trait Mapper {
-    fn map_read(&mut self, addr: Addr, mapped_addr: &mut ExtAddr, v: &mut Byte) -> bool;
-    fn map_write(&mut self, addr: Addr, mapped_addr: &mut ExtAddr, v: Byte) -> bool;
-    fn map_read_chr(&mut self, addr: Addr, mapped_addr: &mut ExtAddr) -> bool;
-    fn map_write_chr(&mut self, addr: Addr, mapped_addr: &mut ExtAddr) -> bool;
+    // CPU Address Bus $4020-$FFFF
+    fn read(&mut self, addr: Addr) -> Mapped;
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped;
+    // PPU Address Bus $0000-$1FFF
+    fn read_chr(&mut self, addr: Addr) -> Mapped;
+    fn write_chr(&mut self, addr: Addr, v: Byte) -> Mapped;
+    // PPU Address Bus $2000-$2FFF. Unmapped leaves the nametables
+    // to the console VRAM arranged by `mirror`
+    fn read_nametable(&mut self, _addr: Addr) -> Mapped {
+        Mapped::Unmapped
+    }
+    fn write_nametable(&mut self, _addr: Addr, _v: Byte) -> Mapped {
+        Mapped::Unmapped
+    }
+    // Observes every address the PPU puts on its bus, whether it is
+    // a rendering fetch or an access through PPUDATA. It is called
+    // once the access itself is done
+    fn ppu_bus(&mut self, _addr: Addr) {}
+    // Called whenever the PPU switches to another kind of fetches,
+    // `tall_sprites` is set in 8x16 sprite mode
+    fn ppu_fetch(&mut self, _fetch: PpuFetch, _tall_sprites: bool) {}
+    // Called once per CPU cycle
+    fn cpu_tick(&mut self) {}
+    // Current level of the expansion audio of the cartridge, on the scale
+    // of the console's own channels where a full volume pulse wave is
+    // about 0.11. It is sampled once per CPU cycle after `cpu_tick`
+    fn audio(&self) -> f32 {
+        0.0
+    }
     fn mirror(&self) -> Mirror {
         Mirror::Hardware
     }
+    // Whether the board has bus conflicts when the header does not say so,
+    // a register write then reads as the value ANDed with the ROM byte
+    fn bus_conflicts(&self) -> bool {
+        false
+    }
+    // Memory inside the mapper kept by the battery, like the internal RAM
+    // of Namco 163. It is stored in the save file right after PRG RAM
+    fn battery_ram(&self) -> &[u8] {
+        &[]
+    }
+    fn load_battery_ram(&mut self, _data: &[u8]) {}
+    // Disk drive of the Famicom Disk System. Sides are numbered in the
+    // order of the image, `None` is an empty drive
+    fn disk_sides(&self) -> usize {
+        0
+    }
+    fn disk_side(&self) -> Option<usize> {
+        None
+    }
+    fn insert_disk(&mut self, _side: Option<usize>) {}
+    // Contents of all disk sides in the format of the loaded image
+    fn disk_image(&self) -> Option<Vec<u8>> {
+        None
+    }
     fn has_irq(&self) -> bool {
         false
     }
     fn clear_irq(&mut self) {}
+    // Called at the end of each visible scanline while rendering is enabled
     fn scanline(&mut self) {}
+    fn reset(&mut self) {}
 }
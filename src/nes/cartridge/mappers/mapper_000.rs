@@ -1,12 +1,13 @@
-use super::mapper::Mapper;
+use super::mapper::{Mapped, Mapper};
 use crate::prelude::*;
 
 pub struct Mapper000 {
     prg_banks: usize,
-    chr_banks: usize,
 }
 
 impl Mapper for Mapper000 {
+    // CPU Address Bus          PRG RAM
+    // 0x6000 -> 0x7FFF: Map    0x0000 -> 0x1FFF
     // if PRGROM is 16KB
     //     CPU Address Bus          PRG ROM
     //     0x8000 -> 0xBFFF: Map    0x0000 -> 0x3FFF
@@ -14,76 +15,44 @@ impl Mapper for Mapper000 {
     // if PRGROM is 32KB
     //     CPU Address Bus          PRG ROM
     //     0x8000 -> 0xFFFF: Map    0x0000 -> 0x7FFF
-    fn map_read(&mut self, addr: Addr, mapped_addr: &mut ExtAddr, v: &mut Byte) -> bool {
+    fn read(&mut self, addr: Addr) -> Mapped {
         match addr {
-            Addr(0x8000..=0xFFFF) => {
-                *mapped_addr = if self.prg_banks > 1 {
-                    (addr & Addr(0x7FFF)).as_lo_ext_addr()
-                } else {
-                    (addr & Addr(0x3FFF)).as_lo_ext_addr()
-                };
-                true
-            }
-            //Addr(0x6000..=0x7FFF) => (addr.as_lo_ext_addr(), Byte(0)),
-            _ => false,
+            Addr(0x6000..=0x7FFF) => Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize()),
+            Addr(0x8000..=0xFFFF) => Mapped::Prg(if self.prg_banks > 1 {
+                (addr & Addr(0x7FFF)).as_usize()
+            } else {
+                (addr & Addr(0x3FFF)).as_usize()
+            }),
+            _ => Mapped::Unmapped,
         }
     }
 
-    // if PRGROM is 16KB
-    //     CPU Address Bus          PRG ROM
-    //     0x8000 -> 0xBFFF: Map    0x0000 -> 0x3FFF
-    //     0xC000 -> 0xFFFF: Mirror 0x0000 -> 0x3FFF
-    // if PRGROM is 32KB
-    //     CPU Address Bus          PRG ROM
-    //     0x8000 -> 0xFFFF: Map    0x0000 -> 0x7FFF
-    fn map_write(&mut self, addr: Addr, mapped_addr: &mut ExtAddr, v: Byte) -> bool {
+    // PRG ROM is not writable
+    fn write(&mut self, addr: Addr, _v: Byte) -> Mapped {
         match addr {
-            Addr(0x8000..=0xFFFF) => {
-                *mapped_addr = if self.prg_banks > 1 {
-                    (addr & Addr(0x7FFF)).as_lo_ext_addr()
-                } else {
-                    (addr & Addr(0x3FFF)).as_lo_ext_addr()
-                };
-                true
-            }
-            //Addr(0x6000..=0x7FFF) => (addr.as_lo_ext_addr(), Byte(0)),
-            _ => false,
+            Addr(0x6000..=0x7FFF) => Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize()),
+            _ => Mapped::Unmapped,
         }
     }
 
     // There is no mapping required for PPU
     // PPU Address Bus          CHR ROM
     // 0x0000 -> 0x1FFF: Map    0x0000 -> 0x1FFF
-    fn map_read_chr(&mut self, addr: Addr, mapped_addr: &mut ExtAddr) -> bool {
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
         match addr {
-            Addr(0x0000..=0x1FFF) => {
-                *mapped_addr = addr.as_lo_ext_addr();
-                true
-            }
-            _ => false,
+            Addr(0x0000..=0x1FFF) => Mapped::Chr(addr.as_usize()),
+            _ => Mapped::Unmapped,
         }
     }
 
-    // There is no mapping required for PPU
-    // PPU Address Bus          CHR ROM
-    // 0x0000 -> 0x1FFF: Map    0x0000 -> 0x1FFF
-    fn map_write_chr(&mut self, addr: Addr, mapped_addr: &mut ExtAddr) -> bool {
-        match addr {
-            // Treat as RAM
-            Addr(0x0000..=0x1FFF) if self.chr_banks == 0 => {
-                *mapped_addr = addr.as_lo_ext_addr();
-                true
-            }
-            _ => false,
-        }
+    // Writes only land when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
     }
 }
 
 impl Mapper000 {
-    pub fn new(prg_banks: usize, chr_banks: usize) -> Self {
-        Self {
-            prg_banks,
-            chr_banks,
-        }
+    pub fn new(prg_banks: usize, _chr_banks: usize) -> Self {
+        Self { prg_banks }
     }
 }
@@ -0,0 +1,195 @@
+use super::mapper::{Mapped, Mapper};
+use crate::cartridge::Mirror;
+use crate::prelude::*;
+
+// MMC1 (SxROM)
+//
+// The registers are loaded through a serial port. Each write to $8000-$FFFF
+// with bit 7 clear shifts bit 0 into a 5-bit shift register, on the fifth
+// write the value is copied into the internal register selected by bits 13
+// and 14 of the address of that last write:
+//     $8000-$9FFF: Control
+//     $A000-$BFFF: CHR bank 0
+//     $C000-$DFFF: CHR bank 1
+//     $E000-$FFFF: PRG bank
+// A write with bit 7 set clears the shift register and locks the PRG ROM
+// bank mode to 3 (fix last bank at $C000).
+//
+// Control register
+// 43210
+// |||||
+// |||++- Mirroring (0: one-screen, lower bank; 1: one-screen, upper bank;
+// |||               2: vertical; 3: horizontal)
+// |++--- PRG ROM bank mode (0, 1: switch 32 KB at $8000, ignoring low bit of bank number;
+// |                         2: fix first bank at $8000 and switch 16 KB bank at $C000;
+// |                         3: fix last bank at $C000 and switch 16 KB bank at $8000)
+// +----- CHR ROM bank mode (0: switch 8 KB at a time; 1: switch two separate 4 KB banks)
+pub struct Mapper001 {
+    prg_banks: usize,
+    chr_banks: usize,
+
+    shift:       Byte,
+    shift_count: u8,
+
+    control:    Byte,
+    chr_bank_0: Byte,
+    chr_bank_1: Byte,
+    prg_bank:   Byte,
+}
+
+impl Mapper for Mapper001 {
+    // CPU Address Bus          PRG RAM
+    // 0x6000 -> 0x7FFF: Map    8 KB PRG RAM bank
+    // CPU Address Bus          PRG ROM
+    // 0x8000 -> 0xBFFF: Map    16 KB PRG ROM bank, either switchable or fixed to the first bank
+    // 0xC000 -> 0xFFFF: Map    16 KB PRG ROM bank, either fixed to the last bank or switchable
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) if self.ram_enabled() => {
+                Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize())
+            }
+            Addr(0x8000..=0xFFFF) => Mapped::Prg(self.map_prg(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) if self.ram_enabled() => {
+                Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize())
+            }
+            Addr(0x8000..=0xFFFF) => {
+                self.write_serial(addr, v);
+                // Registers are not backed by memory, so the write
+                // must not reach PRG ROM
+                Mapped::Data(v)
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // PPU Address Bus          CHR ROM
+    // 0x0000 -> 0x0FFF: Map    4 KB CHR bank 0 (or lower half of 8 KB bank)
+    // 0x1000 -> 0x1FFF: Map    4 KB CHR bank 1 (or upper half of 8 KB bank)
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) => Mapped::Chr(self.map_chr(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
+    }
+
+    fn mirror(&self) -> Mirror {
+        match self.control.0 & 0x03 {
+            0 => Mirror::OneScreenLo,
+            1 => Mirror::OneScreenHi,
+            2 => Mirror::Vertical,
+            _ => Mirror::Horizontal,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.shift = Byte(0x00);
+        self.shift_count = 0;
+        self.control = Byte(0x1C);
+        self.chr_bank_0 = Byte(0x00);
+        self.chr_bank_1 = Byte(0x00);
+        self.prg_bank = Byte(0x00);
+    }
+}
+
+impl Mapper001 {
+    pub fn new(prg_banks: usize, chr_banks: usize) -> Self {
+        let mut s = Self {
+            prg_banks,
+            chr_banks,
+            shift: Byte(0x00),
+            shift_count: 0,
+            control: Byte(0x00),
+            chr_bank_0: Byte(0x00),
+            chr_bank_1: Byte(0x00),
+            prg_bank: Byte(0x00),
+        };
+        s.reset();
+        s
+    }
+
+    fn ram_enabled(&self) -> bool {
+        // Bit 4 of the PRG bank register disables PRG RAM on MMC1B and later
+        self.prg_bank & Byte(0x10) == Byte(0x00)
+    }
+
+    fn write_serial(&mut self, addr: Addr, v: Byte) {
+        if v & Byte(0x80) != Byte(0x00) {
+            // MSB is set, so reset serial loading
+            self.shift = Byte(0x00);
+            self.shift_count = 0;
+            self.control |= Byte(0x0C);
+            return;
+        }
+
+        // Load data in serially into shift register. It arrives LSB
+        // first, so the first bit ends up at bit 0 after five writes
+        self.shift >>= 1;
+        self.shift |= (v & Byte(0x01)) << 4;
+        self.shift_count += 1;
+
+        if self.shift_count == 5 {
+            // Get mapper target register, by examining bits 13 & 14 of the address
+            match (addr.0 >> 13) & 0x03 {
+                0 => self.control = self.shift & Byte(0x1F),
+                1 => self.chr_bank_0 = self.shift & Byte(0x1F),
+                2 => self.chr_bank_1 = self.shift & Byte(0x1F),
+                _ => self.prg_bank = self.shift & Byte(0x1F),
+            }
+
+            // 5 bits were written, and decoded, so reset serial loading
+            self.shift = Byte(0x00);
+            self.shift_count = 0;
+        }
+    }
+
+    fn map_prg(&self, addr: Addr) -> usize {
+        let prg_banks = self.prg_banks.max(1);
+        let bank = (self.prg_bank & Byte(0x0F)).0 as usize;
+
+        let bank = match (self.control.0 >> 2) & 0x03 {
+            // 32K mode, the low bit of the bank number is ignored
+            0 | 1 => (bank & 0x0E) | ((addr.0 as usize >> 14) & 0x01),
+            // Fix first bank at $8000, switch 16K bank at $C000
+            2 => match addr {
+                Addr(0x8000..=0xBFFF) => 0,
+                _ => bank,
+            },
+            // Fix last bank at $C000, switch 16K bank at $8000
+            _ => match addr {
+                Addr(0x8000..=0xBFFF) => bank,
+                _ => prg_banks - 1,
+            },
+        };
+
+        (bank % prg_banks) * 0x4000 + (addr & Addr(0x3FFF)).as_usize()
+    }
+
+    fn map_chr(&self, addr: Addr) -> usize {
+        // Count of 4K banks
+        let chr_banks = self.chr_banks.max(1) * 2;
+
+        let bank = if self.control & Byte(0x10) != Byte(0x00) {
+            // 4K mode, two separate banks
+            match addr {
+                Addr(0x0000..=0x0FFF) => self.chr_bank_0.0 as usize,
+                _ => self.chr_bank_1.0 as usize,
+            }
+        } else {
+            // 8K mode, the low bit of the bank number is ignored
+            (self.chr_bank_0.0 as usize & 0x1E) | ((addr.0 as usize >> 12) & 0x01)
+        };
+
+        (bank % chr_banks) * 0x1000 + (addr & Addr(0x0FFF)).as_usize()
+    }
+}
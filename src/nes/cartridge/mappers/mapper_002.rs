@@ -0,0 +1,87 @@
+use super::mapper::{Mapped, Mapper};
+use crate::prelude::*;
+
+// UxROM
+//
+// CPU Address Bus  Register
+// $8000-$FFFF      Bank select
+//
+// Bank select
+// 76543210
+// ||||||||
+// ++++++++- Select 16 KB PRG ROM bank for CPU $8000-$BFFF
+pub struct Mapper002 {
+    prg_banks: usize,
+
+    prg_bank_lo: usize,
+}
+
+impl Mapper for Mapper002 {
+    // CPU Address Bus          PRG RAM
+    // 0x6000 -> 0x7FFF: Map    0x0000 -> 0x1FFF
+    // CPU Address Bus          PRG ROM
+    // 0x8000 -> 0xBFFF: Map    16 KB switchable bank
+    // 0xC000 -> 0xFFFF: Map    16 KB fixed to the last bank
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) => Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize()),
+            Addr(0x8000..=0xFFFF) => Mapped::Prg(self.map_prg(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) => Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize()),
+            Addr(0x8000..=0xFFFF) => {
+                self.prg_bank_lo = v.0 as usize;
+                // Register is not backed by memory, so the write
+                // must not reach PRG ROM
+                Mapped::Data(v)
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // There is no mapping required for PPU
+    // PPU Address Bus          CHR RAM
+    // 0x0000 -> 0x1FFF: Map    0x0000 -> 0x1FFF
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) => Mapped::Chr(addr.as_usize()),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
+    }
+
+    fn bus_conflicts(&self) -> bool {
+        true
+    }
+
+    fn reset(&mut self) {
+        self.prg_bank_lo = 0;
+    }
+}
+
+impl Mapper002 {
+    pub fn new(prg_banks: usize, _chr_banks: usize) -> Self {
+        Self {
+            prg_banks,
+            prg_bank_lo: 0,
+        }
+    }
+
+    fn map_prg(&self, addr: Addr) -> usize {
+        let prg_banks = self.prg_banks.max(1);
+        let bank = match addr {
+            Addr(0x8000..=0xBFFF) => self.prg_bank_lo,
+            _ => prg_banks - 1,
+        };
+
+        (bank % prg_banks) * 0x4000 + (addr & Addr(0x3FFF)).as_usize()
+    }
+}
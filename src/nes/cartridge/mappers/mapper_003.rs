@@ -0,0 +1,91 @@
+use super::mapper::{Mapped, Mapper};
+use crate::prelude::*;
+
+// CNROM
+//
+// CPU Address Bus  Register
+// $8000-$FFFF      Bank select
+//
+// Bank select
+// 76543210
+// ||||||||
+// ++++++++- Select 8 KB CHR ROM bank for PPU $0000-$1FFF
+pub struct Mapper003 {
+    prg_banks: usize,
+    chr_banks: usize,
+
+    chr_bank: usize,
+}
+
+impl Mapper for Mapper003 {
+    // CPU Address Bus          PRG RAM
+    // 0x6000 -> 0x7FFF: Map    0x0000 -> 0x1FFF
+    // if PRGROM is 16KB
+    //     CPU Address Bus          PRG ROM
+    //     0x8000 -> 0xBFFF: Map    0x0000 -> 0x3FFF
+    //     0xC000 -> 0xFFFF: Mirror 0x0000 -> 0x3FFF
+    // if PRGROM is 32KB
+    //     CPU Address Bus          PRG ROM
+    //     0x8000 -> 0xFFFF: Map    0x0000 -> 0x7FFF
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) => Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize()),
+            Addr(0x8000..=0xFFFF) => Mapped::Prg(if self.prg_banks > 1 {
+                (addr & Addr(0x7FFF)).as_usize()
+            } else {
+                (addr & Addr(0x3FFF)).as_usize()
+            }),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) => Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize()),
+            Addr(0x8000..=0xFFFF) => {
+                self.chr_bank = v.0 as usize;
+                // Register is not backed by memory, so the write
+                // must not reach PRG ROM
+                Mapped::Data(v)
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // PPU Address Bus          CHR ROM
+    // 0x0000 -> 0x1FFF: Map    8 KB switchable bank
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) => Mapped::Chr(self.map_chr(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
+    }
+
+    fn bus_conflicts(&self) -> bool {
+        true
+    }
+
+    fn reset(&mut self) {
+        self.chr_bank = 0;
+    }
+}
+
+impl Mapper003 {
+    pub fn new(prg_banks: usize, chr_banks: usize) -> Self {
+        Self {
+            prg_banks,
+            chr_banks,
+            chr_bank: 0,
+        }
+    }
+
+    fn map_chr(&self, addr: Addr) -> usize {
+        let chr_banks = self.chr_banks.max(1);
+        (self.chr_bank % chr_banks) * 0x2000 + (addr & Addr(0x1FFF)).as_usize()
+    }
+}
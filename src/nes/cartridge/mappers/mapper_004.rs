@@ -0,0 +1,418 @@
+use super::mapper::{Mapped, Mapper};
+use crate::cartridge::Mirror;
+use crate::prelude::*;
+
+// MMC3 (TxROM)
+//
+// CPU Address Bus  Register
+// $8000-$9FFE even Bank select
+// $8001-$9FFF odd  Bank data
+// $A000-$BFFE even Mirroring
+// $A001-$BFFF odd  PRG RAM protect
+// $C000-$DFFE even IRQ latch
+// $C001-$DFFF odd  IRQ reload
+// $E000-$FFFE even IRQ disable
+// $E001-$FFFF odd  IRQ enable
+//
+// Bank select
+// 76543210
+// ||   |||
+// ||   +++- Specify which bank register to update on next write to Bank Data register
+// ||        (0: 2 KB CHR bank at PPU $0000-$07FF (or $1000-$17FF);
+// ||         1: 2 KB CHR bank at PPU $0800-$0FFF (or $1800-$1FFF);
+// ||         2: 1 KB CHR bank at PPU $1000-$13FF (or $0000-$03FF);
+// ||         3: 1 KB CHR bank at PPU $1400-$17FF (or $0400-$07FF);
+// ||         4: 1 KB CHR bank at PPU $1800-$1BFF (or $0800-$0BFF);
+// ||         5: 1 KB CHR bank at PPU $1C00-$1FFF (or $0C00-$0FFF);
+// ||         6: 8 KB PRG ROM bank at $8000-$9FFF (or $C000-$DFFF);
+// ||         7: 8 KB PRG ROM bank at $A000-$BFFF)
+// |+------- PRG ROM bank mode (0: $8000-$9FFF swappable, $C000-$DFFF fixed to second-last bank;
+// |                            1: $C000-$DFFF swappable, $8000-$9FFF fixed to second-last bank)
+// +-------- CHR A12 inversion (0: two 2 KB banks at $0000-$0FFF, four 1 KB banks at $1000-$1FFF;
+//                              1: two 2 KB banks at $1000-$1FFF, four 1 KB banks at $0000-$0FFF)
+//
+// Boards built around the same registers
+//     Mapper  Board     Difference
+//     4       TxROM     -
+//     118     TxSROM    Bit 7 of the CHR bank selects the CIRAM page of the nametable
+//                       in the same 1 KB slot of $0000-$0FFF
+//     119     TQROM     Bit 6 of the CHR bank selects 8 KB of CHR RAM instead of ROM
+//     64      RAMBO-1   See below
+//
+// Tengen RAMBO-1 extends the bank select register
+// 76543210
+// |||| |||
+// |||| +++- Bank register 0-7 as above, 8-9 and $F are new
+// |||+----- Bank register bit 3
+// ||+------ 1 KB CHR mode (0: R0/R1 select 2 KB banks;
+// ||                       1: R0, R8, R1, R9 select 1 KB banks at $0000-$0FFF)
+// |+------- PRG ROM bank mode (0: R6, R7, RF at $8000, $A000, $C000;
+// |                            1: RF, R6, R7 at $8000, $A000, $C000)
+// +-------- CHR A12 inversion
+// $C001 also selects the IRQ counter source in bit 0 (0: scanlines;
+// 1: every 4 CPU cycles)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mmc3Board {
+    Txrom,
+    Txsrom,
+    Tqrom,
+    Rambo1,
+}
+
+impl Mmc3Board {
+    pub fn from_mapper(mapper: u16) -> Self {
+        match mapper {
+            118 => Mmc3Board::Txsrom,
+            119 => Mmc3Board::Tqrom,
+            64 => Mmc3Board::Rambo1,
+            _ => Mmc3Board::Txrom,
+        }
+    }
+}
+
+// CHR RAM of TQROM
+const TQROM_CHR_RAM_SIZE: usize = 0x2000;
+
+pub struct Mapper004 {
+    prg_banks: usize,
+    chr_banks: usize,
+    board:     Mmc3Board,
+
+    target_register: usize,
+    prg_bank_mode:   bool,
+    chr_inversion:   bool,
+    chr_1k_mode:     bool,
+    registers:       [Byte; 16],
+    mirror:          Mirror,
+    chr_ram:         Vec<u8>,
+
+    ram_enabled:   bool,
+    ram_protected: bool,
+
+    irq_active:  bool,
+    irq_enabled: bool,
+    irq_reload:  bool,
+    irq_counter: Byte,
+    irq_latch:   Byte,
+    irq_cycles:  bool,
+    prescaler:   u8,
+}
+
+impl Mapper for Mapper004 {
+    // CPU Address Bus          PRG RAM
+    // 0x6000 -> 0x7FFF: Map    8 KB PRG RAM bank
+    // CPU Address Bus          PRG ROM
+    // 0x8000 -> 0x9FFF: Map    8 KB switchable or fixed to second-last bank
+    // 0xA000 -> 0xBFFF: Map    8 KB switchable
+    // 0xC000 -> 0xDFFF: Map    8 KB fixed to second-last bank or switchable
+    // 0xE000 -> 0xFFFF: Map    8 KB fixed to last bank
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) if self.ram_enabled => {
+                Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize())
+            }
+            Addr(0x8000..=0xFFFF) => Mapped::Prg(self.map_prg(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        let even = addr & Addr(0x0001) == Addr(0x0000);
+
+        match addr {
+            Addr(0x6000..=0x7FFF) if self.ram_enabled && !self.ram_protected => {
+                return Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize());
+            }
+            Addr(0x8000..=0x9FFF) => {
+                if even {
+                    // Bank select
+                    self.prg_bank_mode = v & Byte(0x40) != Byte(0x00);
+                    self.chr_inversion = v & Byte(0x80) != Byte(0x00);
+                    if self.board == Mmc3Board::Rambo1 {
+                        self.target_register = (v & Byte(0x0F)).0 as usize;
+                        self.chr_1k_mode = v & Byte(0x20) != Byte(0x00);
+                    } else {
+                        self.target_register = (v & Byte(0x07)).0 as usize;
+                    }
+                } else {
+                    // Bank data
+                    self.registers[self.target_register] = v;
+                }
+            }
+            Addr(0xA000..=0xBFFF) => {
+                if even {
+                    // Mirroring, TxSROM takes the nametables from the CHR banks instead
+                    self.mirror = if v & Byte(0x01) != Byte(0x00) {
+                        Mirror::Horizontal
+                    } else {
+                        Mirror::Vertical
+                    };
+                } else {
+                    // PRG RAM protect
+                    self.ram_enabled = v & Byte(0x80) != Byte(0x00);
+                    self.ram_protected = v & Byte(0x40) != Byte(0x00);
+                }
+            }
+            Addr(0xC000..=0xDFFF) => {
+                if even {
+                    // IRQ latch
+                    self.irq_latch = v;
+                } else {
+                    // IRQ reload, the counter is reloaded at the next scanline
+                    self.irq_counter = Byte(0x00);
+                    self.irq_reload = true;
+                    if self.board == Mmc3Board::Rambo1 {
+                        self.irq_cycles = v & Byte(0x01) != Byte(0x00);
+                        self.prescaler = 0;
+                    }
+                }
+            }
+            Addr(0xE000..=0xFFFF) => {
+                if even {
+                    // IRQ disable, also acknowledges any pending interrupt
+                    self.irq_enabled = false;
+                    self.irq_active = false;
+                } else {
+                    // IRQ enable
+                    self.irq_enabled = true;
+                }
+            }
+            _ => return Mapped::Unmapped,
+        }
+
+        // Registers are not backed by memory
+        Mapped::Data(v)
+    }
+
+    // PPU Address Bus          CHR ROM (or CHR RAM on TQROM)
+    // 0x0000 -> 0x07FF: Map    2 KB switchable (or four 1 KB switchable if inverted)
+    // 0x0800 -> 0x0FFF: Map    2 KB switchable
+    // 0x1000 -> 0x13FF: Map    1 KB switchable (or two 2 KB switchable if inverted)
+    // 0x1400 -> 0x17FF: Map    1 KB switchable
+    // 0x1800 -> 0x1BFF: Map    1 KB switchable
+    // 0x1C00 -> 0x1FFF: Map    1 KB switchable
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) => match self.map_chr_ram(addr) {
+                Some(i) => Mapped::Data(Byte(self.chr_ram[i])),
+                None => Mapped::Chr(self.map_chr(addr)),
+            },
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land on TQROM CHR RAM or when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, v: Byte) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) => match self.map_chr_ram(addr) {
+                Some(i) => {
+                    self.chr_ram[i] = v.0;
+                    Mapped::Data(v)
+                }
+                None => Mapped::Chr(self.map_chr(addr)),
+            },
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // PPU Address Bus          CIRAM on TxSROM
+    // 0x2000 -> 0x2FFF: Map    4 x 1 KB pages picked by the CHR banks at 0x0000 -> 0x0FFF
+    fn read_nametable(&mut self, addr: Addr) -> Mapped {
+        if self.board != Mmc3Board::Txsrom {
+            return Mapped::Unmapped;
+        }
+
+        let bank = self.chr_bank(Addr(addr.0 & 0x0C00));
+        Mapped::CiRam(((bank >> 7) & 0x01) << 10 | (addr & Addr(0x03FF)).as_usize())
+    }
+
+    fn write_nametable(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_nametable(addr)
+    }
+
+    // RAMBO-1 may clock the IRQ counter every 4 CPU cycles instead
+    fn cpu_tick(&mut self) {
+        if self.irq_cycles {
+            self.prescaler = (self.prescaler + 1) & 0x03;
+            if self.prescaler == 0 {
+                self.clock_irq();
+            }
+        }
+    }
+
+    fn mirror(&self) -> Mirror {
+        self.mirror
+    }
+
+    fn has_irq(&self) -> bool {
+        self.irq_active
+    }
+
+    fn clear_irq(&mut self) {
+        self.irq_active = false;
+    }
+
+    // The counter is clocked by the rising edge of PPU A12, which happens
+    // once per rendered scanline when background and sprites use different
+    // pattern tables
+    fn scanline(&mut self) {
+        if !self.irq_cycles {
+            self.clock_irq();
+        }
+    }
+
+    fn reset(&mut self) {
+        self.target_register = 0;
+        self.prg_bank_mode = false;
+        self.chr_inversion = false;
+        self.chr_1k_mode = false;
+        self.registers = [Byte(0x00); 16];
+        self.mirror = Mirror::Hardware;
+
+        self.ram_enabled = true;
+        self.ram_protected = false;
+
+        self.irq_active = false;
+        self.irq_enabled = false;
+        self.irq_reload = false;
+        self.irq_counter = Byte(0x00);
+        self.irq_latch = Byte(0x00);
+        self.irq_cycles = false;
+        self.prescaler = 0;
+    }
+}
+
+impl Mapper004 {
+    pub fn new(prg_banks: usize, chr_banks: usize) -> Self {
+        Self::with_board(prg_banks, chr_banks, Mmc3Board::Txrom)
+    }
+
+    pub fn with_board(prg_banks: usize, chr_banks: usize, board: Mmc3Board) -> Self {
+        let chr_ram = match board {
+            Mmc3Board::Tqrom => vec![0; TQROM_CHR_RAM_SIZE],
+            _ => vec![],
+        };
+
+        let mut s = Self {
+            prg_banks,
+            chr_banks,
+            board,
+            target_register: 0,
+            prg_bank_mode: false,
+            chr_inversion: false,
+            chr_1k_mode: false,
+            registers: [Byte(0x00); 16],
+            mirror: Mirror::Hardware,
+            chr_ram,
+            ram_enabled: true,
+            ram_protected: false,
+            irq_active: false,
+            irq_enabled: false,
+            irq_reload: false,
+            irq_counter: Byte(0x00),
+            irq_latch: Byte(0x00),
+            irq_cycles: false,
+            prescaler: 0,
+        };
+        s.reset();
+        s
+    }
+
+    fn clock_irq(&mut self) {
+        if self.board == Mmc3Board::Rambo1 {
+            // The counter runs one step longer after a reload, two
+            // for latches above 1
+            if self.irq_reload {
+                let extra = if self.irq_latch.0 <= 1 { 1 } else { 2 };
+                self.irq_counter = Byte(self.irq_latch.0.wrapping_add(extra));
+                self.irq_reload = false;
+            } else if self.irq_counter == Byte(0x00) {
+                self.irq_counter = Byte(self.irq_latch.0.wrapping_add(1));
+            }
+            self.irq_counter.dec();
+        } else if self.irq_counter == Byte(0x00) || self.irq_reload {
+            self.irq_counter = self.irq_latch;
+            self.irq_reload = false;
+        } else {
+            self.irq_counter.dec();
+        }
+
+        if self.irq_counter == Byte(0x00) && self.irq_enabled {
+            self.irq_active = true;
+        }
+    }
+
+    fn map_prg(&self, addr: Addr) -> usize {
+        // Count of 8K banks
+        let prg_banks = self.prg_banks.max(1) * 2;
+        let second_last = prg_banks - 2;
+        let last = prg_banks - 1;
+
+        let r6 = (self.registers[6] & Byte(0x3F)).0 as usize;
+        let r7 = (self.registers[7] & Byte(0x3F)).0 as usize;
+
+        let bank = match (addr.0 >> 13) & 0x03 {
+            _ if self.board == Mmc3Board::Rambo1 => {
+                let rf = (self.registers[15] & Byte(0x3F)).0 as usize;
+                match ((addr.0 >> 13) & 0x03, self.prg_bank_mode) {
+                    (0, false) | (1, true) => r6,
+                    (1, false) | (2, true) => r7,
+                    (2, false) | (0, true) => rf,
+                    _ => last,
+                }
+            }
+            0 if self.prg_bank_mode => second_last,
+            0 => r6,
+            1 => r7,
+            2 if self.prg_bank_mode => r6,
+            2 => second_last,
+            _ => last,
+        };
+
+        (bank % prg_banks) * 0x2000 + (addr & Addr(0x1FFF)).as_usize()
+    }
+
+    fn map_chr(&self, addr: Addr) -> usize {
+        // Count of 1K banks
+        let chr_banks = self.chr_banks.max(1) * 8;
+        let bank = self.chr_bank(addr);
+
+        (bank % chr_banks) * 0x0400 + (addr & Addr(0x03FF)).as_usize()
+    }
+
+    // TQROM maps banks with bit 6 set to its CHR RAM
+    fn map_chr_ram(&self, addr: Addr) -> Option<usize> {
+        let bank = self.chr_bank(addr);
+        if self.board != Mmc3Board::Tqrom || bank & 0x40 == 0 {
+            return None;
+        }
+
+        Some((bank & 0x07) * 0x0400 + (addr & Addr(0x03FF)).as_usize())
+    }
+
+    // Unmasked bank number of the 1 KB slot of the address
+    fn chr_bank(&self, addr: Addr) -> usize {
+        // With inversion set, the two pattern tables swap places
+        let slot = if self.chr_inversion {
+            ((addr.0 >> 10) & 0x07) ^ 0x04
+        } else {
+            (addr.0 >> 10) & 0x07
+        };
+
+        let bank = match slot {
+            // RAMBO-1 can split the 2K banks into 1K banks
+            1 if self.chr_1k_mode => self.registers[8],
+            3 if self.chr_1k_mode => self.registers[9],
+            0 if self.chr_1k_mode => self.registers[0],
+            2 if self.chr_1k_mode => self.registers[1],
+            // 2K banks ignore the low bit of the bank number
+            0 => self.registers[0] & Byte(0xFE),
+            1 => self.registers[0] | Byte(0x01),
+            2 => self.registers[1] & Byte(0xFE),
+            3 => self.registers[1] | Byte(0x01),
+            n => self.registers[n as usize - 2],
+        };
+
+        bank.0 as usize
+    }
+}
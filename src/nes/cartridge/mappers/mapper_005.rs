@@ -0,0 +1,447 @@
+use super::mapper::{Mapped, Mapper, PpuFetch};
+use crate::prelude::*;
+
+// MMC5 (ExROM)
+//
+// CPU Address Bus  Register
+// $5100            PRG mode (0: one 32 KB bank; 1: two 16 KB banks;
+//                            2: one 16 KB bank and two 8 KB banks; 3: four 8 KB banks)
+// $5101            CHR mode (0: 8 KB pages; 1: 4 KB pages; 2: 2 KB pages; 3: 1 KB pages)
+// $5102            PRG RAM protect 1 (writable only when it is 2)
+// $5103            PRG RAM protect 2 (writable only when it is 1)
+// $5104            ExRAM mode (0: extra nametable; 1: extended attributes;
+//                              2: CPU RAM; 3: CPU read-only RAM)
+// $5105            Nametable mapping, 2 bits per nametable (0: CIRAM page 0;
+//                  1: CIRAM page 1; 2: ExRAM; 3: fill mode)
+// $5106            Fill mode tile
+// $5107            Fill mode palette
+// $5113            PRG RAM bank at $6000-$7FFF
+// $5114-$5117      PRG banks, bit 7 selects ROM (1) or RAM (0), $5117 is always ROM
+// $5120-$5127      CHR banks of set A, used by sprites in 8x16 mode
+// $5128-$512B      CHR banks of set B, used by background in 8x16 mode
+// $5130            Upper CHR bank bits
+// $5200            Vertical split mode
+// 76543210
+// || |||||
+// || +++++- Split threshold in tiles
+// |+------- Split region (0: left of the threshold; 1: right of it)
+// +-------- Enable vertical split
+// $5201            Vertical split scroll
+// $5202            Vertical split 4 KB CHR bank
+// $5203            IRQ scanline compare
+// $5204            IRQ enable (write), IRQ status (read, acknowledges the IRQ)
+// 76543210
+// ||
+// |+------- In frame
+// +-------- IRQ pending
+// $5205-$5206      Unsigned 8x8 multiplier, reads give low and high byte of the product
+// $5C00-$5FFF      ExRAM, 1 KB
+pub struct Mapper005 {
+    prg_banks: usize,
+    chr_banks: usize,
+
+    prg_mode:    u8,
+    chr_mode:    u8,
+    ram_protect: [Byte; 2],
+    prg_regs:    [Byte; 5],
+    chr_regs:    [usize; 12],
+    chr_upper:   usize,
+    chr_last_b:  bool,
+
+    exram_mode: u8,
+    exram:      [Byte; 0x0400],
+    nametables: Byte,
+    fill_tile:  Byte,
+    fill_attr:  Byte,
+
+    split_ctrl:   Byte,
+    split_scroll: usize,
+    split_bank:   usize,
+
+    irq_compare: usize,
+    irq_enabled: bool,
+    irq_pending: bool,
+    in_frame:    bool,
+    line:        usize,
+
+    multiplicand: u8,
+    multiplier:   u8,
+
+    // What the PPU is fetching at the moment
+    fetch:        PpuFetch,
+    tall_sprites: bool,
+    tile:         usize,
+    split_y:      usize,
+    in_split:     bool,
+    ext_attr:     Byte,
+}
+
+impl Mapper for Mapper005 {
+    // CPU Address Bus          PRG ROM / RAM
+    // 0x6000 -> 0x7FFF: Map    8 KB PRG RAM bank
+    // 0x8000 -> 0xFFFF: Map    32, 16 or 8 KB banks depending on PRG mode
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x5204) => {
+                let mut v = Byte(0x00);
+                if self.irq_pending {
+                    v |= Byte(0x80);
+                }
+                if self.in_frame {
+                    v |= Byte(0x40);
+                }
+                // Reading acknowledges the IRQ
+                self.irq_pending = false;
+                Mapped::Data(v)
+            }
+            Addr(0x5205) => {
+                let product = self.multiplicand as u16 * self.multiplier as u16;
+                Mapped::Data(Byte(product as u8))
+            }
+            Addr(0x5206) => {
+                let product = self.multiplicand as u16 * self.multiplier as u16;
+                Mapped::Data(Byte((product >> 8) as u8))
+            }
+            // ExRAM is readable by CPU in RAM modes only
+            Addr(0x5C00..=0x5FFF) if self.exram_mode >= 2 => {
+                Mapped::Data(self.exram[(addr & Addr(0x03FF)).as_usize()])
+            }
+            Addr(0x6000..=0xFFFF) => self.map_prg(addr),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        match addr {
+            Addr(0x5100) => self.prg_mode = v.0 & 0x03,
+            Addr(0x5101) => self.chr_mode = v.0 & 0x03,
+            Addr(0x5102) => self.ram_protect[0] = v & Byte(0x03),
+            Addr(0x5103) => self.ram_protect[1] = v & Byte(0x03),
+            Addr(0x5104) => self.exram_mode = v.0 & 0x03,
+            Addr(0x5105) => self.nametables = v,
+            Addr(0x5106) => self.fill_tile = v,
+            Addr(0x5107) => self.fill_attr = v & Byte(0x03),
+            Addr(0x5113..=0x5117) => self.prg_regs[(addr.0 - 0x5113) as usize] = v,
+            Addr(0x5120..=0x512B) => {
+                let reg = (addr.0 - 0x5120) as usize;
+                self.chr_regs[reg] = v.0 as usize | (self.chr_upper << 8);
+                self.chr_last_b = reg >= 8;
+            }
+            Addr(0x5130) => self.chr_upper = (v.0 & 0x03) as usize,
+            Addr(0x5200) => self.split_ctrl = v,
+            Addr(0x5201) => self.split_scroll = v.0 as usize,
+            Addr(0x5202) => self.split_bank = v.0 as usize,
+            Addr(0x5203) => self.irq_compare = v.0 as usize,
+            Addr(0x5204) => self.irq_enabled = v & Byte(0x80) != Byte(0x00),
+            Addr(0x5205) => self.multiplicand = v.0,
+            Addr(0x5206) => self.multiplier = v.0,
+            Addr(0x5C00..=0x5FFF) => {
+                let i = (addr & Addr(0x03FF)).as_usize();
+                match self.exram_mode {
+                    // In nametable modes the CPU can only write while the
+                    // PPU is rendering, otherwise zero is written
+                    0 | 1 if !self.in_frame => self.exram[i] = Byte(0x00),
+                    0..=2 => self.exram[i] = v,
+                    _ => {}
+                }
+            }
+            Addr(0x6000..=0xFFFF) => {
+                return match self.map_prg(addr) {
+                    Mapped::PrgRam(i) if self.ram_writable() => Mapped::PrgRam(i),
+                    _ => Mapped::Unmapped,
+                };
+            }
+            _ => return Mapped::Unmapped,
+        }
+
+        // Registers are not backed by memory
+        Mapped::Data(v)
+    }
+
+    // PPU Address Bus          CHR ROM
+    // 0x0000 -> 0x1FFF: Map    8, 4, 2 or 1 KB banks depending on CHR mode
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) if self.rendering_background() && self.in_split => {
+                // Split tiles take the fine scroll from the split counter
+                let offset = (addr & Addr(0x0FF8)).as_usize() | (self.split_y & 0x07);
+                Mapped::Chr(self.chr_offset(self.split_bank * 0x1000 + offset))
+            }
+            Addr(0x0000..=0x1FFF) if self.rendering_background() && self.exram_mode == 1 => {
+                // Extended attributes select a 4 KB bank per tile
+                let bank = (self.ext_attr & Byte(0x3F)).0 as usize | (self.chr_upper << 6);
+                Mapped::Chr(self.chr_offset(bank * 0x1000 + (addr & Addr(0x0FFF)).as_usize()))
+            }
+            Addr(0x0000..=0x1FFF) => Mapped::Chr(self.map_chr(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) => Mapped::Chr(self.map_chr(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn read_nametable(&mut self, addr: Addr) -> Mapped {
+        let offset = (addr & Addr(0x03FF)).as_usize();
+        let attr = offset >= 0x03C0;
+
+        if self.rendering_background() {
+            if !attr {
+                // Each tile starts with the nametable fetch
+                self.in_split = self.split_active();
+            }
+
+            if self.in_split {
+                let x = self.tile & 0x1F;
+                let y = self.split_y;
+                return if attr {
+                    let v = self.exram[0x03C0 + (y / 32) * 8 + x / 4].0;
+                    let shift = ((y / 16) & 0x01) * 4 + ((x / 2) & 0x01) * 2;
+                    Mapped::Data(Byte(((v >> shift) & 0x03) * 0x55))
+                } else {
+                    Mapped::Data(self.exram[(y / 8) * 32 + x])
+                };
+            }
+
+            if self.exram_mode == 1 {
+                if attr {
+                    // The palette of the tile is repeated for every quadrant
+                    return Mapped::Data(Byte((self.ext_attr.0 >> 6) * 0x55));
+                }
+                self.ext_attr = self.exram[offset];
+            }
+        }
+
+        match self.nametable_source(addr) {
+            0 => Mapped::CiRam(offset),
+            1 => Mapped::CiRam(0x0400 + offset),
+            2 if self.exram_mode <= 1 => Mapped::Data(self.exram[offset]),
+            2 => Mapped::Data(Byte(0x00)),
+            _ if attr => Mapped::Data(Byte(self.fill_attr.0 * 0x55)),
+            _ => Mapped::Data(self.fill_tile),
+        }
+    }
+
+    fn write_nametable(&mut self, addr: Addr, v: Byte) -> Mapped {
+        let offset = (addr & Addr(0x03FF)).as_usize();
+
+        match self.nametable_source(addr) {
+            0 => Mapped::CiRam(offset),
+            1 => Mapped::CiRam(0x0400 + offset),
+            2 => {
+                if self.exram_mode <= 1 {
+                    self.exram[offset] = v;
+                }
+                Mapped::Data(v)
+            }
+            // Fill mode is read-only
+            _ => Mapped::Data(v),
+        }
+    }
+
+    fn ppu_bus(&mut self, addr: Addr) {
+        // The high plane is the last fetch of a background tile
+        if self.rendering_background() && addr < Addr(0x2000) && addr & Addr(0x0008) != Addr(0x0000)
+        {
+            self.tile += 1;
+        }
+    }
+
+    fn ppu_fetch(&mut self, fetch: PpuFetch, tall_sprites: bool) {
+        if let PpuFetch::Idle = fetch {
+            self.in_frame = false;
+        }
+
+        self.fetch = fetch;
+        self.tall_sprites = tall_sprites;
+    }
+
+    fn has_irq(&self) -> bool {
+        self.irq_pending && self.irq_enabled
+    }
+
+    fn clear_irq(&mut self) {
+        self.irq_pending = false;
+    }
+
+    // The pre-render scanline starts the frame, so the counter
+    // holds the number of the scanline to be rendered next
+    fn scanline(&mut self) {
+        self.tile = 0;
+
+        if !self.in_frame {
+            self.in_frame = true;
+            self.irq_pending = false;
+            self.line = 0;
+            self.split_y = self.split_scroll % 240;
+            return;
+        }
+
+        self.line += 1;
+        self.split_y = (self.split_y + 1) % 240;
+        if self.line == self.irq_compare {
+            self.irq_pending = true;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.prg_mode = 3;
+        self.chr_mode = 0;
+        self.ram_protect = [Byte(0x00); 2];
+        self.prg_regs = [Byte(0x00), Byte(0x00), Byte(0x00), Byte(0x00), Byte(0xFF)];
+        self.chr_regs = [0; 12];
+        self.chr_upper = 0;
+        self.chr_last_b = false;
+
+        self.exram_mode = 0;
+        self.exram = [Byte(0x00); 0x0400];
+        self.nametables = Byte(0x00);
+        self.fill_tile = Byte(0x00);
+        self.fill_attr = Byte(0x00);
+
+        self.split_ctrl = Byte(0x00);
+        self.split_scroll = 0;
+        self.split_bank = 0;
+
+        self.irq_compare = 0;
+        self.irq_enabled = false;
+        self.irq_pending = false;
+        self.in_frame = false;
+        self.line = 0;
+
+        self.multiplicand = 0xFF;
+        self.multiplier = 0xFF;
+
+        self.fetch = PpuFetch::Idle;
+        self.tall_sprites = false;
+        self.tile = 0;
+        self.split_y = 0;
+        self.in_split = false;
+        self.ext_attr = Byte(0x00);
+    }
+}
+
+impl Mapper005 {
+    pub fn new(prg_banks: usize, chr_banks: usize) -> Self {
+        let mut s = Self {
+            prg_banks,
+            chr_banks,
+            prg_mode: 3,
+            chr_mode: 0,
+            ram_protect: [Byte(0x00); 2],
+            prg_regs: [Byte(0x00); 5],
+            chr_regs: [0; 12],
+            chr_upper: 0,
+            chr_last_b: false,
+            exram_mode: 0,
+            exram: [Byte(0x00); 0x0400],
+            nametables: Byte(0x00),
+            fill_tile: Byte(0x00),
+            fill_attr: Byte(0x00),
+            split_ctrl: Byte(0x00),
+            split_scroll: 0,
+            split_bank: 0,
+            irq_compare: 0,
+            irq_enabled: false,
+            irq_pending: false,
+            in_frame: false,
+            line: 0,
+            multiplicand: 0xFF,
+            multiplier: 0xFF,
+            fetch: PpuFetch::Idle,
+            tall_sprites: false,
+            tile: 0,
+            split_y: 0,
+            in_split: false,
+            ext_attr: Byte(0x00),
+        };
+        s.reset();
+        s
+    }
+
+    fn ram_writable(&self) -> bool {
+        self.ram_protect[0] == Byte(0x02) && self.ram_protect[1] == Byte(0x01)
+    }
+
+    fn rendering_background(&self) -> bool {
+        self.in_frame && self.fetch == PpuFetch::Background
+    }
+
+    fn split_active(&self) -> bool {
+        if self.split_ctrl & Byte(0x80) == Byte(0x00) || self.exram_mode > 1 {
+            return false;
+        }
+
+        let threshold = (self.split_ctrl & Byte(0x1F)).0 as usize;
+        if self.split_ctrl & Byte(0x40) != Byte(0x00) {
+            self.tile >= threshold
+        } else {
+            self.tile < threshold
+        }
+    }
+
+    fn nametable_source(&self, addr: Addr) -> u8 {
+        let table = (addr.0 >> 10) & 0x03;
+        (self.nametables.0 >> (table * 2)) & 0x03
+    }
+
+    fn map_prg(&self, addr: Addr) -> Mapped {
+        // Register and window size for the address
+        let (reg, size) = match (self.prg_mode, addr) {
+            (_, Addr(0x6000..=0x7FFF)) => (0, 0x2000),
+            (0, _) => (4, 0x8000),
+            (1, Addr(0x8000..=0xBFFF)) => (2, 0x4000),
+            (1, _) => (4, 0x4000),
+            (2, Addr(0x8000..=0xBFFF)) => (2, 0x4000),
+            (2, Addr(0xC000..=0xDFFF)) => (3, 0x2000),
+            (2, _) => (4, 0x2000),
+            _ => (1 + ((addr.0 - 0x8000) >> 13) as usize, 0x2000),
+        };
+
+        let v = self.prg_regs[reg];
+        let offset = (addr.0 as usize) & (size - 1);
+        // Bank numbers are in 8 KB units, bigger windows ignore the low bits
+        let mask = !(size / 0x2000 - 1);
+
+        if reg == 4 || (reg > 0 && v & Byte(0x80) != Byte(0x00)) {
+            let bank = (v & Byte(0x7F)).0 as usize & mask;
+            let prg_size = self.prg_banks.max(1) * 0x4000;
+            Mapped::Prg((bank * 0x2000 + offset) % prg_size)
+        } else {
+            let bank = (v & Byte(0x0F)).0 as usize & mask;
+            Mapped::PrgRam(bank * 0x2000 + offset)
+        }
+    }
+
+    fn map_chr(&self, addr: Addr) -> usize {
+        // In 8x16 mode sprites and background have their own sets,
+        // otherwise the last written set is used for everything
+        let set_b = match self.fetch {
+            PpuFetch::Sprites if self.tall_sprites => false,
+            PpuFetch::Background if self.tall_sprites && self.in_frame => true,
+            _ => self.chr_last_b,
+        };
+
+        let a = addr.0 as usize;
+        let (size, reg) = match self.chr_mode {
+            0 if set_b => (0x2000, 11),
+            0 => (0x2000, 7),
+            1 if set_b => (0x1000, 11),
+            1 => (0x1000, 3 + 4 * (a >> 12)),
+            2 if set_b => (0x0800, 9 + 2 * ((a >> 11) & 0x01)),
+            2 => (0x0800, 1 + 2 * (a >> 11)),
+            _ if set_b => (0x0400, 8 + ((a >> 10) & 0x03)),
+            _ => (0x0400, a >> 10),
+        };
+
+        self.chr_offset(self.chr_regs[reg] * size + (a & (size - 1)))
+    }
+
+    fn chr_offset(&self, offset: usize) -> usize {
+        offset % (self.chr_banks.max(1) * 0x2000)
+    }
+}
@@ -0,0 +1,92 @@
+use super::mapper::{Mapped, Mapper};
+use crate::cartridge::Mirror;
+use crate::prelude::*;
+
+// AxROM
+//
+// CPU Address Bus  Register
+// $8000-$FFFF      Bank select
+//
+// Bank select
+// 76543210
+//    |||||
+//    |+++- Select 32 KB PRG ROM bank for CPU $8000-$FFFF
+//    +---- Select 1 KB VRAM page for all 4 nametables
+pub struct Mapper007 {
+    prg_banks: usize,
+
+    prg_bank: usize,
+    mirror:   Mirror,
+}
+
+impl Mapper for Mapper007 {
+    // CPU Address Bus          PRG RAM
+    // 0x6000 -> 0x7FFF: Map    0x0000 -> 0x1FFF
+    // CPU Address Bus          PRG ROM
+    // 0x8000 -> 0xFFFF: Map    32 KB switchable bank
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) => Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize()),
+            Addr(0x8000..=0xFFFF) => Mapped::Prg(self.map_prg(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) => Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize()),
+            Addr(0x8000..=0xFFFF) => {
+                self.prg_bank = (v & Byte(0x07)).0 as usize;
+                self.mirror = if v & Byte(0x10) != Byte(0x00) {
+                    Mirror::OneScreenHi
+                } else {
+                    Mirror::OneScreenLo
+                };
+                // Register is not backed by memory, so the write
+                // must not reach PRG ROM
+                Mapped::Data(v)
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // There is no mapping required for PPU
+    // PPU Address Bus          CHR RAM
+    // 0x0000 -> 0x1FFF: Map    0x0000 -> 0x1FFF
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) => Mapped::Chr(addr.as_usize()),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
+    }
+
+    fn mirror(&self) -> Mirror {
+        self.mirror
+    }
+
+    fn reset(&mut self) {
+        self.prg_bank = 0;
+        self.mirror = Mirror::OneScreenLo;
+    }
+}
+
+impl Mapper007 {
+    pub fn new(prg_banks: usize, _chr_banks: usize) -> Self {
+        Self {
+            prg_banks,
+            prg_bank: 0,
+            mirror: Mirror::OneScreenLo,
+        }
+    }
+
+    fn map_prg(&self, addr: Addr) -> usize {
+        // Count of 32K banks
+        let prg_banks = (self.prg_banks / 2).max(1);
+        (self.prg_bank % prg_banks) * 0x8000 + (addr & Addr(0x7FFF)).as_usize()
+    }
+}
@@ -0,0 +1,178 @@
+use super::mapper::{Mapped, Mapper};
+use crate::cartridge::Mirror;
+use crate::prelude::*;
+
+// MMC2 (PxROM)
+//
+// CPU Address Bus  Register
+// $A000-$AFFF      PRG ROM bank select (8 KB bank at $8000)
+// $B000-$BFFF      CHR ROM $FD/0000 bank select
+// $C000-$CFFF      CHR ROM $FE/0000 bank select
+// $D000-$DFFF      CHR ROM $FD/1000 bank select
+// $E000-$EFFF      CHR ROM $FE/1000 bank select
+// $F000-$FFFF      Mirroring (0: vertical; 1: horizontal)
+//
+// Each 4 KB half of the pattern tables has a latch choosing between its
+// $FD and $FE bank. The latch is set by the PPU fetching tile $FD or $FE:
+//     $0FD8:       latch 0 = $FD
+//     $0FE8:       latch 0 = $FE
+//     $1FD8-$1FDF: latch 1 = $FD
+//     $1FE8-$1FEF: latch 1 = $FE
+// The new bank is used starting from the next fetch
+pub struct Mapper009 {
+    prg_banks: usize,
+
+    prg_bank: usize,
+    chr:      LatchedChr,
+    mirror:   Mirror,
+}
+
+impl Mapper for Mapper009 {
+    // CPU Address Bus          PRG RAM
+    // 0x6000 -> 0x7FFF: Map    0x0000 -> 0x1FFF
+    // CPU Address Bus          PRG ROM
+    // 0x8000 -> 0x9FFF: Map    8 KB switchable bank
+    // 0xA000 -> 0xFFFF: Map    24 KB fixed to the last three banks
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) => Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize()),
+            Addr(0x8000..=0xFFFF) => Mapped::Prg(self.map_prg(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) => Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize()),
+            Addr(0xA000..=0xAFFF) => {
+                self.prg_bank = (v & Byte(0x0F)).0 as usize;
+                Mapped::Data(v)
+            }
+            Addr(0xB000..=0xEFFF) => {
+                self.chr.write(addr, v);
+                Mapped::Data(v)
+            }
+            Addr(0xF000..=0xFFFF) => {
+                self.mirror = LatchedChr::mirror(v);
+                Mapped::Data(v)
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // PPU Address Bus          CHR ROM
+    // 0x0000 -> 0x0FFF: Map    4 KB switchable bank selected by latch 0
+    // 0x1000 -> 0x1FFF: Map    4 KB switchable bank selected by latch 1
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) => Mapped::Chr(self.chr.map(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
+    }
+
+    fn ppu_bus(&mut self, addr: Addr) {
+        // Latch 0 reacts to a single address only on MMC2
+        match addr {
+            Addr(0x0FD8) | Addr(0x0FE8) | Addr(0x1000..=0x1FFF) => self.chr.fetch(addr),
+            _ => {}
+        }
+    }
+
+    fn mirror(&self) -> Mirror {
+        self.mirror
+    }
+
+    fn reset(&mut self) {
+        self.prg_bank = 0;
+        self.chr.reset();
+        self.mirror = Mirror::Vertical;
+    }
+}
+
+impl Mapper009 {
+    pub fn new(prg_banks: usize, chr_banks: usize) -> Self {
+        Self {
+            prg_banks,
+            prg_bank: 0,
+            chr: LatchedChr::new(chr_banks),
+            mirror: Mirror::Vertical,
+        }
+    }
+
+    fn map_prg(&self, addr: Addr) -> usize {
+        // Count of 8K banks
+        let prg_banks = self.prg_banks.max(1) * 2;
+        let bank = match addr {
+            Addr(0x8000..=0x9FFF) => self.prg_bank,
+            // The last three banks are fixed in their order
+            _ => prg_banks.max(4) - 4 + ((addr.0 >> 13) & 0x03) as usize,
+        };
+
+        (bank % prg_banks) * 0x2000 + (addr & Addr(0x1FFF)).as_usize()
+    }
+}
+
+// Pattern tables of MMC2 and MMC4, two 4 KB banks each switched by
+// a latch between a $FD and a $FE bank register
+pub(super) struct LatchedChr {
+    chr_banks: usize,
+
+    // [half][latch], where latch 0 is $FD and latch 1 is $FE
+    banks:   [[usize; 2]; 2],
+    latches: [usize; 2],
+}
+
+impl LatchedChr {
+    pub(super) fn new(chr_banks: usize) -> Self {
+        let mut s = Self {
+            chr_banks,
+            banks: [[0; 2]; 2],
+            latches: [1; 2],
+        };
+        s.reset();
+        s
+    }
+
+    pub(super) fn reset(&mut self) {
+        self.banks = [[0; 2]; 2];
+        self.latches = [1; 2];
+    }
+
+    // $B000-$EFFF bank registers
+    pub(super) fn write(&mut self, addr: Addr, v: Byte) {
+        let reg = ((addr.0 >> 12) - 0x0B) as usize;
+        self.banks[reg >> 1][reg & 0x01] = (v & Byte(0x1F)).0 as usize;
+    }
+
+    pub(super) fn mirror(v: Byte) -> Mirror {
+        if v & Byte(0x01) != Byte(0x00) {
+            Mirror::Horizontal
+        } else {
+            Mirror::Vertical
+        }
+    }
+
+    // Updates latches after the PPU has fetched from the address
+    pub(super) fn fetch(&mut self, addr: Addr) {
+        let half = ((addr.0 >> 12) & 0x01) as usize;
+        match addr.0 & 0x0FF8 {
+            0x0FD8 => self.latches[half] = 0,
+            0x0FE8 => self.latches[half] = 1,
+            _ => {}
+        }
+    }
+
+    pub(super) fn map(&self, addr: Addr) -> usize {
+        // Count of 4K banks
+        let chr_banks = self.chr_banks.max(1) * 2;
+        let half = ((addr.0 >> 12) & 0x01) as usize;
+        let bank = self.banks[half][self.latches[half]];
+
+        (bank % chr_banks) * 0x1000 + (addr & Addr(0x0FFF)).as_usize()
+    }
+}
@@ -0,0 +1,114 @@
+use super::mapper::{Mapped, Mapper};
+use super::mapper_009::LatchedChr;
+use crate::cartridge::Mirror;
+use crate::prelude::*;
+
+// MMC4 (FxROM)
+//
+// CPU Address Bus  Register
+// $A000-$AFFF      PRG ROM bank select (16 KB bank at $8000)
+// $B000-$BFFF      CHR ROM $FD/0000 bank select
+// $C000-$CFFF      CHR ROM $FE/0000 bank select
+// $D000-$DFFF      CHR ROM $FD/1000 bank select
+// $E000-$EFFF      CHR ROM $FE/1000 bank select
+// $F000-$FFFF      Mirroring (0: vertical; 1: horizontal)
+//
+// The CHR latches work like on MMC2, except both of them react to
+// a range of addresses:
+//     $0FD8-$0FDF: latch 0 = $FD
+//     $0FE8-$0FEF: latch 0 = $FE
+//     $1FD8-$1FDF: latch 1 = $FD
+//     $1FE8-$1FEF: latch 1 = $FE
+pub struct Mapper010 {
+    prg_banks: usize,
+
+    prg_bank: usize,
+    chr:      LatchedChr,
+    mirror:   Mirror,
+}
+
+impl Mapper for Mapper010 {
+    // CPU Address Bus          PRG RAM
+    // 0x6000 -> 0x7FFF: Map    0x0000 -> 0x1FFF
+    // CPU Address Bus          PRG ROM
+    // 0x8000 -> 0xBFFF: Map    16 KB switchable bank
+    // 0xC000 -> 0xFFFF: Map    16 KB fixed to the last bank
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) => Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize()),
+            Addr(0x8000..=0xFFFF) => Mapped::Prg(self.map_prg(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) => Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize()),
+            Addr(0xA000..=0xAFFF) => {
+                self.prg_bank = (v & Byte(0x0F)).0 as usize;
+                Mapped::Data(v)
+            }
+            Addr(0xB000..=0xEFFF) => {
+                self.chr.write(addr, v);
+                Mapped::Data(v)
+            }
+            Addr(0xF000..=0xFFFF) => {
+                self.mirror = LatchedChr::mirror(v);
+                Mapped::Data(v)
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // PPU Address Bus          CHR ROM
+    // 0x0000 -> 0x0FFF: Map    4 KB switchable bank selected by latch 0
+    // 0x1000 -> 0x1FFF: Map    4 KB switchable bank selected by latch 1
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) => Mapped::Chr(self.chr.map(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
+    }
+
+    fn ppu_bus(&mut self, addr: Addr) {
+        if let Addr(0x0000..=0x1FFF) = addr {
+            self.chr.fetch(addr);
+        }
+    }
+
+    fn mirror(&self) -> Mirror {
+        self.mirror
+    }
+
+    fn reset(&mut self) {
+        self.prg_bank = 0;
+        self.chr.reset();
+        self.mirror = Mirror::Vertical;
+    }
+}
+
+impl Mapper010 {
+    pub fn new(prg_banks: usize, chr_banks: usize) -> Self {
+        Self {
+            prg_banks,
+            prg_bank: 0,
+            chr: LatchedChr::new(chr_banks),
+            mirror: Mirror::Vertical,
+        }
+    }
+
+    fn map_prg(&self, addr: Addr) -> usize {
+        let prg_banks = self.prg_banks.max(1);
+        let bank = match addr {
+            Addr(0x8000..=0xBFFF) => self.prg_bank,
+            _ => prg_banks - 1,
+        };
+
+        (bank % prg_banks) * 0x4000 + (addr & Addr(0x3FFF)).as_usize()
+    }
+}
@@ -0,0 +1,83 @@
+use super::discrete::{map_chr, map_prg};
+use super::mapper::{Mapped, Mapper};
+use crate::prelude::*;
+
+// Color Dreams
+//
+// CPU Address Bus  Register
+// $8000-$FFFF      Bank select
+//
+// Bank select
+// 76543210
+// ||||  ||
+// ||||  ++- Select 32 KB PRG ROM bank for CPU $8000-$FFFF
+// ++++----- Select 8 KB CHR ROM bank for PPU $0000-$1FFF
+pub struct Mapper011 {
+    prg_banks: usize,
+    chr_banks: usize,
+
+    prg_bank: usize,
+    chr_bank: usize,
+}
+
+impl Mapper for Mapper011 {
+    // CPU Address Bus          PRG ROM
+    // 0x8000 -> 0xFFFF: Map    32 KB switchable bank
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x8000..=0xFFFF) => {
+                Mapped::Prg(map_prg(self.prg_banks, 0x8000, self.prg_bank, addr))
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        match addr {
+            Addr(0x8000..=0xFFFF) => {
+                self.prg_bank = (v & Byte(0x03)).0 as usize;
+                self.chr_bank = (v.0 >> 4) as usize;
+                // Register is not backed by memory, so the write
+                // must not reach PRG ROM
+                Mapped::Data(v)
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // PPU Address Bus          CHR ROM
+    // 0x0000 -> 0x1FFF: Map    8 KB switchable bank
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) => {
+                Mapped::Chr(map_chr(self.chr_banks, 0x2000, self.chr_bank, addr))
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
+    }
+
+    fn bus_conflicts(&self) -> bool {
+        true
+    }
+
+    fn reset(&mut self) {
+        self.prg_bank = 0;
+        self.chr_bank = 0;
+    }
+}
+
+impl Mapper011 {
+    pub fn new(prg_banks: usize, chr_banks: usize) -> Self {
+        Self {
+            prg_banks,
+            chr_banks,
+            prg_bank: 0,
+            chr_bank: 0,
+        }
+    }
+}
@@ -0,0 +1,294 @@
+use super::mapper::{Mapped, Mapper};
+use crate::cartridge::{CartridgeHeader, Eeprom, EepromChip, Mirror};
+use crate::prelude::*;
+
+// Bandai FCG-1/2 and LZ93D50 (mappers 16, 153 and 159)
+//
+// Registers repeat every 16 bytes, at $6000-$7FFF on FCG-1/2 and at
+// $8000-$FFFF on LZ93D50
+// Register  Meaning
+// $0-$7     1 KB CHR ROM bank. On 153 bit 0 of $0-$3 selects the 256 KB PRG ROM half
+// $8        16 KB PRG ROM bank at $8000
+// $9        Mirroring (0: vertical; 1: horizontal; 2: one-screen lower; 3: one-screen upper)
+// $A        IRQ control, bit 0 enables the IRQ. Acknowledges the IRQ and on
+//           LZ93D50 copies the latch into the counter
+// $B        IRQ counter (FCG-1/2) or latch (LZ93D50), low 8 bits
+// $C        IRQ counter (FCG-1/2) or latch (LZ93D50), high 8 bits
+// $D        EEPROM control, or PRG RAM enable on 153 (bit 5)
+// 76543210
+// |||
+// ||+------ SCL
+// |+------- SDA
+// +-------- Read from the EEPROM
+// Reading $6000-$7FFF returns SDA of the EEPROM in bit 4
+//
+// The IRQ counter decrements every CPU cycle while enabled and
+// raises the IRQ when it is decremented at zero
+//
+//     Mapper  Submapper  Board
+//     16      4          FCG-1/2, no EEPROM
+//     16      5          LZ93D50 with 24C02
+//     16      0          Unknown, both windows with 24C02
+//     153     -          LZ93D50 with 8 KB PRG RAM
+//     159     -          LZ93D50 with 24C01
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BandaiBoard {
+    // Registers respond at $6000-$7FFF
+    pub fcg:     bool,
+    // Registers respond at $8000-$FFFF and the IRQ counter has a latch
+    pub lz93d50: bool,
+    pub eeprom:  Option<EepromChip>,
+    // 8 KB PRG RAM and CHR RAM of mapper 153
+    pub sram:    bool,
+}
+
+impl BandaiBoard {
+    // Picks the board from the mapper and submapper of the header
+    pub fn from_header(header: &CartridgeHeader) -> Self {
+        let board = Self {
+            fcg:     false,
+            lz93d50: true,
+            eeprom:  None,
+            sram:    false,
+        };
+
+        match (header.mapper, header.submapper) {
+            (153, _) => Self {
+                sram: true,
+                ..board
+            },
+            (159, _) => Self {
+                eeprom: Some(EepromChip::C24C01),
+                ..board
+            },
+            (_, 4) => Self {
+                fcg: true,
+                lz93d50: false,
+                ..board
+            },
+            (_, 5) => Self {
+                eeprom: Some(EepromChip::C24C02),
+                ..board
+            },
+            _ => Self {
+                fcg: true,
+                eeprom: Some(EepromChip::C24C02),
+                ..board
+            },
+        }
+    }
+}
+
+pub struct Mapper016 {
+    prg_banks: usize,
+    chr_banks: usize,
+    board:     BandaiBoard,
+
+    prg_bank:    usize,
+    chr_regs:    [usize; 8],
+    mirror:      Mirror,
+    ram_enabled: bool,
+
+    irq_counter: u16,
+    irq_latch:   u16,
+    irq_enabled: bool,
+    irq_active:  bool,
+
+    eeprom: Option<Eeprom>,
+}
+
+impl Mapper for Mapper016 {
+    // CPU Address Bus          PRG RAM or EEPROM
+    // 0x6000 -> 0x7FFF: Map    0x0000 -> 0x1FFF
+    // CPU Address Bus          PRG ROM
+    // 0x8000 -> 0xBFFF: Map    16 KB switchable bank
+    // 0xC000 -> 0xFFFF: Map    16 KB fixed to the last bank
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) if self.board.sram && self.ram_enabled => {
+                Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize())
+            }
+            Addr(0x6000..=0x7FFF) => match self.eeprom {
+                Some(ref e) => Mapped::Data(Byte((e.sda_out() as u8) << 4)),
+                None => Mapped::Unmapped,
+            },
+            Addr(0x8000..=0xFFFF) => Mapped::Prg(self.map_prg(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) if self.board.sram && self.ram_enabled => {
+                Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize())
+            }
+            Addr(0x6000..=0x7FFF) if self.board.fcg => {
+                self.register(addr, v);
+                Mapped::Data(v)
+            }
+            Addr(0x8000..=0xFFFF) if self.board.lz93d50 => {
+                self.register(addr, v);
+                Mapped::Data(v)
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // PPU Address Bus          CHR ROM
+    // 0x0000 -> 0x1FFF: Map    8 x 1 KB switchable banks
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            // 153 carries 8 KB of CHR RAM without banking
+            Addr(0x0000..=0x1FFF) if self.board.sram => Mapped::Chr(addr.as_usize()),
+            Addr(0x0000..=0x1FFF) => Mapped::Chr(self.map_chr(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
+    }
+
+    fn cpu_tick(&mut self) {
+        if self.irq_enabled {
+            if self.irq_counter == 0 {
+                self.irq_active = true;
+            }
+            self.irq_counter = self.irq_counter.wrapping_sub(1);
+        }
+    }
+
+    fn battery_ram(&self) -> &[u8] {
+        match self.eeprom {
+            Some(ref e) => e.data(),
+            None => &[],
+        }
+    }
+
+    fn load_battery_ram(&mut self, data: &[u8]) {
+        if let Some(ref mut e) = self.eeprom {
+            e.load(data);
+        }
+    }
+
+    fn mirror(&self) -> Mirror {
+        self.mirror
+    }
+
+    fn has_irq(&self) -> bool {
+        self.irq_active
+    }
+
+    fn clear_irq(&mut self) {
+        self.irq_active = false;
+    }
+
+    fn reset(&mut self) {
+        self.prg_bank = 0;
+        self.chr_regs = [0; 8];
+        self.mirror = Mirror::Vertical;
+        self.ram_enabled = false;
+
+        self.irq_counter = 0;
+        self.irq_latch = 0;
+        self.irq_enabled = false;
+        self.irq_active = false;
+
+        if let Some(ref mut e) = self.eeprom {
+            e.reset();
+        }
+    }
+}
+
+impl Mapper016 {
+    pub fn new(prg_banks: usize, chr_banks: usize, board: BandaiBoard) -> Self {
+        let mut s = Self {
+            prg_banks,
+            chr_banks,
+            board,
+            prg_bank: 0,
+            chr_regs: [0; 8],
+            mirror: Mirror::Vertical,
+            ram_enabled: false,
+            irq_counter: 0,
+            irq_latch: 0,
+            irq_enabled: false,
+            irq_active: false,
+            eeprom: board.eeprom.map(Eeprom::new),
+        };
+        s.reset();
+        s
+    }
+
+    fn register(&mut self, addr: Addr, v: Byte) {
+        match addr.0 & 0x000F {
+            r @ 0x0..=0x7 => self.chr_regs[r as usize] = v.0 as usize,
+            0x8 => self.prg_bank = (v & Byte(0x0F)).0 as usize,
+            0x9 => {
+                self.mirror = match v.0 & 0x03 {
+                    0 => Mirror::Vertical,
+                    1 => Mirror::Horizontal,
+                    2 => Mirror::OneScreenLo,
+                    _ => Mirror::OneScreenHi,
+                }
+            }
+            0xA => {
+                self.irq_enabled = v & Byte(0x01) != Byte(0x00);
+                self.irq_active = false;
+                if self.board.lz93d50 {
+                    self.irq_counter = self.irq_latch;
+                }
+            }
+            0xB => self.set_irq_reload(0xFF00, v.0 as u16),
+            0xC => self.set_irq_reload(0x00FF, (v.0 as u16) << 8),
+            0xD => {
+                self.ram_enabled = v & Byte(0x20) != Byte(0x00);
+                if let Some(ref mut e) = self.eeprom {
+                    let read = v & Byte(0x80) != Byte(0x00);
+                    // While reading the EEPROM drives SDA on its own
+                    let sda = read || v & Byte(0x40) != Byte(0x00);
+                    e.write(v & Byte(0x20) != Byte(0x00), sda);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // FCG-1/2 writes the counter and LZ93D50 the latch,
+    // an unknown board gets both
+    fn set_irq_reload(&mut self, keep: u16, v: u16) {
+        if self.board.lz93d50 {
+            self.irq_latch = (self.irq_latch & keep) | v;
+        }
+        if self.board.fcg {
+            self.irq_counter = (self.irq_counter & keep) | v;
+        }
+    }
+
+    fn map_prg(&self, addr: Addr) -> usize {
+        let prg_banks = self.prg_banks.max(1);
+        // 153 selects the 256 KB half with CHR registers
+        let outer = if self.board.sram {
+            (self.chr_regs[..4].iter().fold(0, |a, r| a | r) & 0x01) << 4
+        } else {
+            0
+        };
+
+        let bank = match addr {
+            Addr(0x8000..=0xBFFF) => outer | self.prg_bank,
+            _ if self.board.sram => outer | 0x0F,
+            _ => prg_banks - 1,
+        };
+
+        (bank % prg_banks) * 0x4000 + (addr & Addr(0x3FFF)).as_usize()
+    }
+
+    fn map_chr(&self, addr: Addr) -> usize {
+        // Count of 1K banks
+        let chr_banks = self.chr_banks.max(1) * 8;
+        let bank = self.chr_regs[(addr.0 >> 10) as usize & 0x07];
+
+        (bank % chr_banks) * 0x0400 + (addr & Addr(0x03FF)).as_usize()
+    }
+}
@@ -0,0 +1,314 @@
+use super::mapper::{Mapped, Mapper};
+use crate::prelude::*;
+
+// Internal RAM shared by save data and the sound channels
+const INTERNAL_RAM_SIZE: usize = 128;
+// CPU cycles between updates of two sound channels
+const CHANNEL_PERIOD: u8 = 15;
+// A full volume channel spans the same range as a full
+// volume pulse channel of the console
+const N163_LEVEL: f32 = 0.11 / 225.0;
+
+// Namco 163
+//
+// CPU Address Bus  Register
+// $4800-$4FFF      Internal RAM data port
+// $5000-$57FF      IRQ counter, low 8 bits
+// $5800-$5FFF      IRQ counter, high 7 bits and IRQ enable (bit 7)
+// $8000-$BFFF      1 KB CHR banks, one per $800. Values $E0-$FF select
+//                  a page of CIRAM unless disabled by $E800
+// $C000-$DFFF      Nametable banks, one per $800. Values $E0-$FF select
+//                  a page of CIRAM, lower values a 1 KB bank of CHR ROM
+// $E000-$E7FF      8 KB PRG ROM bank at $8000, bit 6 disables sound
+// $E800-$EFFF      8 KB PRG ROM bank at $A000
+// 76543210
+// ||||||||
+// ||++++++- Bank
+// |+------- Use CHR ROM for $E0-$FF at $0000-$0FFF
+// +-------- Use CHR ROM for $E0-$FF at $1000-$1FFF
+// $F000-$F7FF      8 KB PRG ROM bank at $C000
+// $F800-$FFFF      Internal RAM address (bits 0-6), auto-increment (bit 7)
+//
+// The IRQ counter counts up every CPU cycle while enabled and raises
+// the IRQ once it reaches $7FFF, where it stops
+//
+// Sound channels live at the top of the internal RAM, 8 bytes each
+// starting from $78 downwards
+// +0: Frequency, low 8 bits
+// +1: Phase, low 8 bits
+// +2: Frequency, middle 8 bits
+// +3: Phase, middle 8 bits
+// +4: Wave length (256 - bits 2-7 in 4 bit samples), frequency, high 2 bits
+// +5: Phase, high 8 bits
+// +6: Wave address in 4 bit samples
+// +7: Volume (low 4 bits). At $7F, bits 4-6 are the count of enabled channels - 1
+// A single channel is updated every 15 CPU cycles and only the channel last
+// updated is heard, so more channels are quieter and buzz at a lower rate
+pub struct Mapper019 {
+    prg_banks: usize,
+    chr_banks: usize,
+
+    prg_banks_8: [usize; 3],
+    chr_regs:    [usize; 8],
+    nt_regs:     [usize; 4],
+    chr_rom_lo:  bool,
+    chr_rom_hi:  bool,
+
+    irq_counter: u16,
+    irq_enabled: bool,
+    irq_active:  bool,
+
+    ram:           [u8; INTERNAL_RAM_SIZE],
+    ram_addr:      usize,
+    ram_increment: bool,
+
+    sound_disabled: bool,
+    channel:        usize,
+    cycles:         u8,
+    sample:         i32,
+}
+
+impl Mapper for Mapper019 {
+    // CPU Address Bus          PRG RAM
+    // 0x6000 -> 0x7FFF: Map    0x0000 -> 0x1FFF
+    // CPU Address Bus          PRG ROM
+    // 0x8000 -> 0xDFFF: Map    3 x 8 KB switchable banks
+    // 0xE000 -> 0xFFFF: Map    8 KB fixed to the last bank
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x4800..=0x4FFF) => {
+                let v = self.ram[self.ram_addr];
+                self.step_ram_addr();
+                Mapped::Data(Byte(v))
+            }
+            Addr(0x5000..=0x57FF) => Mapped::Data(Byte(self.irq_counter as u8)),
+            Addr(0x5800..=0x5FFF) => {
+                let enabled = if self.irq_enabled { 0x80 } else { 0x00 };
+                Mapped::Data(Byte((self.irq_counter >> 8) as u8 | enabled))
+            }
+            Addr(0x6000..=0x7FFF) => Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize()),
+            Addr(0x8000..=0xFFFF) => Mapped::Prg(self.map_prg(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        match addr {
+            Addr(0x4800..=0x4FFF) => {
+                self.ram[self.ram_addr] = v.0;
+                self.step_ram_addr();
+            }
+            Addr(0x5000..=0x57FF) => {
+                self.irq_counter = (self.irq_counter & 0x7F00) | v.0 as u16;
+                self.irq_active = false;
+            }
+            Addr(0x5800..=0x5FFF) => {
+                self.irq_counter = (self.irq_counter & 0x00FF) | ((v.0 as u16 & 0x7F) << 8);
+                self.irq_enabled = v & Byte(0x80) != Byte(0x00);
+                self.irq_active = false;
+            }
+            Addr(0x6000..=0x7FFF) => return Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize()),
+            Addr(0x8000..=0xBFFF) => self.chr_regs[((addr.0 >> 11) & 0x07) as usize] = v.0 as usize,
+            Addr(0xC000..=0xDFFF) => self.nt_regs[((addr.0 >> 11) & 0x03) as usize] = v.0 as usize,
+            Addr(0xE000..=0xE7FF) => {
+                self.prg_banks_8[0] = (v & Byte(0x3F)).0 as usize;
+                self.sound_disabled = v & Byte(0x40) != Byte(0x00);
+            }
+            Addr(0xE800..=0xEFFF) => {
+                self.prg_banks_8[1] = (v & Byte(0x3F)).0 as usize;
+                self.chr_rom_lo = v & Byte(0x40) != Byte(0x00);
+                self.chr_rom_hi = v & Byte(0x80) != Byte(0x00);
+            }
+            Addr(0xF000..=0xF7FF) => self.prg_banks_8[2] = (v & Byte(0x3F)).0 as usize,
+            Addr(0xF800..=0xFFFF) => {
+                self.ram_addr = (v & Byte(0x7F)).0 as usize;
+                self.ram_increment = v & Byte(0x80) != Byte(0x00);
+            }
+            _ => return Mapped::Unmapped,
+        }
+
+        // Registers are not backed by memory
+        Mapped::Data(v)
+    }
+
+    // PPU Address Bus          CHR ROM or CIRAM
+    // 0x0000 -> 0x1FFF: Map    8 x 1 KB switchable banks
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) => {
+                let bank = self.chr_regs[(addr.0 >> 10) as usize & 0x07];
+                let chr_rom = if addr.0 < 0x1000 {
+                    self.chr_rom_lo
+                } else {
+                    self.chr_rom_hi
+                };
+
+                if bank >= 0xE0 && !chr_rom {
+                    Mapped::CiRam(Self::map_ciram(bank, addr))
+                } else {
+                    Mapped::Chr(self.map_chr(bank, addr))
+                }
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land on CIRAM or when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
+    }
+
+    // PPU Address Bus          CHR ROM or CIRAM
+    // 0x2000 -> 0x2FFF: Map    4 x 1 KB switchable banks
+    fn read_nametable(&mut self, addr: Addr) -> Mapped {
+        let bank = self.nt_regs[(addr.0 >> 10) as usize & 0x03];
+        if bank >= 0xE0 {
+            Mapped::CiRam(Self::map_ciram(bank, addr))
+        } else {
+            Mapped::Chr(self.map_chr(bank, addr))
+        }
+    }
+
+    // Nametables in CHR ROM silently ignore writes
+    fn write_nametable(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_nametable(addr)
+    }
+
+    fn cpu_tick(&mut self) {
+        if self.irq_enabled && self.irq_counter < 0x7FFF {
+            self.irq_counter += 1;
+            if self.irq_counter == 0x7FFF {
+                self.irq_active = true;
+            }
+        }
+
+        self.cycles += 1;
+        if self.cycles == CHANNEL_PERIOD {
+            self.cycles = 0;
+            self.update_channel();
+        }
+    }
+
+    fn audio(&self) -> f32 {
+        self.sample as f32 * N163_LEVEL
+    }
+
+    fn battery_ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    fn load_battery_ram(&mut self, data: &[u8]) {
+        for (cell, v) in self.ram.iter_mut().zip(data) {
+            *cell = *v;
+        }
+    }
+
+    fn has_irq(&self) -> bool {
+        self.irq_active
+    }
+
+    fn clear_irq(&mut self) {
+        self.irq_active = false;
+    }
+
+    // The internal RAM keeps its contents
+    fn reset(&mut self) {
+        self.prg_banks_8 = [0; 3];
+        self.chr_regs = [0; 8];
+        self.nt_regs = [0; 4];
+        self.chr_rom_lo = false;
+        self.chr_rom_hi = false;
+
+        self.irq_counter = 0;
+        self.irq_enabled = false;
+        self.irq_active = false;
+
+        self.ram_addr = 0;
+        self.ram_increment = false;
+
+        self.sound_disabled = false;
+        self.channel = 0;
+        self.cycles = 0;
+        self.sample = 0;
+    }
+}
+
+impl Mapper019 {
+    pub fn new(prg_banks: usize, chr_banks: usize) -> Self {
+        let mut s = Self {
+            prg_banks,
+            chr_banks,
+            prg_banks_8: [0; 3],
+            chr_regs: [0; 8],
+            nt_regs: [0; 4],
+            chr_rom_lo: false,
+            chr_rom_hi: false,
+            irq_counter: 0,
+            irq_enabled: false,
+            irq_active: false,
+            ram: [0; INTERNAL_RAM_SIZE],
+            ram_addr: 0,
+            ram_increment: false,
+            sound_disabled: false,
+            channel: 0,
+            cycles: 0,
+            sample: 0,
+        };
+        s.reset();
+        s
+    }
+
+    fn step_ram_addr(&mut self) {
+        if self.ram_increment {
+            self.ram_addr = (self.ram_addr + 1) & 0x7F;
+        }
+    }
+
+    // Advances the next enabled channel and makes it the one heard
+    fn update_channel(&mut self) {
+        if self.sound_disabled {
+            self.sample = 0;
+            return;
+        }
+
+        let enabled = ((self.ram[0x7F] >> 4) & 0x07) as usize + 1;
+        self.channel = (self.channel + 1) % enabled;
+
+        let base = 0x78 - self.channel * 8;
+        let reg = |i: usize| self.ram[base + i] as u32;
+
+        let freq = reg(0) | (reg(2) << 8) | ((reg(4) & 0x03) << 16);
+        let length = (256 - (reg(4) & 0xFC)) << 16;
+        let mut phase = reg(1) | (reg(3) << 8) | (reg(5) << 16);
+        phase = (phase + freq) % length;
+
+        let index = (((phase >> 16) + reg(6)) & 0xFF) as usize;
+        let nibble = (self.ram[index >> 1] >> ((index & 0x01) * 4)) & 0x0F;
+        self.sample = (nibble as i32 - 8) * (reg(7) & 0x0F) as i32;
+
+        self.ram[base + 1] = phase as u8;
+        self.ram[base + 3] = (phase >> 8) as u8;
+        self.ram[base + 5] = (phase >> 16) as u8;
+    }
+
+    fn map_prg(&self, addr: Addr) -> usize {
+        // Count of 8K banks
+        let prg_banks = self.prg_banks.max(1) * 2;
+        let bank = match addr {
+            Addr(0xE000..=0xFFFF) => prg_banks - 1,
+            _ => self.prg_banks_8[((addr.0 >> 13) & 0x03) as usize],
+        };
+
+        (bank % prg_banks) * 0x2000 + (addr & Addr(0x1FFF)).as_usize()
+    }
+
+    fn map_chr(&self, bank: usize, addr: Addr) -> usize {
+        // Count of 1K banks
+        let chr_banks = self.chr_banks.max(1) * 8;
+        (bank % chr_banks) * 0x0400 + (addr & Addr(0x03FF)).as_usize()
+    }
+
+    fn map_ciram(bank: usize, addr: Addr) -> usize {
+        ((bank & 0x01) << 10) | (addr & Addr(0x03FF)).as_usize()
+    }
+}
@@ -0,0 +1,334 @@
+use super::mapper::{Mapped, Mapper};
+use crate::cartridge::{CartridgeHeader, Mirror};
+use crate::prelude::*;
+
+// Konami VRC2 / VRC4 (mappers 21, 22, 23 and 25)
+//
+// Boards connect the two register select lines of the chip to different
+// CPU address lines, so the same register lives at different addresses:
+//     Mapper  Submapper  Board  Lines
+//     21      1          VRC4a  A1, A2
+//     21      2          VRC4c  A6, A7
+//     22      0          VRC2a  A1, A0 (CHR banks ignore the lowest bit)
+//     23      1          VRC4f  A0, A1
+//     23      2          VRC4e  A2, A3
+//     23      3          VRC2b  A0, A1
+//     25      1          VRC4b  A1, A0
+//     25      2          VRC4d  A3, A2
+//     25      3          VRC2c  A1, A0
+// Without a submapper both wirings of the mapper are decoded at once, which
+// is harmless since games only ever write to one of them, and the board is
+// treated as VRC4 as it is a superset of VRC2.
+//
+// CPU Address Bus  Register (after decoding the select lines)
+// $8000-$8003      PRG bank at $8000 (or $C000 in swap mode)
+// $9000-$9001      Mirroring (VRC2: 0: vertical; 1: horizontal,
+//                             VRC4: 0: vertical; 1: horizontal; 2: one-screen lower; 3: one-screen upper)
+// $9002            PRG swap mode (VRC4 only, bit 1)
+// $A000-$A003      PRG bank at $A000
+// $B000-$E003      CHR banks, two registers per 1 KB bank with the low 4 bits
+//                  and the high 4 bits (5 bits on VRC4)
+// $F000            IRQ latch, low 4 bits (VRC4 only)
+// $F001            IRQ latch, high 4 bits
+// $F002            IRQ control
+// 76543210
+//      |||
+//      ||+- Enable IRQ after acknowledgement
+//      |+-- Enable IRQ
+//      +--- Mode (0: scanline; 1: CPU cycle)
+// $F003            IRQ acknowledge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VrcBoard {
+    pub vrc4:      bool,
+    // Masks of CPU address lines wired to the register select lines
+    pub a0:        u16,
+    pub a1:        u16,
+    // VRC2a drops the lowest CHR bank bit
+    pub chr_shift: u8,
+}
+
+impl VrcBoard {
+    pub fn new(vrc4: bool, a0: u16, a1: u16) -> Self {
+        Self {
+            vrc4,
+            a0,
+            a1,
+            chr_shift: 0,
+        }
+    }
+
+    // Picks the board from the mapper and submapper of the header
+    pub fn from_header(header: &CartridgeHeader) -> Self {
+        match (header.mapper, header.submapper) {
+            (21, 1) => Self::new(true, 0x02, 0x04),
+            (21, 2) => Self::new(true, 0x40, 0x80),
+            (21, _) => Self::new(true, 0x02 | 0x40, 0x04 | 0x80),
+            (22, _) => Self {
+                chr_shift: 1,
+                ..Self::new(false, 0x02, 0x01)
+            },
+            (23, 1) => Self::new(true, 0x01, 0x02),
+            (23, 2) => Self::new(true, 0x04, 0x08),
+            (23, 3) => Self::new(false, 0x01, 0x02),
+            (23, _) => Self::new(true, 0x01 | 0x04, 0x02 | 0x08),
+            (25, 1) => Self::new(true, 0x02, 0x01),
+            (25, 2) => Self::new(true, 0x08, 0x04),
+            (25, 3) => Self::new(false, 0x02, 0x01),
+            _ => Self::new(true, 0x02 | 0x08, 0x01 | 0x04),
+        }
+    }
+}
+
+pub struct Mapper021 {
+    prg_banks: usize,
+    chr_banks: usize,
+    board:     VrcBoard,
+
+    prg_bank_0: usize,
+    prg_bank_1: usize,
+    prg_swap:   bool,
+    chr_regs:   [usize; 8],
+    mirror:     Mirror,
+
+    irq: VrcIrq,
+}
+
+impl Mapper for Mapper021 {
+    // CPU Address Bus          PRG RAM
+    // 0x6000 -> 0x7FFF: Map    0x0000 -> 0x1FFF
+    // CPU Address Bus          PRG ROM
+    // 0x8000 -> 0x9FFF: Map    8 KB switchable or fixed to second-last bank
+    // 0xA000 -> 0xBFFF: Map    8 KB switchable
+    // 0xC000 -> 0xDFFF: Map    8 KB fixed to second-last bank or switchable
+    // 0xE000 -> 0xFFFF: Map    8 KB fixed to last bank
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) => Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize()),
+            Addr(0x8000..=0xFFFF) => Mapped::Prg(self.map_prg(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        let reg = self.register(addr);
+
+        match (addr.0 & 0xF000, reg) {
+            (0x6000..=0x7000, _) => return Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize()),
+            (0x8000, _) => self.prg_bank_0 = (v & Byte(0x1F)).0 as usize,
+            (0x9000, _) if !self.board.vrc4 => {
+                self.mirror = Self::mirror_from(v & Byte(0x01));
+            }
+            (0x9000, 0) | (0x9000, 1) => self.mirror = Self::mirror_from(v & Byte(0x03)),
+            (0x9000, 2) => self.prg_swap = v & Byte(0x02) != Byte(0x00),
+            (0xA000, _) => self.prg_bank_1 = (v & Byte(0x1F)).0 as usize,
+            (0xB000..=0xE000, _) => {
+                // Two 1 KB banks per register group, each split in two nibbles
+                let bank = (((addr.0 >> 12) - 0x0B) * 2) as usize + (reg >> 1);
+                let v = v.0 as usize;
+                self.chr_regs[bank] = if reg & 0x01 == 0 {
+                    (self.chr_regs[bank] & 0x1F0) | (v & 0x0F)
+                } else {
+                    (self.chr_regs[bank] & 0x00F) | ((v & 0x1F) << 4)
+                };
+            }
+            (0xF000, 0) if self.board.vrc4 => {
+                self.irq.latch = (self.irq.latch & Byte(0xF0)) | (v & Byte(0x0F));
+            }
+            (0xF000, 1) if self.board.vrc4 => {
+                self.irq.latch = (self.irq.latch & Byte(0x0F)) | ((v & Byte(0x0F)) << 4);
+            }
+            (0xF000, 2) if self.board.vrc4 => self.irq.control(v),
+            (0xF000, 3) if self.board.vrc4 => self.irq.acknowledge(),
+            _ => return Mapped::Unmapped,
+        }
+
+        // Registers are not backed by memory
+        Mapped::Data(v)
+    }
+
+    // PPU Address Bus          CHR ROM
+    // 0x0000 -> 0x1FFF: Map    8 x 1 KB switchable banks
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) => Mapped::Chr(self.map_chr(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
+    }
+
+    fn cpu_tick(&mut self) {
+        self.irq.tick();
+    }
+
+    fn mirror(&self) -> Mirror {
+        self.mirror
+    }
+
+    fn has_irq(&self) -> bool {
+        self.irq.active
+    }
+
+    fn clear_irq(&mut self) {
+        self.irq.active = false;
+    }
+
+    fn reset(&mut self) {
+        self.prg_bank_0 = 0;
+        self.prg_bank_1 = 0;
+        self.prg_swap = false;
+        self.chr_regs = [0; 8];
+        self.mirror = Mirror::Vertical;
+        self.irq.reset();
+    }
+}
+
+impl Mapper021 {
+    pub fn new(prg_banks: usize, chr_banks: usize, board: VrcBoard) -> Self {
+        let mut s = Self {
+            prg_banks,
+            chr_banks,
+            board,
+            prg_bank_0: 0,
+            prg_bank_1: 0,
+            prg_swap: false,
+            chr_regs: [0; 8],
+            mirror: Mirror::Vertical,
+            irq: VrcIrq::new(),
+        };
+        s.reset();
+        s
+    }
+
+    // Decodes the register select lines into register 0-3
+    fn register(&self, addr: Addr) -> usize {
+        let mut reg = 0;
+        if addr.0 & self.board.a0 != 0 {
+            reg |= 0x01;
+        }
+        if addr.0 & self.board.a1 != 0 {
+            reg |= 0x02;
+        }
+        reg
+    }
+
+    fn mirror_from(v: Byte) -> Mirror {
+        match v.0 {
+            0 => Mirror::Vertical,
+            1 => Mirror::Horizontal,
+            2 => Mirror::OneScreenLo,
+            _ => Mirror::OneScreenHi,
+        }
+    }
+
+    fn map_prg(&self, addr: Addr) -> usize {
+        // Count of 8K banks
+        let prg_banks = self.prg_banks.max(1) * 2;
+        let second_last = prg_banks.max(2) - 2;
+
+        let bank = match (addr.0 >> 13) & 0x03 {
+            0 if self.prg_swap => second_last,
+            0 => self.prg_bank_0,
+            1 => self.prg_bank_1,
+            2 if self.prg_swap => self.prg_bank_0,
+            2 => second_last,
+            _ => prg_banks - 1,
+        };
+
+        (bank % prg_banks) * 0x2000 + (addr & Addr(0x1FFF)).as_usize()
+    }
+
+    fn map_chr(&self, addr: Addr) -> usize {
+        // Count of 1K banks
+        let chr_banks = self.chr_banks.max(1) * 8;
+        let bank = self.chr_regs[(addr.0 >> 10) as usize & 0x07] >> self.board.chr_shift;
+
+        (bank % chr_banks) * 0x0400 + (addr & Addr(0x03FF)).as_usize()
+    }
+}
+
+// IRQ counter shared by VRC4, VRC6 and VRC7. In scanline mode the
+// prescaler divides CPU cycles by 113.667, in cycle mode the counter
+// is clocked by every CPU cycle
+pub(super) struct VrcIrq {
+    pub(super) latch:  Byte,
+    pub(super) active: bool,
+
+    counter:    Byte,
+    prescaler:  i16,
+    enabled:    bool,
+    enable_ack: bool,
+    cycle_mode: bool,
+}
+
+impl VrcIrq {
+    pub(super) fn new() -> Self {
+        let mut s = Self {
+            latch:      Byte(0x00),
+            active:     false,
+            counter:    Byte(0x00),
+            prescaler:  341,
+            enabled:    false,
+            enable_ack: false,
+            cycle_mode: false,
+        };
+        s.reset();
+        s
+    }
+
+    pub(super) fn reset(&mut self) {
+        self.latch = Byte(0x00);
+        self.active = false;
+        self.counter = Byte(0x00);
+        self.prescaler = 341;
+        self.enabled = false;
+        self.enable_ack = false;
+        self.cycle_mode = false;
+    }
+
+    // IRQ control register
+    pub(super) fn control(&mut self, v: Byte) {
+        self.enable_ack = v & Byte(0x01) != Byte(0x00);
+        self.enabled = v & Byte(0x02) != Byte(0x00);
+        self.cycle_mode = v & Byte(0x04) != Byte(0x00);
+        self.active = false;
+        if self.enabled {
+            self.counter = self.latch;
+            self.prescaler = 341;
+        }
+    }
+
+    // IRQ acknowledge register
+    pub(super) fn acknowledge(&mut self) {
+        self.active = false;
+        self.enabled = self.enable_ack;
+    }
+
+    pub(super) fn tick(&mut self) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.cycle_mode {
+            self.clock();
+        } else {
+            self.prescaler -= 3;
+            if self.prescaler <= 0 {
+                self.prescaler += 341;
+                self.clock();
+            }
+        }
+    }
+
+    fn clock(&mut self) {
+        if self.counter == Byte(0xFF) {
+            self.counter = self.latch;
+            self.active = true;
+        } else {
+            self.counter.inc();
+        }
+    }
+}
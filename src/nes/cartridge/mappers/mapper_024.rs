@@ -0,0 +1,369 @@
+use super::mapper::{Mapped, Mapper};
+use super::VrcIrq;
+use crate::cartridge::Mirror;
+use crate::prelude::*;
+
+// Output of the channels has the same weight per volume step
+// as the pulse channels of the console
+const VRC6_LEVEL: f32 = 0.00752;
+
+// Konami VRC6a
+//
+// CPU Address Bus  Register
+// $8000-$8003      16 KB PRG ROM bank at $8000
+// $9000-$9002      Pulse 1
+// $9003            Audio control
+// 76543210
+//      |||
+//      ||+- Halt all channels
+//      |+-- Frequencies are shifted right by 4
+//      +--- Frequencies are shifted right by 8
+// $A000-$A002      Pulse 2
+// $B000-$B002      Sawtooth
+// $B003            PPU banking style
+// 76543210
+// |   ||||
+// |   ||++- PPU banking mode (only mode 0 used by the released games)
+// |   ++--- Mirroring (0: vertical; 1: horizontal; 2: one-screen lower; 3: one-screen upper)
+// +-------- PRG RAM enable
+// $C000-$C003      8 KB PRG ROM bank at $C000
+// $D000-$E003      1 KB CHR ROM banks
+// $F000            IRQ latch
+// $F001            IRQ control
+// $F002            IRQ acknowledge
+//
+// Registers are selected with A0 and A1, VRC6b (mapper 26) swaps these lines
+pub struct Mapper024 {
+    prg_banks: usize,
+    chr_banks: usize,
+
+    prg_bank_16: usize,
+    prg_bank_8:  usize,
+    chr_banks_1: [usize; 8],
+    mirror:      Mirror,
+    ram_enabled: bool,
+    irq:         VrcIrq,
+
+    halt:    bool,
+    shift:   u16,
+    pulse_1: Vrc6Pulse,
+    pulse_2: Vrc6Pulse,
+    saw:     Vrc6Saw,
+}
+
+impl Mapper for Mapper024 {
+    // CPU Address Bus          PRG RAM
+    // 0x6000 -> 0x7FFF: Map    0x0000 -> 0x1FFF
+    // CPU Address Bus          PRG ROM
+    // 0x8000 -> 0xBFFF: Map    16 KB switchable bank
+    // 0xC000 -> 0xDFFF: Map    8 KB switchable bank
+    // 0xE000 -> 0xFFFF: Map    8 KB fixed to the last bank
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) if self.ram_enabled => {
+                Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize())
+            }
+            Addr(0x8000..=0xFFFF) => Mapped::Prg(self.map_prg(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        let reg = (addr.0 & 0x03) as usize;
+
+        match (addr.0 & 0xF000, reg) {
+            (0x6000..=0x7000, _) if self.ram_enabled => {
+                return Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize());
+            }
+            (0x8000, _) => self.prg_bank_16 = (v & Byte(0x0F)).0 as usize,
+            (0x9000, 3) => {
+                self.halt = v & Byte(0x01) != Byte(0x00);
+                self.shift = match v.0 & 0x06 {
+                    0x00 => 0,
+                    0x02 => 4,
+                    _ => 8,
+                };
+            }
+            (0x9000, _) => self.pulse_1.write(reg, v),
+            (0xA000, 3) => {}
+            (0xA000, _) => self.pulse_2.write(reg, v),
+            (0xB000, 3) => {
+                self.mirror = match (v.0 >> 2) & 0x03 {
+                    0 => Mirror::Vertical,
+                    1 => Mirror::Horizontal,
+                    2 => Mirror::OneScreenLo,
+                    _ => Mirror::OneScreenHi,
+                };
+                self.ram_enabled = v & Byte(0x80) != Byte(0x00);
+            }
+            (0xB000, _) => self.saw.write(reg, v),
+            (0xC000, _) => self.prg_bank_8 = (v & Byte(0x1F)).0 as usize,
+            (0xD000, _) => self.chr_banks_1[reg] = v.0 as usize,
+            (0xE000, _) => self.chr_banks_1[4 + reg] = v.0 as usize,
+            (0xF000, 0) => self.irq.latch = v,
+            (0xF000, 1) => self.irq.control(v),
+            (0xF000, 2) => self.irq.acknowledge(),
+            _ => return Mapped::Unmapped,
+        }
+
+        // Registers are not backed by memory
+        Mapped::Data(v)
+    }
+
+    // PPU Address Bus          CHR ROM
+    // 0x0000 -> 0x1FFF: Map    8 x 1 KB switchable banks
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) => Mapped::Chr(self.map_chr(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
+    }
+
+    fn cpu_tick(&mut self) {
+        self.irq.tick();
+
+        if !self.halt {
+            self.pulse_1.tick(self.shift);
+            self.pulse_2.tick(self.shift);
+            self.saw.tick(self.shift);
+        }
+    }
+
+    fn audio(&self) -> f32 {
+        let level = self.pulse_1.output() + self.pulse_2.output() + self.saw.output();
+        level as f32 * VRC6_LEVEL
+    }
+
+    fn mirror(&self) -> Mirror {
+        self.mirror
+    }
+
+    fn has_irq(&self) -> bool {
+        self.irq.active
+    }
+
+    fn clear_irq(&mut self) {
+        self.irq.active = false;
+    }
+
+    fn reset(&mut self) {
+        self.prg_bank_16 = 0;
+        self.prg_bank_8 = 0;
+        self.chr_banks_1 = [0; 8];
+        self.mirror = Mirror::Vertical;
+        self.ram_enabled = false;
+        self.irq.reset();
+
+        self.halt = false;
+        self.shift = 0;
+        self.pulse_1.reset();
+        self.pulse_2.reset();
+        self.saw.reset();
+    }
+}
+
+impl Mapper024 {
+    pub fn new(prg_banks: usize, chr_banks: usize) -> Self {
+        let mut s = Self {
+            prg_banks,
+            chr_banks,
+            prg_bank_16: 0,
+            prg_bank_8: 0,
+            chr_banks_1: [0; 8],
+            mirror: Mirror::Vertical,
+            ram_enabled: false,
+            irq: VrcIrq::new(),
+            halt: false,
+            shift: 0,
+            pulse_1: Vrc6Pulse::new(),
+            pulse_2: Vrc6Pulse::new(),
+            saw: Vrc6Saw::new(),
+        };
+        s.reset();
+        s
+    }
+
+    fn map_prg(&self, addr: Addr) -> usize {
+        // Count of 8K banks
+        let prg_banks = self.prg_banks.max(1) * 2;
+        let bank = match addr {
+            Addr(0x8000..=0xBFFF) => self.prg_bank_16 * 2 + ((addr.0 >> 13) & 0x01) as usize,
+            Addr(0xC000..=0xDFFF) => self.prg_bank_8,
+            _ => prg_banks - 1,
+        };
+
+        (bank % prg_banks) * 0x2000 + (addr & Addr(0x1FFF)).as_usize()
+    }
+
+    fn map_chr(&self, addr: Addr) -> usize {
+        // Count of 1K banks
+        let chr_banks = self.chr_banks.max(1) * 8;
+        let bank = self.chr_banks_1[(addr.0 >> 10) as usize & 0x07];
+
+        (bank % chr_banks) * 0x0400 + (addr & Addr(0x03FF)).as_usize()
+    }
+}
+
+// Pulse channel with 16 step duty cycle
+// $x000: MDDD VVVV  Mode (ignore duty), duty, volume
+// $x001: FFFF FFFF  Frequency, low 8 bits
+// $x002: E... FFFF  Enable, frequency, high 4 bits
+struct Vrc6Pulse {
+    volume:  u8,
+    duty:    u8,
+    mode:    bool,
+    enabled: bool,
+    period:  u16,
+    divider: u16,
+    step:    u8,
+}
+
+impl Vrc6Pulse {
+    fn new() -> Self {
+        let mut s = Self {
+            volume:  0,
+            duty:    0,
+            mode:    false,
+            enabled: false,
+            period:  0,
+            divider: 0,
+            step:    15,
+        };
+        s.reset();
+        s
+    }
+
+    fn reset(&mut self) {
+        self.volume = 0;
+        self.duty = 0;
+        self.mode = false;
+        self.enabled = false;
+        self.period = 0;
+        self.divider = 0;
+        self.step = 15;
+    }
+
+    fn write(&mut self, reg: usize, v: Byte) {
+        match reg {
+            0 => {
+                self.mode = v & Byte(0x80) != Byte(0x00);
+                self.duty = (v.0 >> 4) & 0x07;
+                self.volume = v.0 & 0x0F;
+            }
+            1 => self.period = (self.period & 0x0F00) | v.0 as u16,
+            _ => {
+                self.period = (self.period & 0x00FF) | ((v.0 as u16 & 0x0F) << 8);
+                self.enabled = v & Byte(0x80) != Byte(0x00);
+                // Disabling the channel restarts the duty cycle
+                if !self.enabled {
+                    self.step = 15;
+                }
+            }
+        }
+    }
+
+    fn tick(&mut self, shift: u16) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.divider == 0 {
+            self.divider = self.period >> shift;
+            self.step = self.step.wrapping_sub(1) & 0x0F;
+        } else {
+            self.divider -= 1;
+        }
+    }
+
+    fn output(&self) -> u8 {
+        if self.enabled && (self.mode || self.step <= self.duty) {
+            self.volume
+        } else {
+            0
+        }
+    }
+}
+
+// Sawtooth channel
+// $B000: ..AA AAAA  Accumulator rate
+// $B001: FFFF FFFF  Frequency, low 8 bits
+// $B002: E... FFFF  Enable, frequency, high 4 bits
+//
+// The rate is added to the accumulator on every second clock of the
+// divider, and the accumulator is cleared on the 14th clock
+struct Vrc6Saw {
+    rate:        u8,
+    enabled:     bool,
+    period:      u16,
+    divider:     u16,
+    step:        u8,
+    accumulator: u8,
+}
+
+impl Vrc6Saw {
+    fn new() -> Self {
+        let mut s = Self {
+            rate:        0,
+            enabled:     false,
+            period:      0,
+            divider:     0,
+            step:        0,
+            accumulator: 0,
+        };
+        s.reset();
+        s
+    }
+
+    fn reset(&mut self) {
+        self.rate = 0;
+        self.enabled = false;
+        self.period = 0;
+        self.divider = 0;
+        self.step = 0;
+        self.accumulator = 0;
+    }
+
+    fn write(&mut self, reg: usize, v: Byte) {
+        match reg {
+            0 => self.rate = v.0 & 0x3F,
+            1 => self.period = (self.period & 0x0F00) | v.0 as u16,
+            _ => {
+                self.period = (self.period & 0x00FF) | ((v.0 as u16 & 0x0F) << 8);
+                self.enabled = v & Byte(0x80) != Byte(0x00);
+                if !self.enabled {
+                    self.step = 0;
+                    self.accumulator = 0;
+                }
+            }
+        }
+    }
+
+    fn tick(&mut self, shift: u16) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.divider == 0 {
+            self.divider = self.period >> shift;
+            self.step += 1;
+            if self.step == 14 {
+                self.step = 0;
+                self.accumulator = 0;
+            } else if self.step & 0x01 == 0 {
+                self.accumulator = self.accumulator.wrapping_add(self.rate);
+            }
+        } else {
+            self.divider -= 1;
+        }
+    }
+
+    // Top 5 bits of the accumulator
+    fn output(&self) -> u8 {
+        self.accumulator >> 3
+    }
+}
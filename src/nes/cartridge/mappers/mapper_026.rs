@@ -0,0 +1,70 @@
+use super::mapper::{Mapped, Mapper};
+use super::Mapper024;
+use crate::cartridge::Mirror;
+use crate::prelude::*;
+
+// Konami VRC6b
+//
+// The same chip as VRC6a (mapper 24) with the register select
+// lines A0 and A1 swapped by the board. PRG RAM at $6000-$7FFF
+// sees the address as is
+pub struct Mapper026 {
+    vrc6: Mapper024,
+}
+
+impl Mapper for Mapper026 {
+    fn read(&mut self, addr: Addr) -> Mapped {
+        self.vrc6.read(addr)
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        match addr {
+            Addr(0x8000..=0xFFFF) => {
+                let swapped =
+                    (addr.0 & !0x0003) | ((addr.0 & 0x0001) << 1) | ((addr.0 & 0x0002) >> 1);
+                self.vrc6.write(Addr(swapped), v)
+            }
+            _ => self.vrc6.write(addr, v),
+        }
+    }
+
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        self.vrc6.read_chr(addr)
+    }
+
+    fn write_chr(&mut self, addr: Addr, v: Byte) -> Mapped {
+        self.vrc6.write_chr(addr, v)
+    }
+
+    fn cpu_tick(&mut self) {
+        self.vrc6.cpu_tick();
+    }
+
+    fn audio(&self) -> f32 {
+        self.vrc6.audio()
+    }
+
+    fn mirror(&self) -> Mirror {
+        self.vrc6.mirror()
+    }
+
+    fn has_irq(&self) -> bool {
+        self.vrc6.has_irq()
+    }
+
+    fn clear_irq(&mut self) {
+        self.vrc6.clear_irq();
+    }
+
+    fn reset(&mut self) {
+        self.vrc6.reset();
+    }
+}
+
+impl Mapper026 {
+    pub fn new(prg_banks: usize, chr_banks: usize) -> Self {
+        Self {
+            vrc6: Mapper024::new(prg_banks, chr_banks),
+        }
+    }
+}
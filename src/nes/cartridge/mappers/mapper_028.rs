@@ -0,0 +1,157 @@
+use super::mapper::{Mapped, Mapper};
+use crate::cartridge::Mirror;
+use crate::prelude::*;
+
+// Action 53
+//
+// CPU Address Bus  Register
+// $5000-$5FFF      Register select
+// $8000-$FFFF      Register data
+//
+// Register select
+// 76543210
+// |      |
+// +------+- Register (0x00: CHR bank; 0x01: inner bank; 0x80: mode; 0x81: outer bank)
+//
+// CHR bank ($00) and inner bank ($01)
+// 76543210
+//    |||||
+//    ||||+- CHR bank: 8 KB CHR RAM bank, bits 0-1
+//    ++++-- Inner bank: 16 or 32 KB PRG ROM bank within the game, bits 0-3
+//    +----- Both: Select the one-screen page while mirroring is one-screen
+//
+// Mode ($80)
+// 76543210
+//   ||||||
+//   ||||++- Mirroring (0: one-screen lower; 1: one-screen upper; 2: vertical; 3: horizontal)
+//   ||++--- PRG ROM mode (0, 1: 32 KB; 2: $8000 fixed to the first half of the
+//   ||                   outer bank; 3: $C000 fixed to the second half)
+//   ++----- Game size, the inner bank replaces 1 to 4 low bits of the outer bank
+//           (0: 32 KB; 1: 64 KB; 2: 128 KB; 3: 256 KB)
+//
+// Outer bank ($81) selects 32 KB of PRG ROM, power-up selects the last
+pub struct Mapper028 {
+    prg_banks: usize,
+    chr_banks: usize,
+
+    register:   u8,
+    chr_bank:   usize,
+    inner_bank: usize,
+    outer_bank: usize,
+    mode:       u8,
+}
+
+impl Mapper for Mapper028 {
+    // CPU Address Bus          PRG RAM
+    // 0x6000 -> 0x7FFF: Map    0x0000 -> 0x1FFF
+    // CPU Address Bus          PRG ROM
+    // 0x8000 -> 0xFFFF: Map    32 KB or 2 x 16 KB banks, by mode
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) => Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize()),
+            Addr(0x8000..=0xFFFF) => Mapped::Prg(self.map_prg(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        match addr {
+            Addr(0x5000..=0x5FFF) => self.register = v.0 & 0x81,
+            Addr(0x6000..=0x7FFF) => return Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize()),
+            Addr(0x8000..=0xFFFF) => self.data(v),
+            _ => return Mapped::Unmapped,
+        }
+
+        // Registers are not backed by memory
+        Mapped::Data(v)
+    }
+
+    // PPU Address Bus          CHR RAM
+    // 0x0000 -> 0x1FFF: Map    8 KB switchable bank
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) => Mapped::Chr(self.map_chr(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
+    }
+
+    fn mirror(&self) -> Mirror {
+        match self.mode & 0x03 {
+            0 => Mirror::OneScreenLo,
+            1 => Mirror::OneScreenHi,
+            2 => Mirror::Vertical,
+            _ => Mirror::Horizontal,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.register = 0;
+        self.chr_bank = 0;
+        self.inner_bank = 0;
+        self.outer_bank = 0xFF;
+        self.mode = 0;
+    }
+}
+
+impl Mapper028 {
+    pub fn new(prg_banks: usize, chr_banks: usize) -> Self {
+        let mut s = Self {
+            prg_banks,
+            chr_banks,
+            register: 0,
+            chr_bank: 0,
+            inner_bank: 0,
+            outer_bank: 0xFF,
+            mode: 0,
+        };
+        s.reset();
+        s
+    }
+
+    fn data(&mut self, v: Byte) {
+        // Writing bit 4 to $00 or $01 picks the page while one-screen
+        if self.register <= 0x01 && self.mode & 0x02 == 0 {
+            self.mode = (self.mode & !0x01) | ((v.0 >> 4) & 0x01);
+        }
+
+        match self.register {
+            0x00 => self.chr_bank = (v & Byte(0x03)).0 as usize,
+            0x01 => self.inner_bank = (v & Byte(0x0F)).0 as usize,
+            0x80 => self.mode = v.0 & 0x3F,
+            _ => self.outer_bank = v.0 as usize,
+        }
+    }
+
+    fn map_prg(&self, addr: Addr) -> usize {
+        // Count of 16K banks
+        let prg_banks = self.prg_banks.max(1);
+        let a14 = ((addr.0 >> 14) & 0x01) as usize;
+        let bank_mode = ((self.mode >> 2) & 0x03) as usize;
+        let game_size = ((self.mode >> 4) & 0x03) as usize;
+
+        let bank = if (bank_mode ^ a14) == 0x02 {
+            // The fixed half of UNROM-like modes
+            (self.outer_bank << 1) | a14
+        } else {
+            let inner = if bank_mode & 0x02 != 0 {
+                self.inner_bank
+            } else {
+                (self.inner_bank << 1) | a14
+            };
+            let mask = (0x02 << game_size) - 1;
+            ((self.outer_bank << 1) & !mask) | (inner & mask)
+        };
+
+        (bank % prg_banks) * 0x4000 + (addr & Addr(0x3FFF)).as_usize()
+    }
+
+    fn map_chr(&self, addr: Addr) -> usize {
+        let chr_banks = self.chr_banks.max(1);
+        (self.chr_bank % chr_banks) * 0x2000 + (addr & Addr(0x1FFF)).as_usize()
+    }
+}
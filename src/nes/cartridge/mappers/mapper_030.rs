@@ -0,0 +1,158 @@
+use super::discrete::{map_chr, map_prg};
+use super::mapper::{Mapped, Mapper};
+use crate::cartridge::{CartridgeHeader, Mirror};
+use crate::prelude::*;
+
+// Nametable arrangement of UNROM-512, chosen by bits 3 and 0 of flags 6
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unrom512Mirror {
+    Horizontal,
+    Vertical,
+    // Switched by bit 7 of the bank select
+    OneScreen,
+    // Nametables live in the last 8 KB of CHR RAM
+    FourScreen,
+}
+
+impl Unrom512Mirror {
+    pub fn from_header(header: &CartridgeHeader) -> Self {
+        match (header.mirror, header.mirror_bit) {
+            (Mirror::FourScreen, false) => Unrom512Mirror::OneScreen,
+            (Mirror::FourScreen, true) => Unrom512Mirror::FourScreen,
+            (_, false) => Unrom512Mirror::Horizontal,
+            (_, true) => Unrom512Mirror::Vertical,
+        }
+    }
+}
+
+// UNROM-512
+//
+// CPU Address Bus  Register
+// $8000-$FFFF      Bank select, only $C000-$FFFF when the board is flashable
+//
+// Bank select
+// 76543210
+// ||||||||
+// |||+++++- Select 16 KB PRG ROM bank for CPU $8000-$BFFF
+// |++------ Select 8 KB CHR RAM bank for PPU $0000-$1FFF
+// +-------- Select 1 KB VRAM page for all 4 nametables in one-screen mode
+//
+// A flashable board (battery flag set) passes writes to $8000-$BFFF to the
+// flash chip, at the offset of the bank selected at the time. Commands
+// reach chip address $5555 by bank 1 at $9555, $2AAA by bank 0 at $AAAA
+pub struct Mapper030 {
+    prg_banks: usize,
+    chr_banks: usize,
+    flashable: bool,
+    nametable: Unrom512Mirror,
+
+    prg_bank_lo: usize,
+    chr_bank:    usize,
+    page:        usize,
+}
+
+impl Mapper for Mapper030 {
+    // CPU Address Bus          PRG ROM
+    // 0x8000 -> 0xBFFF: Map    16 KB switchable bank
+    // 0xC000 -> 0xFFFF: Map    16 KB fixed to the last bank
+    fn read(&mut self, addr: Addr) -> Mapped {
+        let bank = match addr {
+            Addr(0x8000..=0xBFFF) => self.prg_bank_lo,
+            Addr(0xC000..=0xFFFF) => self.prg_banks.max(1) - 1,
+            _ => return Mapped::Unmapped,
+        };
+
+        let i = map_prg(self.prg_banks, 0x4000, bank, addr);
+        if self.flashable {
+            Mapped::Flash(i)
+        } else {
+            Mapped::Prg(i)
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        match addr {
+            Addr(0x8000..=0xBFFF) if self.flashable => {
+                Mapped::Flash(map_prg(self.prg_banks, 0x4000, self.prg_bank_lo, addr))
+            }
+            Addr(0x8000..=0xFFFF) => {
+                self.prg_bank_lo = (v & Byte(0x1F)).0 as usize;
+                self.chr_bank = ((v.0 >> 5) & 0x03) as usize;
+                self.page = (v.0 >> 7) as usize;
+                // Register is not backed by memory, so the write
+                // must not reach PRG ROM
+                Mapped::Data(v)
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // PPU Address Bus          CHR RAM
+    // 0x0000 -> 0x1FFF: Map    8 KB switchable bank
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) => {
+                Mapped::Chr(map_chr(self.chr_banks, 0x2000, self.chr_bank, addr))
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
+    }
+
+    // PPU Address Bus          CIRAM or CHR RAM
+    // 0x2000 -> 0x2FFF: Map    1 KB page in one-screen mode, or the
+    //                          last 8 KB of CHR RAM in four-screen mode
+    fn read_nametable(&mut self, addr: Addr) -> Mapped {
+        match self.nametable {
+            Unrom512Mirror::OneScreen => {
+                Mapped::CiRam((self.page << 10) | (addr & Addr(0x03FF)).as_usize())
+            }
+            Unrom512Mirror::FourScreen => {
+                let last = self.chr_banks.max(1) - 1;
+                Mapped::Chr(map_chr(self.chr_banks, 0x2000, last, addr & Addr(0x0FFF)))
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write_nametable(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_nametable(addr)
+    }
+
+    fn mirror(&self) -> Mirror {
+        match self.nametable {
+            Unrom512Mirror::Horizontal => Mirror::Horizontal,
+            Unrom512Mirror::Vertical => Mirror::Vertical,
+            _ => Mirror::Hardware,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.prg_bank_lo = 0;
+        self.chr_bank = 0;
+        self.page = 0;
+    }
+}
+
+impl Mapper030 {
+    pub fn new(
+        prg_banks: usize,
+        chr_banks: usize,
+        flashable: bool,
+        nametable: Unrom512Mirror,
+    ) -> Self {
+        Self {
+            prg_banks,
+            chr_banks,
+            flashable,
+            nametable,
+            prg_bank_lo: 0,
+            chr_bank: 0,
+            page: 0,
+        }
+    }
+}
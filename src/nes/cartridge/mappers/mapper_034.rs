@@ -0,0 +1,120 @@
+use super::discrete::{map_chr, map_prg};
+use super::mapper::{Mapped, Mapper};
+use crate::cartridge::CartridgeHeader;
+use crate::prelude::*;
+
+// BNROM and NINA-001
+//
+// BNROM
+// CPU Address Bus  Register
+// $8000-$FFFF      Select 32 KB PRG ROM bank for CPU $8000-$FFFF
+//
+// NINA-001, registers share the addresses with PRG RAM
+// CPU Address Bus  Register
+// $7FFD            Select 32 KB PRG ROM bank for CPU $8000-$FFFF (bit 0)
+// $7FFE            Select 4 KB CHR ROM bank for PPU $0000-$0FFF (bits 0-3)
+// $7FFF            Select 4 KB CHR ROM bank for PPU $1000-$1FFF (bits 0-3)
+//
+//     Submapper  Board
+//     1          NINA-001
+//     2          BNROM
+//     0          NINA-001 if CHR ROM is larger than 8 KB, otherwise BNROM
+pub struct Mapper034 {
+    prg_banks: usize,
+    chr_banks: usize,
+    nina:      bool,
+
+    prg_bank:    usize,
+    chr_banks_4: [usize; 2],
+}
+
+impl Mapper for Mapper034 {
+    // CPU Address Bus          PRG RAM
+    // 0x6000 -> 0x7FFF: Map    0x0000 -> 0x1FFF
+    // CPU Address Bus          PRG ROM
+    // 0x8000 -> 0xFFFF: Map    32 KB switchable bank
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) => Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize()),
+            Addr(0x8000..=0xFFFF) => {
+                Mapped::Prg(map_prg(self.prg_banks, 0x8000, self.prg_bank, addr))
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) => {
+                // NINA-001 registers also land in PRG RAM
+                match addr {
+                    Addr(0x7FFD) if self.nina => self.prg_bank = (v & Byte(0x01)).0 as usize,
+                    Addr(0x7FFE) if self.nina => self.chr_banks_4[0] = (v & Byte(0x0F)).0 as usize,
+                    Addr(0x7FFF) if self.nina => self.chr_banks_4[1] = (v & Byte(0x0F)).0 as usize,
+                    _ => {}
+                }
+                Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize())
+            }
+            Addr(0x8000..=0xFFFF) if !self.nina => {
+                self.prg_bank = v.0 as usize;
+                // Register is not backed by memory, so the write
+                // must not reach PRG ROM
+                Mapped::Data(v)
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // BNROM
+    //     PPU Address Bus          CHR RAM
+    //     0x0000 -> 0x1FFF: Map    0x0000 -> 0x1FFF
+    // NINA-001
+    //     PPU Address Bus          CHR ROM
+    //     0x0000 -> 0x1FFF: Map    2 x 4 KB switchable banks
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) if self.nina => {
+                let bank = self.chr_banks_4[(addr.0 >> 12) as usize & 0x01];
+                Mapped::Chr(map_chr(self.chr_banks, 0x1000, bank, addr))
+            }
+            Addr(0x0000..=0x1FFF) => Mapped::Chr(addr.as_usize()),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
+    }
+
+    // NINA-001 registers do not overlap the ROM
+    fn bus_conflicts(&self) -> bool {
+        !self.nina
+    }
+
+    fn reset(&mut self) {
+        self.prg_bank = 0;
+        self.chr_banks_4 = [0, 1];
+    }
+}
+
+impl Mapper034 {
+    pub fn new(prg_banks: usize, chr_banks: usize, nina: bool) -> Self {
+        Self {
+            prg_banks,
+            chr_banks,
+            nina,
+            prg_bank: 0,
+            chr_banks_4: [0, 1],
+        }
+    }
+
+    // Whether the header describes NINA-001 rather than BNROM
+    pub fn nina_from_header(header: &CartridgeHeader) -> bool {
+        match header.submapper {
+            1 => true,
+            2 => false,
+            _ => header.chr_rom_size > 0x2000,
+        }
+    }
+}
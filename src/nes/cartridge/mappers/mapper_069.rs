@@ -0,0 +1,226 @@
+use super::mapper::{Mapped, Mapper};
+use crate::audio::Sunsoft5b;
+use crate::cartridge::Mirror;
+use crate::prelude::*;
+
+// A full volume channel has the same weight as a full
+// volume pulse channel of the console
+const SUNSOFT_5B_LEVEL: f32 = 0.11;
+
+// Sunsoft FME-7 and 5B
+//
+// CPU Address Bus  Register
+// $8000-$9FFF      Command
+// $A000-$BFFF      Parameter of the command
+// $C000-$DFFF      Audio register select (5B only)
+// $E000-$FFFF      Audio register write (5B only)
+//
+// Command  Meaning
+// $0-$7    1 KB CHR bank
+// $8       8 KB bank at $6000
+//          76543210
+//          ||||||||
+//          ||++++++- Bank
+//          |+------- Select RAM instead of ROM
+//          +-------- Enable RAM
+// $9-$B    8 KB PRG ROM bank at $8000, $A000, $C000
+// $C       Mirroring (0: vertical; 1: horizontal; 2: one-screen lower; 3: one-screen upper)
+// $D       IRQ control, acknowledges the IRQ
+//          76543210
+//          |      |
+//          |      +- Enable IRQ
+//          +-------- Enable counter
+// $E       IRQ counter, low 8 bits
+// $F       IRQ counter, high 8 bits
+//
+// The IRQ counter decrements every CPU cycle and raises the IRQ when it
+// wraps from $0000 to $FFFF. The audio registers do nothing on FME-7
+pub struct Mapper069 {
+    prg_banks: usize,
+    chr_banks: usize,
+
+    command:     u8,
+    prg_banks_8: [usize; 4],
+    ram_select:  bool,
+    ram_enabled: bool,
+    chr_banks_1: [usize; 8],
+    mirror:      Mirror,
+
+    irq_counter:         u16,
+    irq_enabled:         bool,
+    irq_counter_enabled: bool,
+    irq_active:          bool,
+
+    audio:     Sunsoft5b,
+    audio_reg: u8,
+}
+
+impl Mapper for Mapper069 {
+    // CPU Address Bus          PRG ROM or RAM
+    // 0x6000 -> 0x7FFF: Map    8 KB switchable bank of ROM or RAM
+    // CPU Address Bus          PRG ROM
+    // 0x8000 -> 0xDFFF: Map    3 x 8 KB switchable banks
+    // 0xE000 -> 0xFFFF: Map    8 KB fixed to the last bank
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) if self.ram_select => {
+                if self.ram_enabled {
+                    Mapped::PrgRam(self.map_ram(addr))
+                } else {
+                    Mapped::Unmapped
+                }
+            }
+            Addr(0x6000..=0xFFFF) => Mapped::Prg(self.map_prg(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) if self.ram_select && self.ram_enabled => {
+                return Mapped::PrgRam(self.map_ram(addr));
+            }
+            Addr(0x8000..=0x9FFF) => self.command = v.0 & 0x0F,
+            Addr(0xA000..=0xBFFF) => self.parameter(v),
+            Addr(0xC000..=0xDFFF) => self.audio_reg = v.0,
+            Addr(0xE000..=0xFFFF) => self.audio.write(self.audio_reg, v.0),
+            _ => return Mapped::Unmapped,
+        }
+
+        // Registers are not backed by memory
+        Mapped::Data(v)
+    }
+
+    // PPU Address Bus          CHR ROM
+    // 0x0000 -> 0x1FFF: Map    8 x 1 KB switchable banks
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) => Mapped::Chr(self.map_chr(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
+    }
+
+    fn cpu_tick(&mut self) {
+        if self.irq_counter_enabled {
+            self.irq_counter = self.irq_counter.wrapping_sub(1);
+            if self.irq_counter == 0xFFFF && self.irq_enabled {
+                self.irq_active = true;
+            }
+        }
+
+        self.audio.tick();
+    }
+
+    fn audio(&self) -> f32 {
+        self.audio.output() * SUNSOFT_5B_LEVEL
+    }
+
+    fn mirror(&self) -> Mirror {
+        self.mirror
+    }
+
+    fn has_irq(&self) -> bool {
+        self.irq_active
+    }
+
+    fn clear_irq(&mut self) {
+        self.irq_active = false;
+    }
+
+    fn reset(&mut self) {
+        self.command = 0;
+        self.prg_banks_8 = [0; 4];
+        self.ram_select = false;
+        self.ram_enabled = false;
+        self.chr_banks_1 = [0; 8];
+        self.mirror = Mirror::Vertical;
+
+        self.irq_counter = 0;
+        self.irq_enabled = false;
+        self.irq_counter_enabled = false;
+        self.irq_active = false;
+
+        self.audio.reset();
+        self.audio_reg = 0;
+    }
+}
+
+impl Mapper069 {
+    pub fn new(prg_banks: usize, chr_banks: usize) -> Self {
+        let mut s = Self {
+            prg_banks,
+            chr_banks,
+            command: 0,
+            prg_banks_8: [0; 4],
+            ram_select: false,
+            ram_enabled: false,
+            chr_banks_1: [0; 8],
+            mirror: Mirror::Vertical,
+            irq_counter: 0,
+            irq_enabled: false,
+            irq_counter_enabled: false,
+            irq_active: false,
+            audio: Sunsoft5b::new(),
+            audio_reg: 0,
+        };
+        s.reset();
+        s
+    }
+
+    fn parameter(&mut self, v: Byte) {
+        match self.command {
+            0x0..=0x7 => self.chr_banks_1[self.command as usize] = v.0 as usize,
+            0x8 => {
+                self.prg_banks_8[0] = (v & Byte(0x3F)).0 as usize;
+                self.ram_select = v & Byte(0x40) != Byte(0x00);
+                self.ram_enabled = v & Byte(0x80) != Byte(0x00);
+            }
+            0x9..=0xB => {
+                self.prg_banks_8[(self.command - 0x8) as usize] = (v & Byte(0x3F)).0 as usize;
+            }
+            0xC => {
+                self.mirror = match v.0 & 0x03 {
+                    0 => Mirror::Vertical,
+                    1 => Mirror::Horizontal,
+                    2 => Mirror::OneScreenLo,
+                    _ => Mirror::OneScreenHi,
+                }
+            }
+            0xD => {
+                self.irq_enabled = v & Byte(0x01) != Byte(0x00);
+                self.irq_counter_enabled = v & Byte(0x80) != Byte(0x00);
+                self.irq_active = false;
+            }
+            0xE => self.irq_counter = (self.irq_counter & 0xFF00) | v.0 as u16,
+            _ => self.irq_counter = (self.irq_counter & 0x00FF) | ((v.0 as u16) << 8),
+        }
+    }
+
+    fn map_prg(&self, addr: Addr) -> usize {
+        // Count of 8K banks
+        let prg_banks = self.prg_banks.max(1) * 2;
+        let bank = match addr {
+            Addr(0xE000..=0xFFFF) => prg_banks - 1,
+            _ => self.prg_banks_8[((addr.0 - 0x6000) >> 13) as usize],
+        };
+
+        (bank % prg_banks) * 0x2000 + (addr & Addr(0x1FFF)).as_usize()
+    }
+
+    fn map_ram(&self, addr: Addr) -> usize {
+        self.prg_banks_8[0] * 0x2000 + (addr & Addr(0x1FFF)).as_usize()
+    }
+
+    fn map_chr(&self, addr: Addr) -> usize {
+        // Count of 1K banks
+        let chr_banks = self.chr_banks.max(1) * 8;
+        let bank = self.chr_banks_1[(addr.0 >> 10) as usize & 0x07];
+
+        (bank % chr_banks) * 0x0400 + (addr & Addr(0x03FF)).as_usize()
+    }
+}
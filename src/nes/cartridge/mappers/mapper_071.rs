@@ -0,0 +1,115 @@
+use super::discrete::map_prg;
+use super::mapper::{Mapped, Mapper};
+use crate::cartridge::Mirror;
+use crate::prelude::*;
+
+// Camerica/Codemasters (BF909x)
+//
+// CPU Address Bus  Register
+// $8000-$9FFF      One-screen mirroring (BF9097 only)
+// $C000-$FFFF      Bank select
+//
+// One-screen mirroring
+// 76543210
+//    |
+//    +---- Select 1 KB VRAM page for all 4 nametables
+//
+// Bank select
+// 76543210
+//     ||||
+//     ++++- Select 16 KB PRG ROM bank for CPU $8000-$BFFF
+//
+// Submapper 1 is the BF9097 of Fire Hawk. As other boards leave the
+// mirroring to the hardware, submapper 0 only takes control of it once
+// the game writes to $9000-$9FFF
+pub struct Mapper071 {
+    prg_banks:  usize,
+    one_screen: bool,
+
+    prg_bank_lo:    usize,
+    mirror_control: bool,
+    mirror:         Mirror,
+}
+
+impl Mapper for Mapper071 {
+    // CPU Address Bus          PRG ROM
+    // 0x8000 -> 0xBFFF: Map    16 KB switchable bank
+    // 0xC000 -> 0xFFFF: Map    16 KB fixed to the last bank
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x8000..=0xBFFF) => {
+                Mapped::Prg(map_prg(self.prg_banks, 0x4000, self.prg_bank_lo, addr))
+            }
+            Addr(0xC000..=0xFFFF) => {
+                let last = self.prg_banks.max(1) - 1;
+                Mapped::Prg(map_prg(self.prg_banks, 0x4000, last, addr))
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        match addr {
+            Addr(0x8000..=0x9FFF) => {
+                if addr.0 >= 0x9000 {
+                    self.mirror_control = true;
+                }
+                if self.mirror_control {
+                    self.mirror = if v & Byte(0x10) != Byte(0x00) {
+                        Mirror::OneScreenHi
+                    } else {
+                        Mirror::OneScreenLo
+                    };
+                }
+            }
+            Addr(0xC000..=0xFFFF) => self.prg_bank_lo = (v & Byte(0x0F)).0 as usize,
+            _ => return Mapped::Unmapped,
+        }
+
+        // Registers are not backed by memory
+        Mapped::Data(v)
+    }
+
+    // There is no mapping required for PPU
+    // PPU Address Bus          CHR RAM
+    // 0x0000 -> 0x1FFF: Map    0x0000 -> 0x1FFF
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) => Mapped::Chr(addr.as_usize()),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
+    }
+
+    fn mirror(&self) -> Mirror {
+        self.mirror
+    }
+
+    fn reset(&mut self) {
+        self.prg_bank_lo = 0;
+        self.mirror_control = self.one_screen;
+        self.mirror = if self.one_screen {
+            Mirror::OneScreenLo
+        } else {
+            Mirror::Hardware
+        };
+    }
+}
+
+impl Mapper071 {
+    pub fn new(prg_banks: usize, _chr_banks: usize, one_screen: bool) -> Self {
+        let mut s = Self {
+            prg_banks,
+            one_screen,
+            prg_bank_lo: 0,
+            mirror_control: one_screen,
+            mirror: Mirror::Hardware,
+        };
+        s.reset();
+        s
+    }
+}
@@ -0,0 +1,221 @@
+use super::mapper::{Mapped, Mapper};
+use super::VrcIrq;
+use crate::audio::Opll;
+use crate::cartridge::{CartridgeHeader, Mirror};
+use crate::prelude::*;
+
+// CPU cycles per sample of the OPLL
+const OPLL_PERIOD: u32 = 36;
+// A full volume channel spans the same range as a full volume
+// pulse channel of the console
+const VRC7_LEVEL: f32 = 0.11 / 8192.0;
+
+// Konami VRC7
+//
+// CPU Address Bus  Register
+// $8000            8 KB PRG ROM bank at $8000
+// $8010            8 KB PRG ROM bank at $A000
+// $9000            8 KB PRG ROM bank at $C000
+// $9010            Audio register select
+// $9030            Audio register write
+// $A000-$D010      1 KB CHR banks, two per register group
+// $E000            Control
+// 76543210
+// ||    ||
+// ||    ++- Mirroring (0: vertical; 1: horizontal; 2: one-screen lower; 3: one-screen upper)
+// |+------- Silence and reset the audio
+// +-------- PRG RAM enable
+// $E010            IRQ latch
+// $F000            IRQ control
+// $F010            IRQ acknowledge
+//
+// VRC7a (submapper 2, Lagrange Point) tells the registers of a group apart
+// with A4 as listed, VRC7b (submapper 1, Tiny Toon Adventures 2) uses A3.
+// Without a submapper both lines are decoded
+pub struct Mapper085 {
+    prg_banks: usize,
+    chr_banks: usize,
+    // Mask of the CPU address lines selecting the second register
+    select:    u16,
+
+    prg_banks_8: [usize; 3],
+    chr_banks_1: [usize; 8],
+    mirror:      Mirror,
+    ram_enabled: bool,
+    irq:         VrcIrq,
+
+    opll:      Opll,
+    silenced:  bool,
+    audio_reg: u8,
+    cycles:    u32,
+    sample:    i32,
+}
+
+impl Mapper for Mapper085 {
+    // CPU Address Bus          PRG RAM
+    // 0x6000 -> 0x7FFF: Map    0x0000 -> 0x1FFF
+    // CPU Address Bus          PRG ROM
+    // 0x8000 -> 0xDFFF: Map    3 x 8 KB switchable banks
+    // 0xE000 -> 0xFFFF: Map    8 KB fixed to the last bank
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x6000..=0x7FFF) if self.ram_enabled => {
+                Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize())
+            }
+            Addr(0x8000..=0xFFFF) => Mapped::Prg(self.map_prg(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        let second = addr.0 & self.select != 0;
+
+        match (addr.0 & 0xF000, second) {
+            (0x6000..=0x7000, _) if self.ram_enabled => {
+                return Mapped::PrgRam((addr & Addr(0x1FFF)).as_usize());
+            }
+            (0x8000, false) => self.prg_banks_8[0] = (v & Byte(0x3F)).0 as usize,
+            (0x8000, true) => self.prg_banks_8[1] = (v & Byte(0x3F)).0 as usize,
+            // The audio ports are decoded with A4 and A5 on both boards
+            (0x9000, _) if addr.0 & 0x0030 == 0x0030 => {
+                if !self.silenced {
+                    self.opll.write(self.audio_reg, v.0);
+                }
+            }
+            (0x9000, _) if addr.0 & 0x0010 != 0 => self.audio_reg = v.0,
+            (0x9000, false) => self.prg_banks_8[2] = (v & Byte(0x3F)).0 as usize,
+            (0xA000..=0xD000, _) => {
+                let bank = (((addr.0 >> 12) - 0x0A) * 2) as usize + second as usize;
+                self.chr_banks_1[bank] = v.0 as usize;
+            }
+            (0xE000, false) => {
+                self.mirror = match v.0 & 0x03 {
+                    0 => Mirror::Vertical,
+                    1 => Mirror::Horizontal,
+                    2 => Mirror::OneScreenLo,
+                    _ => Mirror::OneScreenHi,
+                };
+                self.silenced = v & Byte(0x40) != Byte(0x00);
+                if self.silenced {
+                    self.opll.reset();
+                    self.sample = 0;
+                }
+                self.ram_enabled = v & Byte(0x80) != Byte(0x00);
+            }
+            (0xE000, true) => self.irq.latch = v,
+            (0xF000, false) => self.irq.control(v),
+            (0xF000, true) => self.irq.acknowledge(),
+            _ => return Mapped::Unmapped,
+        }
+
+        // Registers are not backed by memory
+        Mapped::Data(v)
+    }
+
+    // PPU Address Bus          CHR ROM
+    // 0x0000 -> 0x1FFF: Map    8 x 1 KB switchable banks
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) => Mapped::Chr(self.map_chr(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
+    }
+
+    // The OPLL runs at its own rate, its last sample is held in between
+    fn cpu_tick(&mut self) {
+        self.irq.tick();
+
+        self.cycles += 1;
+        if self.cycles == OPLL_PERIOD {
+            self.cycles = 0;
+            if !self.silenced {
+                self.sample = self.opll.clock();
+            }
+        }
+    }
+
+    fn audio(&self) -> f32 {
+        self.sample as f32 * VRC7_LEVEL
+    }
+
+    fn mirror(&self) -> Mirror {
+        self.mirror
+    }
+
+    fn has_irq(&self) -> bool {
+        self.irq.active
+    }
+
+    fn clear_irq(&mut self) {
+        self.irq.active = false;
+    }
+
+    fn reset(&mut self) {
+        self.prg_banks_8 = [0; 3];
+        self.chr_banks_1 = [0; 8];
+        self.mirror = Mirror::Vertical;
+        self.ram_enabled = false;
+        self.irq.reset();
+
+        self.opll.reset();
+        self.silenced = false;
+        self.audio_reg = 0;
+        self.cycles = 0;
+        self.sample = 0;
+    }
+}
+
+impl Mapper085 {
+    pub fn new(prg_banks: usize, chr_banks: usize, select: u16) -> Self {
+        let mut s = Self {
+            prg_banks,
+            chr_banks,
+            select,
+            prg_banks_8: [0; 3],
+            chr_banks_1: [0; 8],
+            mirror: Mirror::Vertical,
+            ram_enabled: false,
+            irq: VrcIrq::new(),
+            opll: Opll::new(),
+            silenced: false,
+            audio_reg: 0,
+            cycles: 0,
+            sample: 0,
+        };
+        s.reset();
+        s
+    }
+
+    // Address lines selecting the second register of a group by submapper
+    pub fn select_from_header(header: &CartridgeHeader) -> u16 {
+        match header.submapper {
+            1 => 0x0008,
+            2 => 0x0010,
+            _ => 0x0018,
+        }
+    }
+
+    fn map_prg(&self, addr: Addr) -> usize {
+        // Count of 8K banks
+        let prg_banks = self.prg_banks.max(1) * 2;
+        let bank = match addr {
+            Addr(0xE000..=0xFFFF) => prg_banks - 1,
+            _ => self.prg_banks_8[((addr.0 >> 13) & 0x03) as usize],
+        };
+
+        (bank % prg_banks) * 0x2000 + (addr & Addr(0x1FFF)).as_usize()
+    }
+
+    fn map_chr(&self, addr: Addr) -> usize {
+        // Count of 1K banks
+        let chr_banks = self.chr_banks.max(1) * 8;
+        let bank = self.chr_banks_1[(addr.0 >> 10) as usize & 0x07];
+
+        (bank % chr_banks) * 0x0400 + (addr & Addr(0x03FF)).as_usize()
+    }
+}
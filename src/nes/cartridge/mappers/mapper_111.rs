@@ -0,0 +1,122 @@
+use super::discrete::{map_chr, map_prg};
+use super::mapper::{Mapped, Mapper};
+use crate::prelude::*;
+
+// Nametable RAM of the board, two pages of 8 KB
+const NAMETABLE_RAM_SIZE: usize = 0x4000;
+
+// GTROM (Cheapocabra)
+//
+// CPU Address Bus  Register
+// $5000-$5FFF      Bank select, mirrored at $7000-$7FFF
+//
+// Bank select
+// 76543210
+// ||||||||
+// ||||++++- Select 32 KB PRG ROM bank for CPU $8000-$FFFF
+// |||+----- Select 8 KB CHR RAM page for PPU $0000-$1FFF
+// ||+------ Select 8 KB nametable RAM page for PPU $2000-$3EFF
+// |+------- Green LED (0: on)
+// +-------- Red LED (0: on)
+//
+// PRG ROM is a flash chip the game reprograms for saves. The nametable RAM
+// gives four screens without mirroring
+pub struct Mapper111 {
+    prg_banks: usize,
+    chr_banks: usize,
+
+    prg_bank:       usize,
+    chr_page:       usize,
+    nametable_page: usize,
+    leds:           u8,
+    nametable_ram:  Vec<u8>,
+}
+
+impl Mapper for Mapper111 {
+    // CPU Address Bus          PRG ROM
+    // 0x8000 -> 0xFFFF: Map    32 KB switchable bank
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x5000..=0x5FFF) | Addr(0x7000..=0x7FFF) => Mapped::Unmapped,
+            Addr(0x8000..=0xFFFF) => {
+                Mapped::Flash(map_prg(self.prg_banks, 0x8000, self.prg_bank, addr))
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        match addr {
+            Addr(0x5000..=0x5FFF) | Addr(0x7000..=0x7FFF) => {
+                self.prg_bank = (v & Byte(0x0F)).0 as usize;
+                self.chr_page = ((v.0 >> 4) & 0x01) as usize;
+                self.nametable_page = ((v.0 >> 5) & 0x01) as usize;
+                self.leds = v.0 >> 6;
+                // Register is not backed by memory
+                Mapped::Data(v)
+            }
+            Addr(0x8000..=0xFFFF) => {
+                Mapped::Flash(map_prg(self.prg_banks, 0x8000, self.prg_bank, addr))
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // PPU Address Bus          CHR RAM
+    // 0x0000 -> 0x1FFF: Map    8 KB switchable page
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) => {
+                Mapped::Chr(map_chr(self.chr_banks, 0x2000, self.chr_page, addr))
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
+    }
+
+    // PPU Address Bus          Nametable RAM
+    // 0x2000 -> 0x2FFF: Map    4 KB of the switchable page
+    fn read_nametable(&mut self, addr: Addr) -> Mapped {
+        Mapped::Data(Byte(self.nametable_ram[self.map_nametable(addr)]))
+    }
+
+    fn write_nametable(&mut self, addr: Addr, v: Byte) -> Mapped {
+        let i = self.map_nametable(addr);
+        self.nametable_ram[i] = v.0;
+        Mapped::Data(v)
+    }
+
+    fn reset(&mut self) {
+        self.prg_bank = 0;
+        self.chr_page = 0;
+        self.nametable_page = 0;
+        self.leds = 0;
+    }
+}
+
+impl Mapper111 {
+    pub fn new(prg_banks: usize, chr_banks: usize) -> Self {
+        Self {
+            prg_banks,
+            chr_banks,
+            prg_bank: 0,
+            chr_page: 0,
+            nametable_page: 0,
+            leds: 0,
+            nametable_ram: vec![0; NAMETABLE_RAM_SIZE],
+        }
+    }
+
+    // Whether the green and the red LED are lit
+    pub fn leds(&self) -> (bool, bool) {
+        (self.leds & 0x01 == 0, self.leds & 0x02 == 0)
+    }
+
+    fn map_nametable(&self, addr: Addr) -> usize {
+        self.nametable_page * 0x2000 + (addr & Addr(0x0FFF)).as_usize()
+    }
+}
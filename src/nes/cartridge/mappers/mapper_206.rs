@@ -0,0 +1,103 @@
+use super::discrete::{map_chr, map_prg};
+use super::mapper::{Mapped, Mapper};
+use crate::prelude::*;
+
+// Namco 108 (DxROM), the predecessor of MMC3 without its modes,
+// mirroring control and IRQ
+//
+// CPU Address Bus  Register
+// $8000-$9FFE even Bank select
+// $8001-$9FFF odd  Bank data
+//
+// Bank select
+// 76543210
+//      |||
+//      +++- Specify which bank register to update on next write to Bank Data register
+//           (0: 2 KB CHR bank at PPU $0000-$07FF;
+//            1: 2 KB CHR bank at PPU $0800-$0FFF;
+//            2-5: 1 KB CHR bank at PPU $1000-$13FF, ..., $1C00-$1FFF;
+//            6: 8 KB PRG ROM bank at $8000-$9FFF;
+//            7: 8 KB PRG ROM bank at $A000-$BFFF)
+pub struct Mapper206 {
+    prg_banks: usize,
+    chr_banks: usize,
+
+    target_register: usize,
+    registers:       [usize; 8],
+}
+
+impl Mapper for Mapper206 {
+    // CPU Address Bus          PRG ROM
+    // 0x8000 -> 0x9FFF: Map    8 KB switchable bank
+    // 0xA000 -> 0xBFFF: Map    8 KB switchable bank
+    // 0xC000 -> 0xDFFF: Map    8 KB fixed to the second-last bank
+    // 0xE000 -> 0xFFFF: Map    8 KB fixed to the last bank
+    fn read(&mut self, addr: Addr) -> Mapped {
+        let last = self.prg_banks.max(1) * 2 - 1;
+        let bank = match addr {
+            Addr(0x8000..=0x9FFF) => self.registers[6],
+            Addr(0xA000..=0xBFFF) => self.registers[7],
+            Addr(0xC000..=0xDFFF) => last - 1,
+            Addr(0xE000..=0xFFFF) => last,
+            _ => return Mapped::Unmapped,
+        };
+
+        Mapped::Prg(map_prg(self.prg_banks, 0x2000, bank, addr))
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        match addr {
+            Addr(0x8000..=0x9FFF) if addr.0 & 0x0001 == 0 => {
+                self.target_register = (v & Byte(0x07)).0 as usize;
+            }
+            Addr(0x8000..=0x9FFF) => {
+                let mask = if self.target_register < 6 { 0x3F } else { 0x0F };
+                self.registers[self.target_register] = (v.0 & mask) as usize;
+            }
+            // Nothing else is decoded, but the write must not reach PRG ROM
+            Addr(0xA000..=0xFFFF) => {}
+            _ => return Mapped::Unmapped,
+        }
+
+        // Registers are not backed by memory
+        Mapped::Data(v)
+    }
+
+    // PPU Address Bus          CHR ROM
+    // 0x0000 -> 0x0FFF: Map    2 x 2 KB switchable banks
+    // 0x1000 -> 0x1FFF: Map    4 x 1 KB switchable banks
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x0FFF) => {
+                let bank = self.registers[(addr.0 >> 11) as usize] >> 1;
+                Mapped::Chr(map_chr(self.chr_banks, 0x0800, bank, addr))
+            }
+            Addr(0x1000..=0x1FFF) => {
+                let bank = self.registers[((addr.0 >> 10) & 0x03) as usize + 2];
+                Mapped::Chr(map_chr(self.chr_banks, 0x0400, bank, addr))
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
+    }
+
+    fn reset(&mut self) {
+        self.target_register = 0;
+        self.registers = [0; 8];
+    }
+}
+
+impl Mapper206 {
+    pub fn new(prg_banks: usize, chr_banks: usize) -> Self {
+        Self {
+            prg_banks,
+            chr_banks,
+            target_register: 0,
+            registers: [0; 8],
+        }
+    }
+}
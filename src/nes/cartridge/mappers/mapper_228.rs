@@ -0,0 +1,125 @@
+use super::discrete::{map_chr, map_prg};
+use super::mapper::{Mapped, Mapper};
+use crate::cartridge::Mirror;
+use crate::prelude::*;
+
+// Action 52
+//
+// Writes to $8000-$FFFF latch both the address and the data
+// Address
+// 5432109876543210
+//   |||||||||  ||||
+//   |||||||||  ++++- Select 8 KB CHR ROM bank, high 4 bits
+//   ||||||||+------- PRG ROM mode (0: 32 KB; 1: 16 KB mirrored at $8000 and $C000)
+//   |||+++++-------- Select 16 KB PRG ROM page, the low bit is ignored in 32 KB mode
+//   |++------------- Select PRG ROM chip (0, 1 or 3, chip 2 does not exist)
+//   +--------------- Mirroring (0: vertical; 1: horizontal)
+// Data
+// 76543210
+//       ||
+//       ++- Select 8 KB CHR ROM bank, low 2 bits
+//
+// $4020-$5FFF holds four 4 bit RAM registers, mirrored every 4 bytes.
+// The chips of 512 KB each are stored in the order 0, 1, 3
+pub struct Mapper228 {
+    prg_banks: usize,
+    chr_banks: usize,
+
+    prg_chip:  usize,
+    prg_page:  usize,
+    prg_16k:   bool,
+    chr_bank:  usize,
+    mirror:    Mirror,
+    registers: [u8; 4],
+}
+
+impl Mapper for Mapper228 {
+    // CPU Address Bus          PRG ROM
+    // 0x8000 -> 0xFFFF: Map    32 KB switchable bank, or 16 KB switchable
+    //                          bank mirrored at 0x8000 and 0xC000
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x4020..=0x5FFF) => Mapped::Data(Byte(self.registers[addr.as_usize() & 0x03])),
+            // Missing chip leaves the bus open
+            Addr(0x8000..=0xFFFF) if self.prg_chip == 2 => Mapped::Unmapped,
+            Addr(0x8000..=0xFFFF) => Mapped::Prg(self.map_prg(addr)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, addr: Addr, v: Byte) -> Mapped {
+        match addr {
+            Addr(0x4020..=0x5FFF) => self.registers[addr.as_usize() & 0x03] = v.0 & 0x0F,
+            Addr(0x8000..=0xFFFF) => {
+                self.prg_chip = ((addr.0 >> 11) & 0x03) as usize;
+                self.prg_page = ((addr.0 >> 6) & 0x1F) as usize;
+                self.prg_16k = addr.0 & 0x0020 != 0;
+                self.chr_bank = ((addr.0 & 0x000F) << 2) as usize | (v & Byte(0x03)).0 as usize;
+                self.mirror = if addr.0 & 0x2000 != 0 {
+                    Mirror::Horizontal
+                } else {
+                    Mirror::Vertical
+                };
+            }
+            _ => return Mapped::Unmapped,
+        }
+
+        // Registers are not backed by memory
+        Mapped::Data(v)
+    }
+
+    // PPU Address Bus          CHR ROM
+    // 0x0000 -> 0x1FFF: Map    8 KB switchable bank
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x0000..=0x1FFF) => {
+                Mapped::Chr(map_chr(self.chr_banks, 0x2000, self.chr_bank, addr))
+            }
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    // Writes only land when the cartridge carries CHR RAM
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        self.read_chr(addr)
+    }
+
+    fn mirror(&self) -> Mirror {
+        self.mirror
+    }
+
+    fn reset(&mut self) {
+        self.prg_chip = 0;
+        self.prg_page = 0;
+        self.prg_16k = false;
+        self.chr_bank = 0;
+        self.mirror = Mirror::Vertical;
+    }
+}
+
+impl Mapper228 {
+    pub fn new(prg_banks: usize, chr_banks: usize) -> Self {
+        Self {
+            prg_banks,
+            chr_banks,
+            prg_chip: 0,
+            prg_page: 0,
+            prg_16k: false,
+            chr_bank: 0,
+            mirror: Mirror::Vertical,
+            registers: [0; 4],
+        }
+    }
+
+    fn map_prg(&self, addr: Addr) -> usize {
+        // Chip 3 follows chip 1 in the file
+        let chip = if self.prg_chip == 3 { 2 } else { self.prg_chip };
+        let page = if self.prg_16k {
+            self.prg_page
+        } else {
+            (self.prg_page & !0x01) | ((addr.0 >> 14) & 0x01) as usize
+        };
+
+        map_prg(self.prg_banks, 0x4000, chip * 32 + page, addr)
+    }
+}
@@ -1,5 +1,60 @@
+mod discrete;
+mod fds;
 mod mapper;
 mod mapper_000;
+mod mapper_001;
+mod mapper_002;
+mod mapper_003;
+mod mapper_004;
+mod mapper_005;
+mod mapper_007;
+mod mapper_009;
+mod mapper_010;
+mod mapper_011;
+mod mapper_016;
+mod mapper_019;
+mod mapper_021;
+mod mapper_024;
+mod mapper_026;
+mod mapper_028;
+mod mapper_030;
+mod mapper_034;
+mod mapper_066;
+mod mapper_069;
+mod mapper_071;
+mod mapper_079;
+mod mapper_085;
+mod mapper_111;
+mod mapper_206;
+mod mapper_228;
+mod registry;
 
+pub use fds::*;
 pub use mapper::*;
 pub use mapper_000::*;
+pub use mapper_001::*;
+pub use mapper_002::*;
+pub use mapper_003::*;
+pub use mapper_004::*;
+pub use mapper_005::*;
+pub use mapper_007::*;
+pub use mapper_009::*;
+pub use mapper_010::*;
+pub use mapper_011::*;
+pub use mapper_016::*;
+pub use mapper_019::*;
+pub use mapper_021::*;
+pub use mapper_024::*;
+pub use mapper_026::*;
+pub use mapper_028::*;
+pub use mapper_030::*;
+pub use mapper_034::*;
+pub use mapper_066::*;
+pub use mapper_069::*;
+pub use mapper_071::*;
+pub use mapper_079::*;
+pub use mapper_085::*;
+pub use mapper_111::*;
+pub use mapper_206::*;
+pub use mapper_228::*;
+pub use registry::*;
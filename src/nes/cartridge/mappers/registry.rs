@@ -0,0 +1,193 @@
+use super::mapper::Mapper;
+use super::{
+    BandaiBoard, Mapper000, Mapper001, Mapper002, Mapper003, Mapper004, Mapper005, Mapper007,
+    Mapper009, Mapper010, Mapper011, Mapper016, Mapper019, Mapper021, Mapper024, Mapper026,
+    Mapper028, Mapper030, Mapper034, Mapper066, Mapper069, Mapper071, Mapper079, Mapper085,
+    Mapper111, Mapper206, Mapper228, Mmc3Board, Unrom512Mirror, VrcBoard,
+};
+use crate::cartridge::CartridgeHeader;
+
+use std::collections::HashMap;
+
+const CHARACTER_RAM_UNIT: usize = 8192; // 8 kb
+
+// CHR RAM of a board whose header leaves the size out. Homebrew
+// boards bank more than the usual 8 KB
+pub fn default_chr_ram_size(mapper: u16) -> usize {
+    match mapper {
+        28 | 30 => 4 * CHARACTER_RAM_UNIT,
+        111 => 2 * CHARACTER_RAM_UNIT,
+        _ => CHARACTER_RAM_UNIT,
+    }
+}
+
+// Everything known about the cartridge at the moment its mapper is built
+pub struct MapperInfo<'a> {
+    pub header:       &'a CartridgeHeader,
+    // Count of 16 KB PRG ROM banks
+    pub prg_banks:    usize,
+    // Count of 8 KB CHR banks, either ROM or RAM
+    pub chr_banks:    usize,
+    pub chr_ram:      bool,
+    pub prg_ram_size: usize,
+}
+
+pub type MapperCtor = Box<dyn Fn(&MapperInfo) -> Box<dyn Mapper>>;
+
+// Constructors of mappers by iNES mapper number and optionally NES 2.0
+// submapper. A constructor registered for the exact submapper takes
+// precedence over the one registered for the whole mapper number
+pub struct MapperRegistry {
+    ctors: HashMap<(u16, Option<u8>), MapperCtor>,
+}
+
+impl Default for MapperRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MapperRegistry {
+    // Registry without any mapper
+    pub fn empty() -> Self {
+        Self {
+            ctors: HashMap::new(),
+        }
+    }
+
+    // Registry with the mappers nep supports out of the box
+    pub fn new() -> Self {
+        let mut s = Self::empty();
+        s.register(0, None, |i| {
+            Box::new(Mapper000::new(i.prg_banks, i.chr_banks))
+        });
+        s.register(1, None, |i| {
+            Box::new(Mapper001::new(i.prg_banks, i.chr_banks))
+        });
+        s.register(2, None, |i| {
+            Box::new(Mapper002::new(i.prg_banks, i.chr_banks))
+        });
+        s.register(3, None, |i| {
+            Box::new(Mapper003::new(i.prg_banks, i.chr_banks))
+        });
+        // MMC3 and the boards reusing its registers
+        for mapper in [4, 64, 118, 119] {
+            s.register(mapper, None, move |i| {
+                let board = Mmc3Board::from_mapper(mapper);
+                Box::new(Mapper004::with_board(i.prg_banks, i.chr_banks, board))
+            });
+        }
+        s.register(5, None, |i| {
+            Box::new(Mapper005::new(i.prg_banks, i.chr_banks))
+        });
+        s.register(7, None, |i| {
+            Box::new(Mapper007::new(i.prg_banks, i.chr_banks))
+        });
+        s.register(9, None, |i| {
+            Box::new(Mapper009::new(i.prg_banks, i.chr_banks))
+        });
+        s.register(10, None, |i| {
+            Box::new(Mapper010::new(i.prg_banks, i.chr_banks))
+        });
+        s.register(11, None, |i| {
+            Box::new(Mapper011::new(i.prg_banks, i.chr_banks))
+        });
+        // Bandai boards are told apart by mapper and submapper
+        for mapper in [16, 153, 159] {
+            s.register(mapper, None, |i| {
+                let board = BandaiBoard::from_header(i.header);
+                Box::new(Mapper016::new(i.prg_banks, i.chr_banks, board))
+            });
+        }
+        s.register(19, None, |i| {
+            Box::new(Mapper019::new(i.prg_banks, i.chr_banks))
+        });
+        // Konami VRC2/VRC4 boards are told apart by their submapper
+        for mapper in [21, 22, 23, 25] {
+            s.register(mapper, None, |i| {
+                let board = VrcBoard::from_header(i.header);
+                Box::new(Mapper021::new(i.prg_banks, i.chr_banks, board))
+            });
+        }
+        s.register(24, None, |i| {
+            Box::new(Mapper024::new(i.prg_banks, i.chr_banks))
+        });
+        s.register(26, None, |i| {
+            Box::new(Mapper026::new(i.prg_banks, i.chr_banks))
+        });
+        s.register(28, None, |i| {
+            Box::new(Mapper028::new(i.prg_banks, i.chr_banks))
+        });
+        // UNROM-512 takes flash writes only when the battery flag is set
+        s.register(30, None, |i| {
+            let nametable = Unrom512Mirror::from_header(i.header);
+            let flashable = i.header.battery;
+            Box::new(Mapper030::new(
+                i.prg_banks,
+                i.chr_banks,
+                flashable,
+                nametable,
+            ))
+        });
+        // BNROM and NINA-001 are told apart by submapper or CHR ROM size
+        s.register(34, None, |i| {
+            let nina = Mapper034::nina_from_header(i.header);
+            Box::new(Mapper034::new(i.prg_banks, i.chr_banks, nina))
+        });
+        s.register(66, None, |i| {
+            Box::new(Mapper066::new(i.prg_banks, i.chr_banks))
+        });
+        s.register(69, None, |i| {
+            Box::new(Mapper069::new(i.prg_banks, i.chr_banks))
+        });
+        s.register(71, None, |i| {
+            let one_screen = i.header.submapper == 1;
+            Box::new(Mapper071::new(i.prg_banks, i.chr_banks, one_screen))
+        });
+        s.register(79, None, |i| {
+            Box::new(Mapper079::new(i.prg_banks, i.chr_banks))
+        });
+        s.register(85, None, |i| {
+            let select = Mapper085::select_from_header(i.header);
+            Box::new(Mapper085::new(i.prg_banks, i.chr_banks, select))
+        });
+        s.register(111, None, |i| {
+            Box::new(Mapper111::new(i.prg_banks, i.chr_banks))
+        });
+        s.register(206, None, |i| {
+            Box::new(Mapper206::new(i.prg_banks, i.chr_banks))
+        });
+        s.register(228, None, |i| {
+            Box::new(Mapper228::new(i.prg_banks, i.chr_banks))
+        });
+        s
+    }
+
+    // Adds or replaces constructor of the mapper, `None` as submapper
+    // matches every submapper without its own constructor
+    pub fn register<F>(&mut self, mapper: u16, submapper: Option<u8>, ctor: F)
+    where
+        F: Fn(&MapperInfo) -> Box<dyn Mapper> + 'static,
+    {
+        self.ctors.insert((mapper, submapper), Box::new(ctor));
+    }
+
+    pub fn unregister(&mut self, mapper: u16, submapper: Option<u8>) {
+        self.ctors.remove(&(mapper, submapper));
+    }
+
+    pub fn contains(&self, mapper: u16, submapper: u8) -> bool {
+        self.find(mapper, submapper).is_some()
+    }
+
+    pub fn create(&self, info: &MapperInfo) -> Option<Box<dyn Mapper>> {
+        self.find(info.header.mapper, info.header.submapper)
+            .map(|ctor| ctor(info))
+    }
+
+    fn find(&self, mapper: u16, submapper: u8) -> Option<&MapperCtor> {
+        self.ctors
+            .get(&(mapper, Some(submapper)))
+            .or_else(|| self.ctors.get(&(mapper, None)))
+    }
+}
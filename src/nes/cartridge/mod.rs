@@ -1,4 +1,15 @@
 mod cartridge;
+mod disk;
+mod eeprom;
+mod flash;
+mod header;
+mod ips;
 pub mod mappers;
+mod unif;
 
 pub use self::cartridge::*;
+pub use self::disk::*;
+pub use self::eeprom::*;
+pub use self::flash::*;
+pub use self::header::*;
+pub use self::unif::*;
@@ -0,0 +1,259 @@
+use super::cartridge::Mirror;
+use super::header::{CartridgeHeader, ConsoleType, ExpansionDevice, HeaderFormat, Timing};
+use crate::prelude::*;
+
+use std::convert::TryInto;
+
+const UNIF_MAGIC: &[u8] = b"UNIF";
+// Magic, revision and reserved bytes
+const UNIF_HEADER_SIZE: usize = 32;
+// Chunk ID and length
+const CHUNK_HEADER_SIZE: usize = 8;
+
+const PROGRAM_ROM_UNIT: usize = 16384; // 16 kb
+const CHARACTER_ROM_UNIT: usize = 8192; // 8 kb
+const PROGRAM_RAM_UNIT: usize = 8192; // 8 kb
+
+// UNIF file format:
+// 0-3: Constant "UNIF"
+// 4-7: Revision, little endian
+// 8-31: Reserved
+// Chunks follow up to the end of the file, each being a 4 byte ID,
+// the length of the data as 32 bit little endian and the data
+//
+// ID         Meaning
+// MAPR       Board name, NUL terminated, e.g. "NES-TLROM" or "UNL-..."
+// PRG0-PRGF  PRG ROM, the chips are concatenated in the order of the hex digit
+// CHR0-CHRF  CHR ROM, the same way. Without them the board has CHR RAM
+// MIRR       Mirroring (0: horizontal; 1: vertical; 2: one-screen lower;
+//            3: one-screen upper; 4: four-screen; 5: mapper controlled)
+// BATR       Battery-backed memory is present
+// TVCI       TV system (0: NTSC; 1: PAL; 2: both)
+// Any other chunk (NAME, READ, DINF, CTRL, checksums) is skipped
+pub struct Unif {
+    pub board:  String,
+    pub header: CartridgeHeader,
+    pub prg:    Vec<u8>,
+    pub chr:    Vec<u8>,
+}
+
+impl Unif {
+    pub fn detect(data: &[u8]) -> bool {
+        data.starts_with(UNIF_MAGIC)
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if !Self::detect(data) {
+            let mut magic = [0; 4];
+            let len = data.len().min(magic.len());
+            magic[..len].copy_from_slice(&data[..len]);
+            return errors::BadMagic { magic }.fail();
+        }
+        if data.len() < UNIF_HEADER_SIZE {
+            return errors::TruncatedHeader { size: data.len() }.fail();
+        }
+
+        let mut board = None;
+        let mut prg_chips: [Vec<u8>; 16] = Default::default();
+        let mut chr_chips: [Vec<u8>; 16] = Default::default();
+        let mut mirr = None;
+        let mut battery = false;
+        let mut timing = Timing::Ntsc;
+
+        let mut i = UNIF_HEADER_SIZE;
+        while i < data.len() {
+            let (id, chunk) = match Self::chunk(&data[i..]) {
+                Some(c) => c,
+                None => return errors::TruncatedChunk { offset: i }.fail(),
+            };
+            i += CHUNK_HEADER_SIZE + chunk.len();
+
+            match id {
+                b"MAPR" => {
+                    let name = chunk.split(|v| *v == 0).next().unwrap_or(&[]);
+                    board = Some(String::from_utf8_lossy(name).trim().to_string());
+                }
+                [b'P', b'R', b'G', n] => match Self::chip(*n) {
+                    Some(n) => prg_chips[n] = chunk.to_vec(),
+                    None => println!("[CARTGE] skip UNIF chunk: {:?}", id),
+                },
+                [b'C', b'H', b'R', n] => match Self::chip(*n) {
+                    Some(n) => chr_chips[n] = chunk.to_vec(),
+                    None => println!("[CARTGE] skip UNIF chunk: {:?}", id),
+                },
+                b"MIRR" => mirr = chunk.first().copied(),
+                b"BATR" => battery = true,
+                b"TVCI" => {
+                    timing = match chunk.first() {
+                        Some(1) => Timing::Pal,
+                        Some(2) => Timing::Multi,
+                        _ => Timing::Ntsc,
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let board = match board {
+            Some(b) => b,
+            None => {
+                return errors::MissingChunk {
+                    id: "MAPR".to_string(),
+                }
+                .fail();
+            }
+        };
+        println!("[CARTGE] UNIF board: {}", board);
+
+        let (mapper, submapper) = match Self::board_mapper(&board) {
+            Some(m) => m,
+            None => return errors::UnsupportedBoard { name: board }.fail(),
+        };
+
+        let prg = Self::fill_bank(prg_chips.concat(), PROGRAM_ROM_UNIT);
+        let chr = Self::fill_bank(chr_chips.concat(), CHARACTER_ROM_UNIT);
+        if prg.is_empty() {
+            return errors::InvalidSize {
+                detail: "PRG ROM is empty".to_string(),
+            }
+            .fail();
+        }
+
+        let mirror = match mirr {
+            Some(1) => Mirror::Vertical,
+            Some(2) => Mirror::OneScreenLo,
+            Some(3) => Mirror::OneScreenHi,
+            Some(4) => Mirror::FourScreen,
+            Some(5) => Mirror::Hardware,
+            _ => Mirror::Horizontal,
+        };
+
+        let (prg_ram_size, prg_nvram_size) = if battery {
+            (0, PROGRAM_RAM_UNIT)
+        } else {
+            (PROGRAM_RAM_UNIT, 0)
+        };
+
+        let header = CartridgeHeader {
+            format: HeaderFormat::Unif,
+            mapper,
+            submapper,
+            prg_rom_size: prg.len(),
+            chr_rom_size: chr.len(),
+            prg_ram_size,
+            prg_nvram_size,
+            chr_ram_size: match chr.len() {
+                0 => Self::chr_ram_size(&board),
+                _ => 0,
+            },
+            chr_nvram_size: 0,
+            mirror,
+            mirror_bit: mirr == Some(1),
+            battery,
+            trainer: false,
+            bus_conflicts: false,
+            timing,
+            console_type: ConsoleType::Nes,
+            vs_system: None,
+            misc_roms: 0,
+            default_expansion_device: ExpansionDevice::Unspecified,
+        };
+
+        Ok(Self {
+            board,
+            header,
+            prg,
+            chr,
+        })
+    }
+
+    // ID and data of the chunk at the start of `data`
+    fn chunk(data: &[u8]) -> Option<(&[u8; 4], &[u8])> {
+        let id = data.get(0..4)?.try_into().ok()?;
+        let len = data.get(4..8)?;
+        let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize;
+        let chunk = data.get(CHUNK_HEADER_SIZE..CHUNK_HEADER_SIZE.checked_add(len)?)?;
+
+        Some((id, chunk))
+    }
+
+    // Number of the ROM chip from the hex digit of the chunk ID
+    fn chip(digit: u8) -> Option<usize> {
+        (digit as char).to_digit(16).map(|n| n as usize)
+    }
+
+    // Chips smaller than a bank are mirrored to fill it, as the
+    // board would see them
+    fn fill_bank(mut rom: Vec<u8>, unit: usize) -> Vec<u8> {
+        if !rom.is_empty() && rom.len() < unit && unit.is_multiple_of(rom.len()) {
+            rom = rom.repeat(unit / rom.len());
+        }
+        rom
+    }
+
+    // The boards of UNROM-512 name their CHR RAM size, for any
+    // other board it is left to the board default
+    fn chr_ram_size(board: &str) -> usize {
+        match Self::strip_prefix(board) {
+            "UNROM-512-8" => CHARACTER_ROM_UNIT,
+            "UNROM-512-16" => 2 * CHARACTER_ROM_UNIT,
+            "UNROM-512-32" => 4 * CHARACTER_ROM_UNIT,
+            _ => 0,
+        }
+    }
+
+    // Boards are named with the prefix of their maker or market
+    fn strip_prefix(board: &str) -> &str {
+        [
+            "NES-",
+            "HVC-",
+            "UNL-",
+            "BTL-",
+            "BMC-",
+            "MLT-",
+            "AVE-",
+            "CAMERICA-",
+        ]
+        .iter()
+        .find_map(|p| board.strip_prefix(p))
+        .unwrap_or(board)
+    }
+
+    // Mapper and submapper implementing a board
+    pub fn board_mapper(board: &str) -> Option<(u16, u8)> {
+        let m = match Self::strip_prefix(board) {
+            "NROM" | "NROM-128" | "NROM-256" | "RROM" | "RROM-128" => (0, 0),
+            "SAROM" | "SBROM" | "SCROM" | "SC1ROM" | "SEROM" | "SFROM" | "SGROM" | "SHROM"
+            | "SJROM" | "SKROM" | "SLROM" | "SL1ROM" | "SLRROM" | "SNROM" | "SOROM" | "SUROM"
+            | "SXROM" => (1, 0),
+            "UNROM" | "UOROM" => (2, 0),
+            "CNROM" => (3, 0),
+            "COLORDREAMS-74*377" | "NINA-07" => (11, 0),
+            "NAMCOT-163" => (19, 0),
+            "ACTION53" => (28, 0),
+            "TBROM" | "TEROM" | "TFROM" | "TGROM" | "TKROM" | "TLROM" | "TNROM" | "TR1ROM"
+            | "TVROM" | "B4" => (4, 0),
+            "ELROM" | "EKROM" | "ETROM" | "EWROM" => (5, 0),
+            "AMROM" | "ANROM" | "AN1ROM" | "AOROM" => (7, 0),
+            "PNROM" | "PEEOROM" => (9, 0),
+            "FJROM" | "FKROM" => (10, 0),
+            "UNROM-512-8" | "UNROM-512-16" | "UNROM-512-32" => (30, 0),
+            "NINA-001" => (34, 1),
+            "BNROM" => (34, 2),
+            "TENGEN-800032" => (64, 0),
+            "GNROM" | "MHROM" => (66, 0),
+            "JLROM" | "JSROM" | "BTR" => (69, 0),
+            "BF9093" => (71, 0),
+            // One-screen mirroring of Fire Hawk
+            "BF9097" => (71, 1),
+            "NINA-03" | "NINA-06" => (79, 0),
+            "CHEAPOCABRA" => (111, 0),
+            "TLSROM" | "TKSROM" => (118, 0),
+            "TQROM" => (119, 0),
+            "DEROM" | "DE1ROM" | "DRROM" => (206, 0),
+            "ACTION52" => (228, 0),
+            _ => return None,
+        };
+        Some(m)
+    }
+}
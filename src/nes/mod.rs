@@ -1,3 +1,4 @@
+pub mod audio;
 pub mod cartridge;
 pub mod clock;
 pub mod cpu;
@@ -11,7 +12,9 @@ pub mod utils;
 
 use prelude::*;
 
-use cartridge::Cartridge;
+use audio::{Mixer, DEFAULT_SAMPLE_RATE};
+use cartridge::mappers::MapperRegistry;
+use cartridge::{BusConflicts, Cartridge};
 use clock::Clock;
 use cpu::bus::CpuBus;
 use cpu::Cpu;
@@ -27,6 +30,9 @@ use std::io::{Read, Seek};
 use std::path::Path;
 use std::rc::Rc;
 
+// Battery-backed RAM is flushed to disk every 5 seconds
+const SAVE_INTERVAL_FRAMES: u32 = 300;
+
 pub struct Emu {
     clock: Clock,
     cart:  Cartridge,
@@ -36,23 +42,29 @@ pub struct Emu {
     ppu:   Ppu,
     joy_1: Joypad,
     joy_2: Joypad,
+    audio: Mixer,
+
+    save_frames: u32,
 }
 
 impl Emu {
     pub fn new() -> Self {
         Self {
-            clock: Clock::new(),
-            cart:  Cartridge::new(),
-            ram:   Ram::new(),
-            dma:   Dma::new(),
-            cpu:   Cpu::new(),
-            ppu:   Ppu::new(),
-            joy_1: Joypad::new(),
-            joy_2: Joypad::new(),
+            clock:       Clock::new(),
+            cart:        Cartridge::new(),
+            ram:         Ram::new(),
+            dma:         Dma::new(),
+            cpu:         Cpu::new(),
+            ppu:         Ppu::new(),
+            joy_1:       Joypad::new(),
+            joy_2:       Joypad::new(),
+            audio:       Mixer::new(DEFAULT_SAMPLE_RATE),
+            save_frames: 0,
         }
     }
 
     pub fn reset(&mut self) {
+        self.cart.reset();
         self.cpu.reset(CpuBus::new(
             &mut self.cart,
             &mut self.ram,
@@ -63,6 +75,7 @@ impl Emu {
         ));
         self.ppu.reset();
         self.clock.reset();
+        self.audio.reset();
     }
 
     pub fn screen(&self) -> &Screen {
@@ -76,11 +89,74 @@ impl Emu {
     }
 
     pub fn load_from_file<P: AsRef<Path>>(&mut self, file_path: P) -> Result<()> {
+        // Keep progress of the previous cartridge before it is replaced
+        self.cart.save()?;
         self.cart.load_from_file(file_path)?;
         self.reset();
         Ok(())
     }
 
+    // Directory for save files of battery-backed cartridges, by default they
+    // are placed next to the ROM. Takes effect on the next load
+    pub fn set_save_dir<P: AsRef<Path>>(&mut self, dir: P) {
+        self.cart.set_save_dir(dir);
+    }
+
+    // Whether register writes of discrete boards are ANDed with the ROM.
+    // Turning them off or on finds code that relies on them. Takes effect
+    // on the next load
+    pub fn set_bus_conflicts(&mut self, bus_conflicts: BusConflicts) {
+        self.cart.set_bus_conflicts(bus_conflicts);
+    }
+
+    // Logs the first write to each address that a bus conflict changes
+    pub fn set_bus_conflict_log(&mut self, enabled: bool) {
+        self.cart.set_bus_conflict_log(enabled);
+    }
+
+    // BIOS of the Famicom Disk System, needed to load .fds and QD images.
+    // Takes effect on the next load
+    pub fn set_fds_bios<P: AsRef<Path>>(&mut self, path: P) {
+        self.cart.set_fds_bios(path);
+    }
+
+    // Count of disk sides of a loaded disk image, zero for cartridges
+    pub fn disk_sides(&self) -> usize {
+        self.cart.disk_sides()
+    }
+
+    // Side in the drive, if any
+    pub fn disk_side(&self) -> Option<usize> {
+        self.cart.disk_side()
+    }
+
+    // Swaps the side in the drive, `None` ejects the disk. Games notice
+    // a swap more reliably when the drive stays empty for a second
+    pub fn insert_disk(&mut self, side: Option<usize>) {
+        self.cart.insert_disk(side);
+    }
+
+    // Flushes battery-backed RAM to the save file
+    pub fn save(&mut self) -> Result<()> {
+        self.save_frames = 0;
+        self.cart.save()
+    }
+
+    // Custom mappers are registered here before the cartridge is loaded
+    pub fn mappers_mut(&mut self) -> &mut MapperRegistry {
+        self.cart.mappers_mut()
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.audio.set_sample_rate(sample_rate);
+    }
+
+    // Mono samples produced since the previous call, where silence is 0.0
+    // and a full volume pulse wave spans about 0.11
+    pub fn take_audio(&mut self) -> Vec<f32> {
+        self.audio.take_samples()
+    }
+
     pub fn update_joypads(&mut self, joy_1_state: u8, joy_2_state: u8) {
         self.joy_1.update(JoypadState(joy_1_state));
         self.joy_2.update(JoypadState(joy_2_state));
@@ -93,6 +169,10 @@ impl Emu {
             }
 
             if self.clock.need_step_cpu() {
+                // Mapper counters run on every CPU cycle, DMA included
+                self.cart.cpu_tick();
+                self.audio.step(self.cart.audio());
+
                 // Is the system performing a DMA transfer form CPU memory to
                 // OAM memory on PPU?...
                 if self.dma.has_request() {
@@ -136,9 +216,10 @@ impl Emu {
                 ));
             }
 
-            // Check if cartridge is requesting IRQ
+            // Check if cartridge is requesting IRQ. The line is held by the
+            // mapper until the program acknowledges it, so an IRQ raised while
+            // interrupts are disabled is serviced as soon as they are enabled
             if self.cart.has_irq() {
-                self.cart.clear_irq();
                 self.cpu.irq(CpuBus::new(
                     &mut self.cart,
                     &mut self.ram,
@@ -155,5 +236,12 @@ impl Emu {
                 break;
             }
         }
+
+        self.save_frames += 1;
+        if self.save_frames >= SAVE_INTERVAL_FRAMES {
+            if let Err(err) = self.save() {
+                println!("[EMU] cannot flush save file: {}", err);
+            }
+        }
     }
 }
@@ -4,6 +4,7 @@ use super::oam::{Oam, OamEntry};
 use super::pixel::Pixel;
 use super::registers::{AddrReg, PpuCtrl, PpuMask, PpuStatus};
 use super::screen::Screen;
+use crate::nes::cartridge::mappers::{Mapped, PpuFetch};
 use crate::nes::cartridge::{Cartridge, Mirror};
 use crate::prelude::*;
 
@@ -153,6 +154,25 @@ impl Ppu {
         addr & Addr(0x001F)
     }
 
+    // Resolves a nametable address into the 1 KB page of VRAM backing it and
+    // the cell inside that page. Pages 0 and 1 are the VRAM inside the console,
+    // pages 2 and 3 are provided by four-screen cartridges
+    fn map_addr_name(mirror: Mirror, addr: Addr) -> (usize, Addr) {
+        let addr = Self::normalize_addr_name(addr);
+        let table = (addr.0 >> 10) as usize;
+        let page = match mirror {
+            Mirror::Vertical => table & 0x01,
+            Mirror::OneScreenLo => 0,
+            Mirror::OneScreenHi => 1,
+            Mirror::FourScreen => table,
+            // Hardware mirroring is resolved by the cartridge, so it only
+            // gets here without one. Fall back to horizontal as the
+            // header does when the mirroring bit is clear
+            Mirror::Horizontal | Mirror::Hardware => table >> 1,
+        };
+        (page, addr & Addr(0x03FF))
+    }
+
     pub fn reset(&mut self) {
         self.fine_x = Addr(0);
         self.addr_latch = 0;
@@ -299,39 +319,19 @@ impl Ppu {
     fn read_chr(&mut self, cart: &mut Cartridge, addr: Addr) -> Byte {
         let addr = Self::normalize_addr_chr(addr);
 
-        match addr {
-            Addr(0x0000..=0x1FFF) => {
-                let (v, mapped) = cart.read_chr(addr);
-                if mapped {
-                    v
-                } else {
+        let v = match addr {
+            Addr(0x0000..=0x1FFF) => match cart.read_chr(addr) {
+                Mapped::Data(v) => v,
+                // The mapper may fetch patterns from the console VRAM
+                Mapped::CiRam(i) => self.tbl_name[(i >> 10) & 0x01][i & 0x03FF],
+                _ => {
                     // If the cartridge cant map the address, have
                     // a physical location ready here
                     let (table_num, cell) = Self::normalize_addr_pattern(addr);
                     self.tbl_pattern[table_num.as_usize()][cell.as_usize()]
                 }
-            }
-            Addr(0x2000..=0x3EFF) => {
-                let addr = Self::normalize_addr_name(addr);
-                let tbl_addr = addr & Addr(0x03FF);
-                match cart.mirror() {
-                    Mirror::Vertical => match addr {
-                        Addr(0x0000..=0x03FF) => self.tbl_name[0][tbl_addr.as_usize()],
-                        Addr(0x0400..=0x07FF) => self.tbl_name[1][tbl_addr.as_usize()],
-                        Addr(0x0800..=0x0BFF) => self.tbl_name[0][tbl_addr.as_usize()],
-                        Addr(0x0C00..=0x0FFF) => self.tbl_name[1][tbl_addr.as_usize()],
-                        _ => Byte(0),
-                    },
-                    Mirror::Horizontal => match addr {
-                        Addr(0x0000..=0x03FF) => self.tbl_name[0][tbl_addr.as_usize()],
-                        Addr(0x0400..=0x07FF) => self.tbl_name[0][tbl_addr.as_usize()],
-                        Addr(0x0800..=0x0BFF) => self.tbl_name[1][tbl_addr.as_usize()],
-                        Addr(0x0C00..=0x0FFF) => self.tbl_name[1][tbl_addr.as_usize()],
-                        _ => Byte(0),
-                    },
-                    _ => Byte(0),
-                }
-            }
+            },
+            Addr(0x2000..=0x3EFF) => self.read_name(cart, addr),
             Addr(0x3F00..=0x3FFF) => {
                 let mut addr = Self::normalize_addr_palette(addr);
                 addr = match addr {
@@ -351,43 +351,31 @@ impl Ppu {
                 res
             }
             _ => Byte(0),
+        };
+
+        // Palette lives inside the PPU and never reaches the cartridge. The
+        // mapper sees the address after the fetch, so anything it switches
+        // in response only affects the following fetches
+        if addr < Addr(0x3F00) {
+            cart.ppu_bus(addr);
         }
+
+        v
     }
 
     fn write_chr(&mut self, cart: &mut Cartridge, addr: Addr, v: Byte) {
         let addr = Self::normalize_addr_chr(addr);
 
         match addr {
-            Addr(0x0000..=0x1FFF) => {
-                let mapped = cart.write_chr(addr, v);
-                if mapped {
-                    return;
-                } else {
+            Addr(0x0000..=0x1FFF) => match cart.write_chr(addr, v) {
+                Mapped::Data(_) => {}
+                Mapped::CiRam(i) => self.tbl_name[(i >> 10) & 0x01][i & 0x03FF] = v,
+                _ => {
                     let (table_num, cell) = Self::normalize_addr_pattern(addr);
                     self.tbl_pattern[table_num.as_usize()][cell.as_usize()] = v;
                 }
-            }
-            Addr(0x2000..=0x3EFF) => {
-                let addr = Self::normalize_addr_name(addr);
-                let tbl_addr = addr & Addr(0x03FF);
-                match cart.mirror() {
-                    Mirror::Vertical => match addr {
-                        Addr(0x0000..=0x03FF) => self.tbl_name[0][tbl_addr.as_usize()] = v,
-                        Addr(0x0400..=0x07FF) => self.tbl_name[1][tbl_addr.as_usize()] = v,
-                        Addr(0x0800..=0x0BFF) => self.tbl_name[0][tbl_addr.as_usize()] = v,
-                        Addr(0x0C00..=0x0FFF) => self.tbl_name[1][tbl_addr.as_usize()] = v,
-                        _ => {}
-                    },
-                    Mirror::Horizontal => match addr {
-                        Addr(0x0000..=0x03FF) => self.tbl_name[0][tbl_addr.as_usize()] = v,
-                        Addr(0x0400..=0x07FF) => self.tbl_name[0][tbl_addr.as_usize()] = v,
-                        Addr(0x0800..=0x0BFF) => self.tbl_name[1][tbl_addr.as_usize()] = v,
-                        Addr(0x0C00..=0x0FFF) => self.tbl_name[1][tbl_addr.as_usize()] = v,
-                        _ => {}
-                    },
-                    _ => {}
-                }
-            }
+            },
+            Addr(0x2000..=0x3EFF) => self.write_name(cart, addr, v),
             Addr(0x3F00..=0x3FFF) => {
                 let mut addr = Self::normalize_addr_palette(addr);
                 addr = match addr {
@@ -402,6 +390,57 @@ impl Ppu {
             }
             _ => {}
         }
+
+        if addr < Addr(0x3F00) {
+            cart.ppu_bus(addr);
+        }
+    }
+
+    fn read_name(&self, cart: &mut Cartridge, addr: Addr) -> Byte {
+        // The mapper may take over the nametables
+        match cart.read_nametable(addr) {
+            Mapped::Data(v) => return v,
+            Mapped::CiRam(i) => return self.tbl_name[(i >> 10) & 0x01][i & 0x03FF],
+            _ => {}
+        }
+
+        match Self::map_addr_name(cart.mirror(), addr) {
+            (page, cell) if page < TABLE_NAME_COUNT => self.tbl_name[page][cell.as_usize()],
+            (page, cell) => {
+                cart.read_vram(Addr(((page - TABLE_NAME_COUNT) * TABLE_NAME_SIZE) as u16) | cell)
+            }
+        }
+    }
+
+    fn write_name(&mut self, cart: &mut Cartridge, addr: Addr, v: Byte) {
+        // The mapper may take over the nametables
+        match cart.write_nametable(addr, v) {
+            Mapped::Data(_) => return,
+            Mapped::CiRam(i) => {
+                self.tbl_name[(i >> 10) & 0x01][i & 0x03FF] = v;
+                return;
+            }
+            _ => {}
+        }
+
+        match Self::map_addr_name(cart.mirror(), addr) {
+            (page, cell) if page < TABLE_NAME_COUNT => self.tbl_name[page][cell.as_usize()] = v,
+            (page, cell) => cart.write_vram(
+                Addr(((page - TABLE_NAME_COUNT) * TABLE_NAME_SIZE) as u16) | cell,
+                v,
+            ),
+        }
+    }
+
+    // Tells the mapper what is being fetched, with rendering disabled
+    // the bus is only driven through PPUDATA
+    fn notify_fetch(&self, cart: &mut Cartridge, fetch: PpuFetch) {
+        let fetch = if self.mask.render_background() || self.mask.render_sprites() {
+            fetch
+        } else {
+            PpuFetch::Idle
+        };
+        cart.ppu_fetch(fetch, self.control.sprite_size());
     }
 
     fn get_color_from_palette(
@@ -614,6 +653,11 @@ impl Ppu {
                 self.cycle = 1;
             }
 
+            if self.cycle == 1 {
+                // Let the mapper know the background fetches begin
+                self.notify_fetch(cart, PpuFetch::Background);
+            }
+
             if self.scanline == -1 && self.cycle == 1 {
                 // Effectively start of new frame, so clear vertical blank flag
                 self.status.disable_vertical_blank();
@@ -920,6 +964,7 @@ impl Ppu {
             if self.cycle == 340 {
                 // Now we're at the very end of the scanline, I'm going to prepare the
                 // sprite shifters with the 8 or less selected sprites.
+                self.notify_fetch(cart, PpuFetch::Sprites);
 
                 for i in 0..self.sprite_count {
                     // We need to extract the 8-bit row patterns of the sprite with the
@@ -1022,6 +1067,8 @@ impl Ppu {
                     self.sprite_shifter_pattern_lo[i] = sprite_pattern_bits_lo;
                     self.sprite_shifter_pattern_hi[i] = sprite_pattern_bits_hi;
                 }
+
+                self.notify_fetch(cart, PpuFetch::Background);
             }
         }
 
@@ -1033,6 +1080,7 @@ impl Ppu {
             if self.scanline == 241 && self.cycle == 1 {
                 // Effectively end of frame, so set vertical blank flag
                 self.status.enable_vertical_blank();
+                self.notify_fetch(cart, PpuFetch::Idle);
 
                 // If the control register tells us to emit a NMI when
                 // entering vertical blanking period, do it! The CPU
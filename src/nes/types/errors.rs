@@ -14,14 +14,99 @@ pub enum Error {
         backtrace: Backtrace,
         detail:    String,
     },
+    #[snafu(display("Error during read cartridge: truncated header, size = {}", size))]
+    TruncatedHeader {
+        backtrace: Backtrace,
+        size:      usize,
+    },
+    #[snafu(display("Error during read cartridge: bad magic, [0..4] = {:02X?}", magic))]
+    BadMagic {
+        backtrace: Backtrace,
+        magic:     [u8; 4],
+    },
+    #[snafu(display(
+        "Error during read cartridge: truncated trainer, expected = {}, actual = {}",
+        expected,
+        actual
+    ))]
+    TruncatedTrainer {
+        backtrace: Backtrace,
+        expected:  usize,
+        actual:    usize,
+    },
+    #[snafu(display(
+        "Error during read cartridge: truncated PRG ROM, expected = {}, actual = {}",
+        expected,
+        actual
+    ))]
+    TruncatedPrg {
+        backtrace: Backtrace,
+        expected:  usize,
+        actual:    usize,
+    },
+    #[snafu(display(
+        "Error during read cartridge: truncated CHR ROM, expected = {}, actual = {}",
+        expected,
+        actual
+    ))]
+    TruncatedChr {
+        backtrace: Backtrace,
+        expected:  usize,
+        actual:    usize,
+    },
+    #[snafu(display(
+        "Error during read cartridge: unsupported mapper, id = {}, submapper = {}",
+        id,
+        submapper
+    ))]
+    UnsupportedMapper {
+        backtrace: Backtrace,
+        id:        u16,
+        submapper: u8,
+    },
+    #[snafu(display("Error during read cartridge: unsupported UNIF board, name = {}", name))]
+    UnsupportedBoard {
+        backtrace: Backtrace,
+        name:      String,
+    },
+    #[snafu(display(
+        "Error during read cartridge: truncated UNIF chunk, offset = {:#X}",
+        offset
+    ))]
+    TruncatedChunk {
+        backtrace: Backtrace,
+        offset:    usize,
+    },
+    #[snafu(display("Error during read cartridge: missing UNIF chunk, id = {}", id))]
+    MissingChunk {
+        backtrace: Backtrace,
+        id:        String,
+    },
+    #[snafu(display("Error during read cartridge: invalid size, {}", detail))]
+    InvalidSize {
+        backtrace: Backtrace,
+        detail:    String,
+    },
     #[snafu(display("Error during read file: {}", source))]
     ReadFile {
         backtrace: Backtrace,
         source:    std::io::Error,
     },
+    #[snafu(display("Error during write file: {}", source))]
+    WriteFile {
+        backtrace: Backtrace,
+        source:    std::io::Error,
+    },
     #[snafu(display("Error during open file: {}", source))]
     OpenFile {
         backtrace: Backtrace,
         source:    std::io::Error,
     },
+    #[snafu(display("Error during read cartridge: disk images need the FDS BIOS"))]
+    MissingFdsBios { backtrace: Backtrace },
+    #[snafu(display("Error during read patch: {}", detail))]
+    InvalidPatch {
+        backtrace: Backtrace,
+        detail:    String,
+    },
 }
@@ -0,0 +1,98 @@
+use nep::audio::Opll;
+
+// Hashes of the rendered PCM, any change to the synthesis shows up here.
+// Update them only after listening to the new output
+const REFERENCE_SINE: u64 = 0x1DEDE8CF7245A1A4;
+const REFERENCE_MELODY: u64 = 0xC93E505E480AF9D4;
+const REFERENCE_CHORD: u64 = 0x735F1D29F64ACDE7;
+
+// FNV-1a over the little-endian bytes of the samples
+fn fnv1a(samples: &[i32]) -> u64 {
+    let mut hash = 0xCBF2_9CE4_8422_2325u64;
+    for b in samples.iter().flat_map(|s| s.to_le_bytes()) {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01B3);
+    }
+    hash
+}
+
+// Applies register writes, then renders the samples that follow them
+fn render(opll: &mut Opll, writes: &[(u8, u8)], samples: usize, pcm: &mut Vec<i32>) {
+    for &(reg, v) in writes {
+        opll.write(reg, v);
+    }
+    pcm.extend((0..samples).map(|_| opll.clock()));
+}
+
+#[test]
+fn opll_renders_sine_with_custom_instrument() {
+    let mut opll = Opll::new();
+    let mut pcm = Vec::new();
+
+    // Silent modulator and a sustained carrier make a pure sine,
+    // 0x122 in block 4 is 440 Hz
+    let custom = [0x21, 0x21, 0x3F, 0x00, 0xF0, 0xF0, 0x0F, 0x0F];
+    let writes: Vec<_> = (0..8).map(|i| (i as u8, custom[i])).collect();
+    render(&mut opll, &writes, 0, &mut pcm);
+    render(
+        &mut opll,
+        &[(0x30, 0x00), (0x10, 0x22), (0x20, 0x19)],
+        4972,
+        &mut pcm,
+    );
+
+    let periods = pcm.windows(2).filter(|w| w[0] < 0 && w[1] >= 0).count();
+    assert_eq!(periods, 44);
+    assert_eq!(pcm.iter().max(), Some(&4084));
+
+    render(&mut opll, &[(0x20, 0x09)], 4972, &mut pcm);
+    assert_eq!(fnv1a(&pcm), REFERENCE_SINE);
+}
+
+#[test]
+fn opll_renders_reference_melody() {
+    let mut opll = Opll::new();
+    let mut pcm = Vec::new();
+
+    // A short phrase on the built-in instruments, with notes released
+    // both with and without sustain
+    for (i, &(fnum, block)) in [(0x16B, 4), (0x181, 4), (0x1B0, 4), (0x120, 5)]
+        .iter()
+        .enumerate()
+    {
+        let ch = i as u8;
+        render(
+            &mut opll,
+            &[
+                (0x30 + ch, ((ch * 4 + 1) << 4) | 0x02),
+                (0x10 + ch, (fnum & 0xFF) as u8),
+                (0x20 + ch, 0x10 | (block << 1) | (fnum >> 8) as u8),
+            ],
+            2000,
+            &mut pcm,
+        );
+        render(
+            &mut opll,
+            &[(0x20 + ch, (i as u8 & 0x01) << 5)],
+            1000,
+            &mut pcm,
+        );
+    }
+    assert_eq!(fnv1a(&pcm), REFERENCE_MELODY);
+}
+
+#[test]
+fn opll_renders_reference_chord() {
+    let mut opll = Opll::new();
+    let mut pcm = Vec::new();
+
+    // All six channels at once, including instruments with vibrato and tremolo
+    let mut writes = Vec::new();
+    for ch in 0..6u8 {
+        writes.push((0x30 + ch, ((ch * 2 + 4) << 4) | ch));
+        writes.push((0x10 + ch, 0x40 + ch * 0x20));
+        writes.push((0x20 + ch, 0x16 + (ch & 0x01)));
+    }
+    render(&mut opll, &writes, 20000, &mut pcm);
+    assert_eq!(fnv1a(&pcm), REFERENCE_CHORD);
+}
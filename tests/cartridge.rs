@@ -0,0 +1,812 @@
+use nep::cartridge::mappers::{Mapped, Mapper};
+use nep::cartridge::*;
+use nep::prelude::*;
+use nep::Emu;
+
+use std::cell::Cell;
+use std::io::Cursor;
+use std::rc::Rc;
+
+fn ines(prg_banks: u8, chr_banks: u8, flags_6: u8, flags_7: u8) -> Vec<u8> {
+    let mut rom = vec![
+        0x4E, 0x45, 0x53, 0x1A, prg_banks, chr_banks, flags_6, flags_7,
+    ];
+    rom.resize(16, 0);
+    rom.resize(
+        16 + prg_banks as usize * 16384 + chr_banks as usize * 8192,
+        0,
+    );
+    rom
+}
+
+#[test]
+fn four_screen_ignores_mapper_mirroring() -> Result<()> {
+    // MMC3 with four-screen VRAM
+    let mut cart = Cartridge::new();
+    cart.load(&mut Cursor::new(ines(2, 1, 0x48, 0x00)))?;
+    assert!(matches!(cart.mirror(), Mirror::FourScreen));
+
+    cart.write(Addr(0xA000), Byte(0x01));
+    assert!(matches!(cart.mirror(), Mirror::FourScreen));
+
+    cart.write_vram(Addr(0x0400), Byte(0x42));
+    assert_eq!(cart.read_vram(Addr(0x0400)), Byte(0x42));
+    Ok(())
+}
+
+#[test]
+fn parses_ines_header() -> Result<()> {
+    let header = CartridgeHeader::parse(&ines(2, 1, 0x13, 0x10)[..16])?;
+    assert_eq!(header.format, HeaderFormat::INes);
+    assert_eq!(header.mapper, 0x11);
+    assert_eq!(header.prg_rom_size, 32768);
+    assert_eq!(header.chr_rom_size, 8192);
+    assert_eq!(header.prg_nvram_size, 8192);
+    assert_eq!(header.mirror, Mirror::Vertical);
+    assert!(header.battery);
+    assert!(!header.trainer);
+    Ok(())
+}
+
+#[test]
+fn parses_archaic_ines_header() -> Result<()> {
+    // Ripper's name in bytes 7-15 must not leak into the mapper number
+    let mut rom = ines(1, 1, 0x10, 0x00);
+    rom[7..16].copy_from_slice(b"DiskDude!");
+    let header = CartridgeHeader::parse(&rom[..16])?;
+    assert_eq!(header.format, HeaderFormat::Archaic);
+    assert_eq!(header.mapper, 0x01);
+    Ok(())
+}
+
+#[test]
+fn parses_nes_2_0_header() -> Result<()> {
+    let mut rom = ines(0, 0, 0x40, 0x58);
+    rom[8] = 0x21; // submapper 2, mapper bits 8-11 = 1
+    rom[9] = 0x1F; // CHR ROM MSB 1, PRG ROM in exponent-multiplier form
+    rom[4] = 0x51; // 2^20 * 3
+    rom[5] = 0x02;
+    rom[10] = 0x70; // 8 KB PRG-NVRAM
+    rom[11] = 0x07; // 8 KB CHR-RAM
+    rom[12] = 0x03;
+    rom[15] = 0x08;
+
+    let header = CartridgeHeader::parse(&rom[..16])?;
+    assert_eq!(header.format, HeaderFormat::Nes20);
+    assert_eq!(header.mapper, 0x154);
+    assert_eq!(header.submapper, 2);
+    assert_eq!(header.prg_rom_size, 3 << 20);
+    assert_eq!(header.chr_rom_size, 0x102 * 8192);
+    assert_eq!(header.prg_ram_size, 0);
+    assert_eq!(header.prg_nvram_size, 8192);
+    assert_eq!(header.chr_ram_size, 8192);
+    assert_eq!(header.timing, Timing::Dendy);
+    assert_eq!(header.default_expansion_device, ExpansionDevice::Zapper);
+    Ok(())
+}
+
+#[test]
+fn exposes_loaded_header() -> Result<()> {
+    let cart = Cartridge::from_file("./roms/nestest.nes")?;
+    let header = cart.header().expect("header of loaded cartridge");
+    assert_eq!(header.mapper, 0);
+    assert_eq!(header.prg_rom_size, 16384);
+    Ok(())
+}
+
+#[test]
+fn persists_battery_backed_ram() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("nep-save-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let rom_path = dir.join("battery.nes");
+    std::fs::write(&rom_path, ines(1, 1, 0x02, 0x00)).unwrap();
+
+    let mut cart = Cartridge::new();
+    cart.set_save_dir(dir.join("saves"));
+    cart.load_from_file(&rom_path)?;
+    cart.write(Addr(0x6123), Byte(0x5A));
+    cart.save()?;
+    assert_eq!(
+        cart.save_path(),
+        Some(dir.join("saves").join("battery.sav").as_path())
+    );
+
+    let mut cart = Cartridge::new();
+    cart.set_save_dir(dir.join("saves"));
+    cart.load_from_file(&rom_path)?;
+    assert_eq!(cart.read(Addr(0x6123)), Byte(0x5A));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+    Ok(())
+}
+
+#[test]
+fn persists_battery_backed_mapper_ram() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("nep-save-n163-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let rom_path = dir.join("namco.nes");
+    // Namco 163 with battery
+    std::fs::write(&rom_path, ines(2, 1, 0x32, 0x10)).unwrap();
+
+    let mut cart = Cartridge::new();
+    cart.set_save_dir(&dir);
+    cart.load_from_file(&rom_path)?;
+    // Internal RAM at $10 with auto-increment
+    cart.write(Addr(0xF800), Byte(0x90));
+    cart.write(Addr(0x4800), Byte(0x12));
+    cart.write(Addr(0x4800), Byte(0x34));
+    cart.save()?;
+    assert_eq!(
+        std::fs::read(dir.join("namco.sav")).unwrap().len(),
+        8192 + 128
+    );
+
+    let mut cart = Cartridge::new();
+    cart.set_save_dir(&dir);
+    cart.load_from_file(&rom_path)?;
+    cart.reset();
+    cart.write(Addr(0xF800), Byte(0x90));
+    assert_eq!(cart.read(Addr(0x4800)), Byte(0x12));
+    assert_eq!(cart.read(Addr(0x4800)), Byte(0x34));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+    Ok(())
+}
+
+#[test]
+fn persists_reprogrammed_flash() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("nep-save-flash-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let rom_path = dir.join("unrom512.nes");
+    // Flashable UNROM-512, 512 KB PRG ROM
+    std::fs::write(&rom_path, ines(32, 0, 0xE2, 0x10)).unwrap();
+
+    // Commands reach chip address $5555 through bank 1, $2AAA through bank 0
+    let command = |cart: &mut Cartridge, seq: &[(u8, u16, u8)]| {
+        for &(bank, addr, v) in seq {
+            cart.write(Addr(0xC000), Byte(bank));
+            cart.write(Addr(addr), Byte(v));
+        }
+    };
+    let unlock = [(1, 0x9555, 0xAA), (0, 0xAAAA, 0x55)];
+
+    let mut cart = Cartridge::new();
+    cart.set_save_dir(&dir);
+    cart.load_from_file(&rom_path)?;
+
+    command(&mut cart, &unlock);
+    command(&mut cart, &[(1, 0x9555, 0x90)]);
+    assert_eq!(cart.read(Addr(0x8000)), Byte(0xBF));
+    assert_eq!(cart.read(Addr(0x8001)), Byte(0xB7));
+    command(&mut cart, &[(0, 0x8000, 0xF0)]);
+    assert_eq!(cart.read(Addr(0x8000)), Byte(0x00));
+
+    // Erase the sector at $8000 of bank 2, then program a byte
+    command(&mut cart, &unlock);
+    command(&mut cart, &[(1, 0x9555, 0x80)]);
+    command(&mut cart, &unlock);
+    command(&mut cart, &[(2, 0x8000, 0x30)]);
+    assert_eq!(cart.read(Addr(0x8FFF)), Byte(0xFF));
+    command(&mut cart, &unlock);
+    command(&mut cart, &[(1, 0x9555, 0xA0), (2, 0x8010, 0x42)]);
+    assert_eq!(cart.read(Addr(0x8010)), Byte(0x42));
+    // $F0 as data is programmed rather than taken as the reset command
+    command(&mut cart, &unlock);
+    command(&mut cart, &[(1, 0x9555, 0xA0), (2, 0x8012, 0xF0)]);
+    assert_eq!(cart.read(Addr(0x8012)), Byte(0xF0));
+
+    // Writes outside of a command do not reach the ROM
+    cart.write(Addr(0x8011), Byte(0x00));
+    assert_eq!(cart.read(Addr(0x8011)), Byte(0xFF));
+
+    cart.save()?;
+    assert_eq!(
+        std::fs::read(dir.join("unrom512.sav")).unwrap().len(),
+        8192 + 512 * 1024
+    );
+
+    let mut cart = Cartridge::new();
+    cart.set_save_dir(&dir);
+    cart.load_from_file(&rom_path)?;
+    cart.write(Addr(0xC000), Byte(0x02));
+    assert_eq!(cart.read(Addr(0x8010)), Byte(0x42));
+    assert_eq!(cart.read(Addr(0x8011)), Byte(0xFF));
+    assert_eq!(cart.read(Addr(0x8012)), Byte(0xF0));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+    Ok(())
+}
+
+fn unif(chunks: &[(&[u8], Vec<u8>)]) -> Vec<u8> {
+    let mut rom = b"UNIF".to_vec();
+    rom.resize(32, 0);
+    rom[4] = 7;
+    for (id, data) in chunks {
+        rom.extend_from_slice(id);
+        rom.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        rom.extend_from_slice(data);
+    }
+    rom
+}
+
+#[test]
+fn loads_unif_boards() -> Result<()> {
+    // PRG chips are joined by their number, not by their order in the file
+    let rom = unif(&[
+        (b"MAPR", b"NES-TLROM\0".to_vec()),
+        (b"PRG1", vec![0x01; 16384]),
+        (b"PRG0", vec![0x00; 16384]),
+        (b"CHR0", vec![0x42; 8192]),
+        (b"MIRR", vec![0x01]),
+        (b"BATR", vec![0x01]),
+        (b"NAME", b"Test\0".to_vec()),
+    ]);
+
+    let mut cart = Cartridge::new();
+    cart.load(&mut Cursor::new(rom))?;
+    let header = cart.header().unwrap();
+    assert_eq!(header.format, HeaderFormat::Unif);
+    assert_eq!((header.mapper, header.prg_rom_size), (4, 32768));
+    assert!(header.battery);
+    assert_eq!(cart.mirror(), Mirror::Vertical);
+    assert_eq!(cart.read(Addr(0xC000)), Byte(0x01));
+    assert_eq!(cart.read(Addr(0x8000)), Byte(0x00));
+    assert_eq!(cart.read_chr(Addr(0x0000)), Mapped::Data(Byte(0x42)));
+
+    // Without CHR chips the board has CHR RAM
+    cart.load(&mut Cursor::new(unif(&[
+        (b"MAPR", b"UNL-UNROM-512-32\0".to_vec()),
+        (b"PRG0", vec![0x00; 32768]),
+    ])))?;
+    assert_eq!(cart.header().unwrap().mapper, 30);
+    assert_eq!(cart.header().unwrap().chr_ram_size, 32768);
+    assert!(cart.has_chr_ram());
+
+    let res = cart.load(&mut Cursor::new(unif(&[
+        (b"MAPR", b"UNL-NOPE\0".to_vec()),
+        (b"PRG0", vec![0x00; 16384]),
+    ])));
+    assert!(matches!(res, Err(errors::Error::UnsupportedBoard { .. })));
+
+    let mut rom = unif(&[(b"MAPR", b"NES-NROM-128\0".to_vec())]);
+    rom.extend_from_slice(b"PRG0\xFF\xFF");
+    let res = cart.load(&mut Cursor::new(rom));
+    assert!(matches!(res, Err(errors::Error::TruncatedChunk { .. })));
+
+    let res = cart.load(&mut Cursor::new(unif(&[(b"PRG0", vec![0x00; 16384])])));
+    assert!(matches!(res, Err(errors::Error::MissingChunk { .. })));
+    Ok(())
+}
+
+#[test]
+fn loads_unif_pirate_and_multicart_boards() -> Result<()> {
+    let mut cart = Cartridge::new();
+    cart.load(&mut Cursor::new(unif(&[
+        (b"MAPR", b"MLT-ACTION52\0".to_vec()),
+        (b"PRG0", vec![0x00; 524288]),
+        (b"PRG1", vec![0x01; 524288]),
+        (b"PRG2", vec![0x03; 524288]),
+        (b"CHR0", vec![0x42; 524288]),
+    ])))?;
+    assert_eq!(cart.header().unwrap().mapper, 228);
+    // Chip 3 is the third one in the file
+    cart.write(Addr(0x9800), Byte(0x00));
+    assert_eq!(cart.read(Addr(0x8000)), Byte(0x03));
+
+    cart.load(&mut Cursor::new(unif(&[
+        (b"MAPR", b"CAMERICA-BF9097\0".to_vec()),
+        (b"PRG0", vec![0x00; 131072]),
+        (b"MIRR", vec![0x05]),
+    ])))?;
+    let header = cart.header().unwrap();
+    assert_eq!((header.mapper, header.submapper), (71, 1));
+    cart.write(Addr(0x8000), Byte(0x10));
+    assert_eq!(cart.mirror(), Mirror::OneScreenHi);
+    Ok(())
+}
+
+// fwNES image where each side holds one file of four bytes
+fn fds(sides: u8) -> Vec<u8> {
+    let mut image = vec![0x46, 0x44, 0x53, 0x1A, sides];
+    image.resize(16, 0);
+    for side in 0..sides {
+        let mut info = b"\x01*NINTENDO-HVC*".to_vec();
+        info.resize(56, side);
+        image.extend_from_slice(&info);
+        image.extend_from_slice(&[0x02, 0x01]);
+        let mut header = vec![0x03; 16];
+        header[13..15].copy_from_slice(&[0x04, 0x00]);
+        image.extend_from_slice(&header);
+        image.extend_from_slice(&[0x04, 0x10, 0x20, 0x30, 0x40]);
+        image.resize(16 + (side as usize + 1) * 65500, 0);
+    }
+    image
+}
+
+// Runs the drive until it has transferred a byte
+fn wait_disk_irq(cart: &mut Cartridge) {
+    for _ in 0..1_000_000 {
+        cart.cpu_tick();
+        if cart.has_irq() {
+            return;
+        }
+    }
+    panic!("no disk IRQ");
+}
+
+fn read_disk_info(cart: &mut Cartridge) -> Vec<u8> {
+    // Motor on, read, transfer and IRQ
+    cart.write(Addr(0x4025), Byte(0xC5));
+    (0..56)
+        .map(|_| {
+            wait_disk_irq(cart);
+            cart.read(Addr(0x4031)).0
+        })
+        .collect()
+}
+
+#[test]
+fn loads_disk_images_with_fds_bios() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("nep-fds-load-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let bios_path = dir.join("disksys.rom");
+    let mut bios = vec![0; 8192];
+    bios[0x1FFC..].copy_from_slice(&[0x24, 0xEE, 0xAF, 0xE1]);
+    std::fs::write(&bios_path, bios).unwrap();
+
+    let mut cart = Cartridge::new();
+    let res = cart.load(&mut Cursor::new(fds(2)));
+    assert!(matches!(res, Err(errors::Error::MissingFdsBios { .. })));
+
+    cart.set_fds_bios(&bios_path);
+    cart.load(&mut Cursor::new(fds(2)))?;
+    assert!(cart.header().is_none());
+    assert!(cart.has_chr_ram());
+    assert_eq!(cart.read(Addr(0xFFFC)), Byte(0x24));
+    cart.write(Addr(0xDFFF), Byte(0x42));
+    assert_eq!(cart.read(Addr(0xDFFF)), Byte(0x42));
+
+    // The drive reads the first block right after the gap
+    cart.write(Addr(0x4023), Byte(0x01));
+    assert_eq!(cart.read(Addr(0x4032)), Byte(0x42));
+    let info = read_disk_info(&mut cart);
+    assert_eq!(&info[..15], b"\x01*NINTENDO-HVC*");
+    assert_eq!(info[55], 0x00);
+    assert_eq!(cart.read(Addr(0x4032)), Byte(0x40));
+
+    // Swapping sides goes through an empty drive
+    assert_eq!(cart.disk_sides(), 2);
+    cart.insert_disk(None);
+    assert_eq!(cart.disk_side(), None);
+    assert_eq!(cart.read(Addr(0x4032)), Byte(0x47));
+    cart.insert_disk(Some(1));
+    cart.write(Addr(0x4025), Byte(0x00));
+    cart.cpu_tick();
+    assert_eq!(read_disk_info(&mut cart)[55], 0x01);
+
+    // Headerless images load as well
+    cart.load(&mut Cursor::new(fds(1)[16..].to_vec()))?;
+    assert_eq!(cart.disk_sides(), 1);
+
+    let res = cart.load(&mut Cursor::new(fds(1)[..10].to_vec()));
+    assert!(matches!(
+        res,
+        Err(errors::Error::TruncatedHeader { size: 10, .. })
+    ));
+    let mut rom = fds(1);
+    rom[17] = b'X';
+    let res = cart.load(&mut Cursor::new(rom));
+    assert!(matches!(res, Err(errors::Error::BadMagic { .. })));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+    Ok(())
+}
+
+#[test]
+fn persists_disk_writes_as_ips_patch() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("nep-fds-save-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let bios_path = dir.join("disksys.rom");
+    std::fs::write(&bios_path, vec![0; 8192]).unwrap();
+    let disk_path = dir.join("game.fds");
+    std::fs::write(&disk_path, fds(1)).unwrap();
+
+    let mut cart = Cartridge::new();
+    cart.set_fds_bios(&bios_path);
+    cart.load_from_file(&disk_path)?;
+
+    // Rewrite the disk info block the way the BIOS does: the gap up to
+    // its end mark, the block, then the CRC
+    let mut info = b"\x01*NINTENDO-HVC*".to_vec();
+    info.resize(56, 0x00);
+    info[20] = 0x99;
+
+    cart.write(Addr(0x4023), Byte(0x01));
+    cart.write(Addr(0x4025), Byte(0x81));
+    for _ in 0..28300 / 8 {
+        wait_disk_irq(&mut cart);
+        cart.write(Addr(0x4024), Byte(0x00));
+    }
+    cart.write(Addr(0x4025), Byte(0xC1));
+    cart.write(Addr(0x4024), Byte(0x80));
+    for v in info {
+        wait_disk_irq(&mut cart);
+        cart.write(Addr(0x4024), Byte(v));
+    }
+    wait_disk_irq(&mut cart);
+    cart.write(Addr(0x4025), Byte(0xD1));
+    cart.write(Addr(0x4025), Byte(0x00));
+    cart.save()?;
+
+    let patch = std::fs::read(dir.join("game.ips")).unwrap();
+    assert_eq!(patch, b"PATCH\x00\x00\x14\x00\x01\x99EOF");
+
+    let mut cart = Cartridge::new();
+    cart.set_fds_bios(&bios_path);
+    cart.load_from_file(&disk_path)?;
+    cart.write(Addr(0x4023), Byte(0x01));
+    assert_eq!(read_disk_info(&mut cart)[20], 0x99);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+    Ok(())
+}
+
+#[test]
+fn applies_bus_conflicts_of_discrete_boards() -> Result<()> {
+    // UxROM, each bank starts with its number and $C000 holds $01
+    let mut rom = ines(4, 0, 0x20, 0x00);
+    for bank in 0..4 {
+        rom[16 + bank * 16384 + 1] = bank as u8;
+    }
+    rom[16 + 3 * 16384] = 0x01;
+
+    let mut cart = Cartridge::new();
+    cart.load(&mut Cursor::new(rom.clone()))?;
+    assert!(cart.bus_conflicts_enabled());
+    cart.write(Addr(0xC000), Byte(0x03));
+    assert_eq!(cart.read(Addr(0x8001)), Byte(0x01));
+
+    cart.set_bus_conflicts(BusConflicts::Never);
+    cart.load(&mut Cursor::new(rom.clone()))?;
+    cart.write(Addr(0xC000), Byte(0x03));
+    assert_eq!(cart.read(Addr(0x8001)), Byte(0x03));
+
+    // UxROM submapper 1 has none, AxROM takes them from flags 10
+    cart.set_bus_conflicts(BusConflicts::Auto);
+    rom[7] = 0x08;
+    rom[8] = 0x10;
+    cart.load(&mut Cursor::new(rom.clone()))?;
+    assert!(!cart.bus_conflicts_enabled());
+
+    let mut rom = ines(4, 0, 0x70, 0x00);
+    cart.load(&mut Cursor::new(rom.clone()))?;
+    assert!(!cart.bus_conflicts_enabled());
+    rom[10] = 0x20;
+    cart.load(&mut Cursor::new(rom))?;
+    assert!(cart.bus_conflicts_enabled());
+    Ok(())
+}
+
+#[test]
+fn ands_cnrom_bank_select_with_rom_byte() -> Result<()> {
+    // Each CHR bank starts with its number and $8000 holds $01
+    let mut rom = ines(2, 4, 0x30, 0x00);
+    rom[16] = 0x01;
+    for bank in 0..4 {
+        rom[16 + 32768 + bank * 8192] = bank as u8;
+    }
+
+    let mut cart = Cartridge::new();
+    cart.load(&mut Cursor::new(rom))?;
+    assert!(cart.bus_conflicts_enabled());
+    cart.write(Addr(0x8000), Byte(0x03));
+    assert_eq!(cart.read_chr(Addr(0x0000)), Mapped::Data(Byte(0x01)));
+    Ok(())
+}
+
+#[test]
+fn allocates_chr_ram_without_chr_rom() -> Result<()> {
+    let mut cart = Cartridge::new();
+    cart.load(&mut Cursor::new(ines(1, 0, 0x00, 0x00)))?;
+    assert!(cart.has_chr_ram());
+
+    assert_eq!(
+        cart.write_chr(Addr(0x1ABC), Byte(0x42)),
+        Mapped::Data(Byte(0x42))
+    );
+    assert_eq!(cart.read_chr(Addr(0x1ABC)), Mapped::Data(Byte(0x42)));
+
+    // CHR ROM keeps its contents
+    let mut rom = ines(1, 1, 0x00, 0x00);
+    rom[16 + 16384] = 0x24;
+
+    let mut cart = Cartridge::new();
+    cart.load(&mut Cursor::new(rom))?;
+    assert!(!cart.has_chr_ram());
+
+    cart.write_chr(Addr(0x0000), Byte(0x42));
+    assert_eq!(cart.read_chr(Addr(0x0000)), Mapped::Data(Byte(0x24)));
+    Ok(())
+}
+
+#[test]
+fn applies_board_default_chr_ram() -> Result<()> {
+    // UNROM-512 banks 32 KB of CHR RAM the iNES header cannot declare
+    let mut cart = Cartridge::new();
+    cart.load(&mut Cursor::new(ines(2, 0, 0xE0, 0x10)))?;
+    assert_eq!(cart.header().unwrap().chr_ram_size, 0);
+
+    cart.write(Addr(0xC000), Byte(0x60));
+    cart.write_chr(Addr(0x0000), Byte(0x42));
+    assert_eq!(cart.read_chr(Addr(0x0000)), Mapped::Data(Byte(0x42)));
+    cart.write(Addr(0xC000), Byte(0x00));
+    assert_eq!(cart.read_chr(Addr(0x0000)), Mapped::Data(Byte(0x00)));
+    Ok(())
+}
+
+#[test]
+fn sizes_chr_ram_from_nes_2_0_header() -> Result<()> {
+    // MMC1 with 32 KB of CHR RAM
+    let mut rom = ines(2, 0, 0x10, 0x08);
+    rom[11] = 0x09;
+
+    let mut cart = Cartridge::new();
+    cart.load(&mut Cursor::new(rom))?;
+    assert_eq!(cart.header().unwrap().chr_ram_size, 32768);
+
+    // Switch 4 KB CHR mode and select the last 4 KB bank
+    for (addr, v) in [(0x8000, 0x10), (0xA000, 0x07)] {
+        for i in 0..5 {
+            cart.write(Addr(addr), Byte((v >> i) & 0x01));
+        }
+    }
+
+    cart.write_chr(Addr(0x0000), Byte(0x42));
+    assert_eq!(cart.read_chr(Addr(0x0000)), Mapped::Data(Byte(0x42)));
+    assert_eq!(cart.read_chr(Addr(0x1000)), Mapped::Data(Byte(0x00)));
+    Ok(())
+}
+
+#[test]
+fn copies_trainer_into_work_ram_on_reset() -> Result<()> {
+    let mut rom = ines(1, 1, 0x04, 0x00);
+    let trainer: Vec<u8> = (0..512).map(|i| i as u8).collect();
+    rom.splice(16..16, trainer);
+
+    let mut cart = Cartridge::new();
+    cart.load(&mut Cursor::new(rom))?;
+    assert_eq!(cart.trainer().len(), 512);
+    assert_eq!(cart.read(Addr(0x7001)), Byte(0x00));
+
+    cart.reset();
+    assert_eq!(cart.read(Addr(0x7000)), Byte(0x00));
+    assert_eq!(cart.read(Addr(0x7001)), Byte(0x01));
+    assert_eq!(cart.read(Addr(0x71FF)), Byte(0xFF));
+    Ok(())
+}
+
+#[test]
+fn rejects_truncated_trainer() {
+    let mut rom = ines(0, 0, 0x04, 0x00);
+    rom.resize(16 + 100, 0);
+
+    let mut cart = Cartridge::new();
+    let res = cart.load(&mut Cursor::new(rom));
+    assert!(matches!(res, Err(errors::Error::TruncatedTrainer { .. })));
+}
+
+#[test]
+fn reports_structured_loading_errors() {
+    let load = |rom: Vec<u8>| Cartridge::new().load(&mut Cursor::new(rom));
+
+    let mut rom = ines(1, 1, 0x00, 0x00);
+    rom[0] = b'X';
+    assert!(matches!(load(rom), Err(errors::Error::BadMagic { .. })));
+
+    let rom = ines(1, 1, 0x00, 0x00)[..10].to_vec();
+    assert!(matches!(
+        load(rom),
+        Err(errors::Error::TruncatedHeader { size: 10, .. })
+    ));
+
+    let mut rom = ines(2, 1, 0x00, 0x00);
+    rom.truncate(16 + 20000);
+    assert!(matches!(
+        load(rom),
+        Err(errors::Error::TruncatedPrg {
+            expected: 32768,
+            actual: 20000,
+            ..
+        })
+    ));
+
+    let mut rom = ines(1, 1, 0x00, 0x00);
+    rom.truncate(16 + 16384 + 100);
+    assert!(matches!(
+        load(rom),
+        Err(errors::Error::TruncatedChr {
+            expected: 8192,
+            actual: 100,
+            ..
+        })
+    ));
+
+    let rom = ines(1, 1, 0xF0, 0xF0);
+    assert!(matches!(
+        load(rom),
+        Err(errors::Error::UnsupportedMapper { id: 0xFF, .. })
+    ));
+
+    let rom = ines(0, 1, 0x00, 0x00);
+    assert!(matches!(load(rom), Err(errors::Error::InvalidSize { .. })));
+}
+
+#[test]
+fn never_panics_on_mutated_roms() {
+    // Deterministic xorshift, so a failure can be reproduced
+    let mut seed: u32 = 0x1234_5678;
+    let mut rand = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed
+    };
+
+    let mut paths: Vec<_> = std::fs::read_dir("./roms")
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().map_or(false, |e| e == "nes"))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let rom = std::fs::read(&path).unwrap();
+
+        for i in 0..48 {
+            let mut rom = rom.clone();
+            match i % 3 {
+                // Garbage in the header
+                0 => {
+                    for _ in 0..(rand() % 4 + 1) {
+                        let at = rand() as usize % 16;
+                        rom[at] = rand() as u8;
+                    }
+                }
+                // Garbage in the header, but with a valid magic
+                1 => {
+                    let at = rand() as usize % 12 + 4;
+                    rom[at] = rand() as u8;
+                }
+                // Cut somewhere
+                _ => {
+                    let len = rand() as usize % rom.len();
+                    rom.truncate(len);
+                }
+            }
+
+            let mut cart = Cartridge::new();
+            if cart.load(&mut Cursor::new(rom)).is_err() {
+                continue;
+            }
+
+            // Poke the mapper with whatever it managed to load
+            cart.reset();
+            for _ in 0..256 {
+                let addr = Addr(rand() as u16 | 0x4000);
+                cart.write(addr, Byte(rand() as u8));
+                cart.read(Addr(rand() as u16 | 0x4000));
+                cart.write_chr(Addr(rand() as u16 & 0x1FFF), Byte(rand() as u8));
+                cart.read_chr(Addr(rand() as u16 & 0x1FFF));
+                cart.scanline();
+                cart.mirror();
+            }
+        }
+    }
+}
+
+// Board that answers every PRG read with its own id
+struct ConstMapper(u8);
+
+impl Mapper for ConstMapper {
+    fn read(&mut self, addr: Addr) -> Mapped {
+        match addr {
+            Addr(0x8000..=0xFFFF) => Mapped::Data(Byte(self.0)),
+            _ => Mapped::Unmapped,
+        }
+    }
+
+    fn write(&mut self, _addr: Addr, _v: Byte) -> Mapped {
+        Mapped::Unmapped
+    }
+
+    fn read_chr(&mut self, _addr: Addr) -> Mapped {
+        Mapped::Unmapped
+    }
+
+    fn write_chr(&mut self, _addr: Addr, _v: Byte) -> Mapped {
+        Mapped::Unmapped
+    }
+}
+
+#[test]
+fn loads_registered_mappers() -> Result<()> {
+    // NES 2.0, mapper 0x1FF
+    let mut rom = ines(1, 1, 0xF0, 0xF8);
+    rom[8] = 0x01;
+
+    let mut cart = Cartridge::new();
+    assert!(!cart.mappers().contains(0x1FF, 0));
+    assert!(cart.load(&mut Cursor::new(rom.clone())).is_err());
+
+    cart.mappers_mut()
+        .register(0x1FF, None, |i| Box::new(ConstMapper(i.prg_banks as u8)));
+    cart.mappers_mut()
+        .register(0x1FF, Some(3), |_| Box::new(ConstMapper(0x33)));
+
+    cart.load(&mut Cursor::new(rom.clone()))?;
+    assert_eq!(cart.read(Addr(0x8000)), Byte(0x01));
+
+    // Submapper 3 has its own constructor
+    rom[8] = 0x31;
+    cart.load(&mut Cursor::new(rom))?;
+    assert_eq!(cart.read(Addr(0x8000)), Byte(0x33));
+    Ok(())
+}
+
+// Board that counts what it observes and fills the program space with NOPs
+struct ProbeMapper {
+    cpu_ticks:   Rc<Cell<u32>>,
+    ppu_fetches: Rc<Cell<u32>>,
+}
+
+impl Mapper for ProbeMapper {
+    fn read(&mut self, _addr: Addr) -> Mapped {
+        Mapped::Data(Byte(0xEA))
+    }
+
+    fn write(&mut self, _addr: Addr, _v: Byte) -> Mapped {
+        Mapped::Unmapped
+    }
+
+    fn read_chr(&mut self, addr: Addr) -> Mapped {
+        Mapped::Chr(addr.as_usize())
+    }
+
+    fn write_chr(&mut self, addr: Addr, _v: Byte) -> Mapped {
+        Mapped::Chr(addr.as_usize())
+    }
+
+    fn ppu_bus(&mut self, _addr: Addr) {
+        self.ppu_fetches.set(self.ppu_fetches.get() + 1);
+    }
+
+    fn cpu_tick(&mut self) {
+        self.cpu_ticks.set(self.cpu_ticks.get() + 1);
+    }
+}
+
+#[test]
+fn mapper_observes_cpu_cycles_and_ppu_bus() -> Result<()> {
+    let cpu_ticks = Rc::new(Cell::new(0));
+    let ppu_fetches = Rc::new(Cell::new(0));
+
+    let mut emu = Emu::new();
+    let (ticks, fetches) = (cpu_ticks.clone(), ppu_fetches.clone());
+    emu.mappers_mut().register(0x1FE, None, move |_| {
+        Box::new(ProbeMapper {
+            cpu_ticks:   ticks.clone(),
+            ppu_fetches: fetches.clone(),
+        })
+    });
+
+    // NES 2.0, mapper 0x1FE
+    let mut rom = ines(1, 1, 0xE0, 0xF8);
+    rom[8] = 0x01;
+    emu.load(&mut Cursor::new(rom))?;
+
+    emu.step();
+    // A frame lasts a bit less than 30K CPU cycles
+    assert!((29000..31000).contains(&cpu_ticks.get()));
+    assert!(ppu_fetches.get() > 0);
+    Ok(())
+}
@@ -0,0 +1,1087 @@
+use nep::cartridge::mappers::*;
+use nep::cartridge::{CartridgeHeader, DiskImage, EepromChip, Mirror};
+use nep::prelude::*;
+
+fn write_serial<M: Mapper>(m: &mut M, addr: Addr, v: u8) {
+    for i in 0..5 {
+        m.write(addr, Byte((v >> i) & 0x01));
+    }
+}
+
+#[test]
+fn mapper_001_switches_prg_banks() {
+    let mut m = Mapper001::new(8, 1);
+
+    // Power-up state fixes the last bank at $C000
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg(7 * 0x4000));
+
+    write_serial(&mut m, Addr(0xE000), 0x03);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(3 * 0x4000));
+    assert_eq!(m.read(Addr(0xFFFF)), Mapped::Prg(7 * 0x4000 + 0x3FFF));
+
+    // 32K mode ignores the low bit of the bank number
+    write_serial(&mut m, Addr(0x8000), 0x00);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(2 * 0x4000));
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg(3 * 0x4000));
+}
+
+#[test]
+fn mapper_001_controls_mirroring() {
+    let mut m = Mapper001::new(2, 1);
+
+    write_serial(&mut m, Addr(0x8000), 0x00);
+    assert!(matches!(m.mirror(), Mirror::OneScreenLo));
+    write_serial(&mut m, Addr(0x8000), 0x01);
+    assert!(matches!(m.mirror(), Mirror::OneScreenHi));
+    write_serial(&mut m, Addr(0x8000), 0x02);
+    assert!(matches!(m.mirror(), Mirror::Vertical));
+    write_serial(&mut m, Addr(0x8000), 0x03);
+    assert!(matches!(m.mirror(), Mirror::Horizontal));
+}
+
+fn write<M: Mapper>(m: &mut M, addr: Addr, v: u8) {
+    m.write(addr, Byte(v));
+}
+
+#[test]
+fn mapper_004_switches_prg_banks() {
+    let mut m = Mapper004::new(8, 1);
+
+    write(&mut m, Addr(0x8000), 0x06);
+    write(&mut m, Addr(0x8001), 0x03);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(3 * 0x2000));
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg(14 * 0x2000));
+    assert_eq!(m.read(Addr(0xE000)), Mapped::Prg(15 * 0x2000));
+
+    // PRG mode 1 swaps $8000 and $C000
+    write(&mut m, Addr(0x8000), 0x46);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(14 * 0x2000));
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg(3 * 0x2000));
+}
+
+#[test]
+fn mapper_004_raises_scanline_irq() {
+    let mut m = Mapper004::new(2, 1);
+
+    write(&mut m, Addr(0xC000), 0x02);
+    write(&mut m, Addr(0xC001), 0x00);
+    write(&mut m, Addr(0xE001), 0x00);
+
+    m.scanline(); // reload with 2
+    m.scanline(); // 1
+    assert!(!m.has_irq());
+    m.scanline(); // 0
+    assert!(m.has_irq());
+
+    // Disabling acknowledges the pending interrupt
+    write(&mut m, Addr(0xE000), 0x00);
+    assert!(!m.has_irq());
+}
+
+#[test]
+fn mapper_118_takes_nametables_from_chr_banks() {
+    let mut m = Mapper004::with_board(8, 16, Mmc3Board::Txsrom);
+
+    // R0 covers $2000 and $2400, R1 covers $2800 and $2C00
+    write(&mut m, Addr(0x8000), 0x00);
+    write(&mut m, Addr(0x8001), 0x80);
+    write(&mut m, Addr(0x8000), 0x01);
+    write(&mut m, Addr(0x8001), 0x02);
+    assert_eq!(m.read_nametable(Addr(0x2005)), Mapped::CiRam(0x0405));
+    assert_eq!(m.read_nametable(Addr(0x2405)), Mapped::CiRam(0x0405));
+    assert_eq!(m.read_nametable(Addr(0x2C05)), Mapped::CiRam(0x0005));
+
+    // With inversion the 1 KB banks pick the pages
+    write(&mut m, Addr(0x8000), 0x85);
+    write(&mut m, Addr(0x8001), 0x80);
+    assert_eq!(m.read_nametable(Addr(0x2C05)), Mapped::CiRam(0x0405));
+    assert_eq!(m.read_nametable(Addr(0x2005)), Mapped::CiRam(0x0005));
+}
+
+#[test]
+fn mapper_119_mixes_chr_rom_and_ram() {
+    let mut m = Mapper004::with_board(8, 8, Mmc3Board::Tqrom);
+
+    write(&mut m, Addr(0x8000), 0x02);
+    write(&mut m, Addr(0x8001), 0x05);
+    write(&mut m, Addr(0x8000), 0x03);
+    write(&mut m, Addr(0x8001), 0x41);
+    assert_eq!(m.read_chr(Addr(0x1010)), Mapped::Chr(5 * 0x0400 + 0x10));
+
+    // Banks with bit 6 set land in the CHR RAM of the board
+    assert_eq!(
+        m.write_chr(Addr(0x1410), Byte(0x5A)),
+        Mapped::Data(Byte(0x5A))
+    );
+    assert_eq!(m.read_chr(Addr(0x1410)), Mapped::Data(Byte(0x5A)));
+    write(&mut m, Addr(0x8000), 0x02);
+    write(&mut m, Addr(0x8001), 0x49);
+    assert_eq!(m.read_chr(Addr(0x1010)), Mapped::Data(Byte(0x5A)));
+}
+
+#[test]
+fn mapper_064_switches_extra_banks() {
+    let mut m = Mapper004::with_board(8, 32, Mmc3Board::Rambo1);
+
+    for (r, v) in [
+        (0x6, 0x01),
+        (0x7, 0x02),
+        (0xF, 0x03),
+        (0x0, 0x10),
+        (0x8, 0x21),
+    ] {
+        write(&mut m, Addr(0x8000), r);
+        write(&mut m, Addr(0x8001), v);
+    }
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(0x2000));
+    assert_eq!(m.read(Addr(0xA000)), Mapped::Prg(2 * 0x2000));
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg(3 * 0x2000));
+    assert_eq!(m.read(Addr(0xE000)), Mapped::Prg(15 * 0x2000));
+    assert_eq!(m.read_chr(Addr(0x0400)), Mapped::Chr(0x11 * 0x0400));
+
+    // PRG mode 1 rotates the banks, 1 KB mode brings in R8
+    write(&mut m, Addr(0x8000), 0x60);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(3 * 0x2000));
+    assert_eq!(m.read(Addr(0xA000)), Mapped::Prg(0x2000));
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg(2 * 0x2000));
+    assert_eq!(m.read_chr(Addr(0x0000)), Mapped::Chr(0x10 * 0x0400));
+    assert_eq!(m.read_chr(Addr(0x0400)), Mapped::Chr(0x21 * 0x0400));
+}
+
+#[test]
+fn mapper_064_counts_cpu_cycles_for_irq() {
+    let mut m = Mapper004::with_board(8, 32, Mmc3Board::Rambo1);
+
+    write(&mut m, Addr(0xC000), 0x02);
+    write(&mut m, Addr(0xC001), 0x01);
+    write(&mut m, Addr(0xE001), 0x00);
+
+    // Reloading adds two steps for latches above 1, each step takes 4 cycles
+    for _ in 0..15 {
+        m.cpu_tick();
+        m.scanline();
+    }
+    assert!(!m.has_irq());
+    m.cpu_tick();
+    assert!(m.has_irq());
+}
+
+#[test]
+fn mapper_002_fixes_last_bank() {
+    let mut m = Mapper002::new(8, 0);
+
+    write(&mut m, Addr(0x8000), 0x05);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(5 * 0x4000));
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg(7 * 0x4000));
+}
+
+#[test]
+fn mapper_003_switches_chr_banks() {
+    let mut m = Mapper003::new(2, 4);
+    assert!(m.bus_conflicts());
+
+    write(&mut m, Addr(0x8000), 0x02);
+    assert_eq!(m.read_chr(Addr(0x0010)), Mapped::Chr(2 * 0x2000 + 0x0010));
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg(0x4000));
+
+    // Banks beyond the CHR ROM wrap around
+    write(&mut m, Addr(0xFFFF), 0x05);
+    assert_eq!(m.read_chr(Addr(0x1FFF)), Mapped::Chr(0x2000 + 0x1FFF));
+}
+
+#[test]
+fn mapper_007_selects_one_screen_mirroring() {
+    let mut m = Mapper007::new(8, 0);
+
+    write(&mut m, Addr(0x8000), 0x12);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(2 * 0x8000));
+    assert!(matches!(m.mirror(), Mirror::OneScreenHi));
+
+    write(&mut m, Addr(0x8000), 0x01);
+    assert!(matches!(m.mirror(), Mirror::OneScreenLo));
+}
+
+#[test]
+fn mapper_009_switches_chr_on_latch_fetch() {
+    let mut m = Mapper009::new(8, 16);
+
+    write(&mut m, Addr(0xB000), 0x04); // $FD/0000
+    write(&mut m, Addr(0xC000), 0x05); // $FE/0000
+    write(&mut m, Addr(0xD000), 0x06); // $FD/1000
+    write(&mut m, Addr(0xE000), 0x07); // $FE/1000
+
+    assert_eq!(m.read_chr(Addr(0x0000)), Mapped::Chr(5 * 0x1000));
+
+    // The fetch of $0FD8 itself still uses the old bank
+    assert_eq!(m.read_chr(Addr(0x0FD8)), Mapped::Chr(5 * 0x1000 + 0x0FD8));
+    m.ppu_bus(Addr(0x0FD8));
+    assert_eq!(m.read_chr(Addr(0x0000)), Mapped::Chr(4 * 0x1000));
+
+    // Latch 0 of MMC2 ignores the rest of the row
+    m.ppu_bus(Addr(0x0FE9));
+    assert_eq!(m.read_chr(Addr(0x0000)), Mapped::Chr(4 * 0x1000));
+
+    m.ppu_bus(Addr(0x1FDA));
+    assert_eq!(m.read_chr(Addr(0x1000)), Mapped::Chr(6 * 0x1000));
+    m.ppu_bus(Addr(0x1FEF));
+    assert_eq!(m.read_chr(Addr(0x1000)), Mapped::Chr(7 * 0x1000));
+
+    // The last three 8 KB banks are fixed
+    write(&mut m, Addr(0xA000), 0x02);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(2 * 0x2000));
+    assert_eq!(m.read(Addr(0xA000)), Mapped::Prg(13 * 0x2000));
+    assert_eq!(m.read(Addr(0xE000)), Mapped::Prg(15 * 0x2000));
+}
+
+#[test]
+fn mapper_010_latches_on_whole_row() {
+    let mut m = Mapper010::new(8, 16);
+
+    write(&mut m, Addr(0xB000), 0x04);
+    write(&mut m, Addr(0xC000), 0x05);
+
+    m.ppu_bus(Addr(0x0FDB));
+    assert_eq!(m.read_chr(Addr(0x0000)), Mapped::Chr(4 * 0x1000));
+    m.ppu_bus(Addr(0x0FEF));
+    assert_eq!(m.read_chr(Addr(0x0000)), Mapped::Chr(5 * 0x1000));
+
+    write(&mut m, Addr(0xA000), 0x03);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(3 * 0x4000));
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg(7 * 0x4000));
+
+    write(&mut m, Addr(0xF000), 0x01);
+    assert!(matches!(m.mirror(), Mirror::Horizontal));
+}
+
+#[test]
+fn mapper_005_switches_prg_banks() {
+    let mut m = Mapper005::new(16, 16);
+
+    // Power-up maps the last bank everywhere in mode 3
+    assert_eq!(m.read(Addr(0xE000)), Mapped::Prg(31 * 0x2000));
+
+    // Mode 1, 16 KB at $8000 from RAM and 16 KB at $C000 from ROM
+    write(&mut m, Addr(0x5100), 0x01);
+    write(&mut m, Addr(0x5115), 0x03);
+    write(&mut m, Addr(0x5117), 0x85);
+    assert_eq!(m.read(Addr(0x8001)), Mapped::PrgRam(2 * 0x2000 + 1));
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg(4 * 0x2000));
+
+    // RAM is writable only with both protect registers unlocked
+    assert_eq!(m.write(Addr(0x8001), Byte(0x42)), Mapped::Unmapped);
+    write(&mut m, Addr(0x5102), 0x02);
+    write(&mut m, Addr(0x5103), 0x01);
+    assert_eq!(
+        m.write(Addr(0x8001), Byte(0x42)),
+        Mapped::PrgRam(2 * 0x2000 + 1)
+    );
+
+    // Mode 3, four 8 KB banks
+    write(&mut m, Addr(0x5100), 0x03);
+    write(&mut m, Addr(0x5114), 0x87);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(7 * 0x2000));
+}
+
+#[test]
+fn mapper_005_uses_separate_chr_sets_for_tall_sprites() {
+    let mut m = Mapper005::new(16, 16);
+
+    // 1 KB CHR pages
+    write(&mut m, Addr(0x5101), 0x03);
+    write(&mut m, Addr(0x5120), 0x10);
+    write(&mut m, Addr(0x5128), 0x20);
+
+    // The last written set is used outside of 8x16 mode
+    assert_eq!(m.read_chr(Addr(0x0000)), Mapped::Chr(0x20 * 0x0400));
+
+    m.scanline();
+    m.ppu_fetch(PpuFetch::Sprites, true);
+    assert_eq!(m.read_chr(Addr(0x0000)), Mapped::Chr(0x10 * 0x0400));
+    m.ppu_fetch(PpuFetch::Background, true);
+    assert_eq!(m.read_chr(Addr(0x0000)), Mapped::Chr(0x20 * 0x0400));
+    // Set B is repeated in both pattern tables
+    assert_eq!(m.read_chr(Addr(0x1000)), Mapped::Chr(0x20 * 0x0400));
+}
+
+#[test]
+fn mapper_005_supplies_nametables() {
+    let mut m = Mapper005::new(16, 16);
+
+    // $2000 from CIRAM page 1, $2400 from ExRAM, $2800 filled
+    write(&mut m, Addr(0x5105), 0x39);
+    write(&mut m, Addr(0x5106), 0x42);
+    write(&mut m, Addr(0x5107), 0x02);
+
+    assert_eq!(m.read_nametable(Addr(0x2005)), Mapped::CiRam(0x0405));
+    assert_eq!(
+        m.write_nametable(Addr(0x2405), Byte(0x24)),
+        Mapped::Data(Byte(0x24))
+    );
+    assert_eq!(m.read_nametable(Addr(0x2405)), Mapped::Data(Byte(0x24)));
+    assert_eq!(m.read_nametable(Addr(0x2805)), Mapped::Data(Byte(0x42)));
+    assert_eq!(m.read_nametable(Addr(0x2BC5)), Mapped::Data(Byte(0xAA)));
+}
+
+#[test]
+fn mapper_005_extends_attributes() {
+    let mut m = Mapper005::new(16, 16);
+    write(&mut m, Addr(0x5104), 0x01);
+
+    m.scanline();
+    m.ppu_fetch(PpuFetch::Background, false);
+    // CPU writes land only while rendering in this mode
+    write(&mut m, Addr(0x5C05), 0xC3);
+
+    m.read_nametable(Addr(0x2005));
+    assert_eq!(m.read_nametable(Addr(0x23C1)), Mapped::Data(Byte(0xFF)));
+    assert_eq!(m.read_chr(Addr(0x0012)), Mapped::Chr(3 * 0x1000 + 0x12));
+}
+
+#[test]
+fn mapper_005_splits_screen() {
+    let mut m = Mapper005::new(16, 16);
+    write(&mut m, Addr(0x5104), 0x01);
+    // Split the left 2 tiles with scroll 9 using 4 KB bank 5
+    write(&mut m, Addr(0x5200), 0x82);
+    write(&mut m, Addr(0x5201), 0x09);
+    write(&mut m, Addr(0x5202), 0x05);
+
+    m.scanline();
+    m.ppu_fetch(PpuFetch::Background, false);
+    write(&mut m, Addr(0x5C21), 0x77);
+
+    // Tile 1 of the split comes from the ExRAM row of the split scroll
+    m.ppu_bus(Addr(0x0008));
+    assert_eq!(m.read_nametable(Addr(0x2000)), Mapped::Data(Byte(0x77)));
+    assert_eq!(m.read_chr(Addr(0x1770)), Mapped::Chr(5 * 0x1000 + 0x0771));
+    m.ppu_bus(Addr(0x1778));
+
+    // Tile 2 is outside of the split
+    assert_eq!(m.read_nametable(Addr(0x2002)), Mapped::CiRam(0x0002));
+}
+
+#[test]
+fn mapper_005_raises_scanline_irq() {
+    let mut m = Mapper005::new(16, 16);
+
+    write(&mut m, Addr(0x5203), 0x02);
+    write(&mut m, Addr(0x5204), 0x80);
+
+    m.scanline(); // pre-render
+    m.scanline(); // 1
+    assert!(!m.has_irq());
+    m.scanline(); // 2
+    assert!(m.has_irq());
+
+    // Reading the status acknowledges the IRQ
+    assert_eq!(m.read(Addr(0x5204)), Mapped::Data(Byte(0xC0)));
+    assert!(!m.has_irq());
+
+    m.ppu_fetch(PpuFetch::Idle, false);
+    assert_eq!(m.read(Addr(0x5204)), Mapped::Data(Byte(0x00)));
+}
+
+#[test]
+fn mapper_005_multiplies() {
+    let mut m = Mapper005::new(16, 16);
+
+    write(&mut m, Addr(0x5205), 0xC8);
+    write(&mut m, Addr(0x5206), 0x0F);
+    assert_eq!(m.read(Addr(0x5205)), Mapped::Data(Byte(0xB8)));
+    assert_eq!(m.read(Addr(0x5206)), Mapped::Data(Byte(0x0B)));
+}
+
+#[test]
+fn mapper_021_decodes_board_address_lines() {
+    // VRC4e selects registers with A2/A3, VRC4b with A1/A0 swapped
+    let mut m = Mapper021::new(8, 16, VrcBoard::new(true, 0x04, 0x08));
+    write(&mut m, Addr(0xB000), 0x05);
+    write(&mut m, Addr(0xB004), 0x01);
+    assert_eq!(m.read_chr(Addr(0x0000)), Mapped::Chr(0x15 * 0x0400));
+
+    let mut m = Mapper021::new(8, 16, VrcBoard::new(true, 0x02, 0x01));
+    write(&mut m, Addr(0xB002), 0x05);
+    write(&mut m, Addr(0xB000), 0x03);
+    assert_eq!(m.read_chr(Addr(0x0000)), Mapped::Chr(0x53 * 0x0400));
+
+    // Without submapper both wirings of mapper 23 respond
+    let header = CartridgeHeader::parse(&[
+        0x4E, 0x45, 0x53, 0x1A, 0x08, 0x10, 0x70, 0x10, 0, 0, 0, 0, 0, 0, 0, 0,
+    ])
+    .unwrap();
+    let mut m = Mapper021::new(8, 16, VrcBoard::from_header(&header));
+    write(&mut m, Addr(0xB001), 0x01);
+    assert_eq!(m.read_chr(Addr(0x0000)), Mapped::Chr(0x10 * 0x0400));
+    write(&mut m, Addr(0xB004), 0x02);
+    assert_eq!(m.read_chr(Addr(0x0000)), Mapped::Chr(0x20 * 0x0400));
+}
+
+#[test]
+fn mapper_021_reaches_upper_half_of_512k_chr() {
+    // VRC4a with 512 KB CHR ROM, the high part of a bank has 5 bits
+    let mut m = Mapper021::new(8, 64, VrcBoard::new(true, 0x02, 0x04));
+    write(&mut m, Addr(0xB002), 0x1F);
+    write(&mut m, Addr(0xB000), 0x13);
+    assert_eq!(m.read_chr(Addr(0x0000)), Mapped::Chr(0x1F3 * 0x0400));
+
+    // The low part keeps 4 bits and leaves bit 8 alone
+    write(&mut m, Addr(0xB000), 0x05);
+    assert_eq!(m.read_chr(Addr(0x0000)), Mapped::Chr(0x1F5 * 0x0400));
+}
+
+#[test]
+fn mapper_021_swaps_prg_banks() {
+    let mut m = Mapper021::new(8, 16, VrcBoard::new(true, 0x01, 0x02));
+
+    write(&mut m, Addr(0x8000), 0x03);
+    write(&mut m, Addr(0xA000), 0x04);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(3 * 0x2000));
+    assert_eq!(m.read(Addr(0xA000)), Mapped::Prg(4 * 0x2000));
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg(14 * 0x2000));
+    assert_eq!(m.read(Addr(0xE000)), Mapped::Prg(15 * 0x2000));
+
+    write(&mut m, Addr(0x9002), 0x02);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(14 * 0x2000));
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg(3 * 0x2000));
+
+    write(&mut m, Addr(0x9000), 0x03);
+    assert!(matches!(m.mirror(), Mirror::OneScreenHi));
+}
+
+#[test]
+fn mapper_021_counts_cpu_cycles_for_irq() {
+    let mut m = Mapper021::new(8, 16, VrcBoard::new(true, 0x01, 0x02));
+
+    // Cycle mode overflows after 0x100 - latch cycles
+    write(&mut m, Addr(0xF000), 0x0C);
+    write(&mut m, Addr(0xF001), 0x0F);
+    write(&mut m, Addr(0xF002), 0x06);
+    for _ in 0..3 {
+        m.cpu_tick();
+    }
+    assert!(!m.has_irq());
+    m.cpu_tick();
+    assert!(m.has_irq());
+
+    // Acknowledging copies the enable-after-ack bit, which is clear
+    write(&mut m, Addr(0xF003), 0x00);
+    assert!(!m.has_irq());
+    for _ in 0..0x100 {
+        m.cpu_tick();
+    }
+    assert!(!m.has_irq());
+
+    // Scanline mode clocks the counter every 341 / 3 CPU cycles
+    write(&mut m, Addr(0xF000), 0x0F);
+    write(&mut m, Addr(0xF002), 0x02);
+    for _ in 0..113 {
+        m.cpu_tick();
+    }
+    assert!(!m.has_irq());
+    m.cpu_tick();
+    assert!(m.has_irq());
+}
+
+#[test]
+fn mapper_024_switches_banks() {
+    let mut m = Mapper024::new(8, 16);
+
+    write(&mut m, Addr(0x8000), 0x02);
+    write(&mut m, Addr(0xC000), 0x07);
+    write(&mut m, Addr(0xE002), 0x21);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(4 * 0x2000));
+    assert_eq!(m.read(Addr(0xA000)), Mapped::Prg(5 * 0x2000));
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg(7 * 0x2000));
+    assert_eq!(m.read(Addr(0xE000)), Mapped::Prg(15 * 0x2000));
+    assert_eq!(m.read_chr(Addr(0x1800)), Mapped::Chr(0x21 * 0x0400));
+
+    // VRC6b swaps A0 and A1, so $E001 reaches the register at $E002
+    let mut m = Mapper026::new(8, 16);
+    write(&mut m, Addr(0xE001), 0x21);
+    assert_eq!(m.read_chr(Addr(0x1800)), Mapped::Chr(0x21 * 0x0400));
+    write(&mut m, Addr(0xB003), 0x84);
+    assert!(matches!(m.mirror(), Mirror::Horizontal));
+
+    // PRG RAM is not affected by the swap
+    assert_eq!(m.write(Addr(0x6001), Byte(0x42)), Mapped::PrgRam(0x0001));
+    assert_eq!(m.read(Addr(0x6001)), Mapped::PrgRam(0x0001));
+    assert_eq!(m.write(Addr(0x7FFE), Byte(0x42)), Mapped::PrgRam(0x1FFE));
+}
+
+#[test]
+fn mapper_024_plays_expansion_audio() {
+    let mut m = Mapper024::new(8, 16);
+    assert_eq!(m.audio(), 0.0);
+
+    // Pulse 1 at full volume with duty 8/16 toggles every 8 clocks
+    write(&mut m, Addr(0x9000), 0x7F);
+    write(&mut m, Addr(0x9001), 0x00);
+    write(&mut m, Addr(0x9002), 0x80);
+    let mut levels = Vec::new();
+    for _ in 0..16 {
+        m.cpu_tick();
+        levels.push(m.audio());
+    }
+    assert_eq!(levels.iter().filter(|&&l| l > 0.0).count(), 8);
+
+    // Halting freezes the channels
+    write(&mut m, Addr(0x9003), 0x01);
+    let level = m.audio();
+    for _ in 0..16 {
+        m.cpu_tick();
+        assert_eq!(m.audio(), level);
+    }
+
+    // The sawtooth rises over 14 clocks and restarts
+    write(&mut m, Addr(0x9003), 0x00);
+    write(&mut m, Addr(0x9002), 0x00);
+    write(&mut m, Addr(0xB000), 0x20);
+    write(&mut m, Addr(0xB002), 0x80);
+    let mut levels = Vec::new();
+    for _ in 0..14 {
+        m.cpu_tick();
+        levels.push(m.audio());
+    }
+    // Six additions of 0x20 leave 0xC0 in the accumulator
+    assert_eq!(levels[11], (0xC0 >> 3) as f32 * 0.00752);
+    assert_eq!(levels[13], 0.0);
+}
+
+#[test]
+fn mapper_085_switches_banks_and_plays_fm() {
+    let mut m = Mapper085::new(8, 16, 0x0010);
+
+    write(&mut m, Addr(0x8010), 0x05);
+    write(&mut m, Addr(0xD010), 0x33);
+    assert_eq!(m.read(Addr(0xA000)), Mapped::Prg(5 * 0x2000));
+    assert_eq!(m.read(Addr(0xE000)), Mapped::Prg(15 * 0x2000));
+    assert_eq!(m.read_chr(Addr(0x1C00)), Mapped::Chr(0x33 * 0x0400));
+
+    // Key on a note through the audio ports
+    for &(reg, v) in &[(0x30, 0x30), (0x10, 0x22), (0x20, 0x19)] {
+        write(&mut m, Addr(0x9010), reg);
+        write(&mut m, Addr(0x9030), v);
+    }
+    let mut levels = Vec::new();
+    for _ in 0..36 * 100 {
+        m.cpu_tick();
+        levels.push(m.audio());
+    }
+    assert!(levels.iter().any(|&l| l != 0.0));
+
+    // Silencing resets the synthesizer
+    write(&mut m, Addr(0xE000), 0x40);
+    for _ in 0..36 * 100 {
+        m.cpu_tick();
+        assert_eq!(m.audio(), 0.0);
+    }
+}
+
+#[test]
+fn mapper_069_switches_banks_through_commands() {
+    let mut m = Mapper069::new(8, 16);
+
+    write(&mut m, Addr(0x8000), 0x0A);
+    write(&mut m, Addr(0xA000), 0x07);
+    write(&mut m, Addr(0x8000), 0x03);
+    write(&mut m, Addr(0xA000), 0x2A);
+    assert_eq!(m.read(Addr(0xA000)), Mapped::Prg(7 * 0x2000));
+    assert_eq!(m.read(Addr(0xE000)), Mapped::Prg(15 * 0x2000));
+    assert_eq!(m.read_chr(Addr(0x0C00)), Mapped::Chr(0x2A * 0x0400));
+
+    // $6000 holds ROM until RAM is selected, and nothing while RAM is disabled
+    write(&mut m, Addr(0x8000), 0x08);
+    write(&mut m, Addr(0xA000), 0x03);
+    assert_eq!(m.read(Addr(0x6000)), Mapped::Prg(3 * 0x2000));
+    write(&mut m, Addr(0xA000), 0x40);
+    assert_eq!(m.read(Addr(0x6000)), Mapped::Unmapped);
+    write(&mut m, Addr(0xA000), 0xC0);
+    assert_eq!(m.write(Addr(0x6001), Byte(0x00)), Mapped::PrgRam(1));
+}
+
+#[test]
+fn mapper_069_counts_cpu_cycles_for_irq() {
+    let mut m = Mapper069::new(8, 16);
+
+    for &(command, v) in &[(0x0E, 0x02), (0x0F, 0x00), (0x0D, 0x81)] {
+        write(&mut m, Addr(0x8000), command);
+        write(&mut m, Addr(0xA000), v);
+    }
+    for _ in 0..2 {
+        m.cpu_tick();
+    }
+    assert!(!m.has_irq());
+    m.cpu_tick();
+    assert!(m.has_irq());
+
+    // Writing the control acknowledges
+    write(&mut m, Addr(0x8000), 0x0D);
+    write(&mut m, Addr(0xA000), 0x00);
+    assert!(!m.has_irq());
+}
+
+#[test]
+fn mapper_069_plays_5b_square() {
+    let mut m = Mapper069::new(8, 16);
+    assert_eq!(m.audio(), 0.0);
+
+    // Channel A tone only, period 2 toggles every 32 CPU cycles
+    for &(reg, v) in &[(0x00, 0x02), (0x01, 0x00), (0x07, 0x3E), (0x08, 0x0F)] {
+        write(&mut m, Addr(0xC000), reg);
+        write(&mut m, Addr(0xE000), v);
+    }
+    let mut levels = Vec::new();
+    for _ in 0..128 {
+        m.cpu_tick();
+        levels.push(m.audio());
+    }
+    let edges = levels.windows(2).filter(|w| w[0] != w[1]).count();
+    assert_eq!(edges, 4);
+    assert!(levels.iter().any(|&l| (l - 0.11).abs() < 1e-6));
+}
+
+#[test]
+fn mapper_019_maps_ciram_through_chr_banks() {
+    let mut m = Mapper019::new(8, 32);
+
+    write(&mut m, Addr(0x8800), 0xE1);
+    write(&mut m, Addr(0xC800), 0xE1);
+    write(&mut m, Addr(0xD000), 0x12);
+    assert_eq!(m.read_chr(Addr(0x0410)), Mapped::CiRam(0x0410));
+    assert_eq!(m.read_nametable(Addr(0x2410)), Mapped::CiRam(0x0410));
+    assert_eq!(
+        m.read_nametable(Addr(0x2810)),
+        Mapped::Chr(0x12 * 0x0400 + 0x10)
+    );
+
+    // $E800 bit 6 turns $E0-$FF back into CHR ROM for the lower pattern table
+    write(&mut m, Addr(0xE800), 0x40);
+    assert_eq!(m.read_chr(Addr(0x0410)), Mapped::Chr(0xE1 * 0x0400 + 0x10));
+}
+
+#[test]
+fn mapper_019_counts_cpu_cycles_for_irq() {
+    let mut m = Mapper019::new(8, 32);
+
+    write(&mut m, Addr(0x5000), 0xFD);
+    write(&mut m, Addr(0x5800), 0xFF);
+    assert_eq!(m.read(Addr(0x5800)), Mapped::Data(Byte(0xFF)));
+    m.cpu_tick();
+    assert!(!m.has_irq());
+    m.cpu_tick();
+    assert!(m.has_irq());
+
+    // The counter stops at $7FFF
+    m.cpu_tick();
+    assert_eq!(m.read(Addr(0x5000)), Mapped::Data(Byte(0xFF)));
+}
+
+#[test]
+fn mapper_019_multiplexes_wavetable_channels() {
+    let mut m = Mapper019::new(8, 32);
+
+    // Wave of 0xF samples at $00, two channels, the one at $78 silent
+    write(&mut m, Addr(0xF800), 0x80);
+    for _ in 0..4 {
+        write(&mut m, Addr(0x4800), 0xFF);
+    }
+    let channel = [0x00, 0x00, 0x00, 0x00, 0xF8, 0x00, 0x00, 0x0F];
+    write(&mut m, Addr(0xF800), 0xF0);
+    for &v in &channel {
+        write(&mut m, Addr(0x4800), v);
+    }
+    write(&mut m, Addr(0xF800), 0xFF);
+    write(&mut m, Addr(0x4800), 0x10);
+
+    // Each channel is heard for 15 cycles in turn
+    let mut levels = Vec::new();
+    for _ in 0..60 {
+        m.cpu_tick();
+        levels.push(m.audio());
+    }
+    let loud = levels.iter().filter(|&&l| l > 0.0).count();
+    assert_eq!(loud, 30);
+    assert!(levels
+        .iter()
+        .any(|&l| l > 0.0 && (l - 0.11 * 105.0 / 225.0).abs() < 1e-6));
+}
+
+// Drives the I2C lines of the Bandai EEPROM through $800D
+fn i2c<M: Mapper>(m: &mut M, scl: bool, sda: bool) {
+    write(m, Addr(0x800D), ((sda as u8) << 6) | ((scl as u8) << 5));
+}
+
+fn i2c_start<M: Mapper>(m: &mut M) {
+    i2c(m, false, true);
+    i2c(m, true, true);
+    i2c(m, true, false);
+    i2c(m, false, false);
+}
+
+fn i2c_stop<M: Mapper>(m: &mut M) {
+    i2c(m, false, false);
+    i2c(m, true, false);
+    i2c(m, true, true);
+}
+
+// Sends a byte and returns whether the EEPROM acknowledged it
+fn i2c_send<M: Mapper>(m: &mut M, v: u8) -> bool {
+    for i in (0..8).rev() {
+        let bit = (v >> i) & 0x01 != 0;
+        i2c(m, false, bit);
+        i2c(m, true, bit);
+        i2c(m, false, bit);
+    }
+    i2c(m, false, true);
+    i2c(m, true, true);
+    let ack = m.read(Addr(0x6000)) == Mapped::Data(Byte(0x00));
+    i2c(m, false, true);
+    ack
+}
+
+fn i2c_receive<M: Mapper>(m: &mut M, ack: bool) -> u8 {
+    let mut v = 0;
+    for _ in 0..8 {
+        i2c(m, false, true);
+        i2c(m, true, true);
+        v = (v << 1) | (m.read(Addr(0x6000)) == Mapped::Data(Byte(0x10))) as u8;
+        i2c(m, false, true);
+    }
+    i2c(m, false, !ack);
+    i2c(m, true, !ack);
+    i2c(m, false, !ack);
+    v
+}
+
+#[test]
+fn mapper_016_stores_data_in_24c02() {
+    let board = BandaiBoard {
+        fcg:     false,
+        lz93d50: true,
+        eeprom:  Some(EepromChip::C24C02),
+        sram:    false,
+    };
+    let mut m = Mapper016::new(16, 32, board);
+
+    // Write two bytes at $10
+    i2c_start(&mut m);
+    assert!(i2c_send(&mut m, 0xA0));
+    assert!(i2c_send(&mut m, 0x10));
+    assert!(i2c_send(&mut m, 0x12));
+    assert!(i2c_send(&mut m, 0x34));
+    i2c_stop(&mut m);
+
+    // Set the address, then read them back
+    i2c_start(&mut m);
+    assert!(i2c_send(&mut m, 0xA0));
+    assert!(i2c_send(&mut m, 0x10));
+    i2c_start(&mut m);
+    assert!(i2c_send(&mut m, 0xA1));
+    assert_eq!(i2c_receive(&mut m, true), 0x12);
+    assert_eq!(i2c_receive(&mut m, false), 0x34);
+    i2c_stop(&mut m);
+
+    // Other devices on the bus are ignored
+    i2c_start(&mut m);
+    assert!(!i2c_send(&mut m, 0x50));
+    i2c_stop(&mut m);
+
+    assert_eq!(&m.battery_ram()[0x10..0x12], &[0x12, 0x34]);
+    assert_eq!(m.battery_ram().len(), 256);
+}
+
+#[test]
+fn mapper_016_stores_data_in_24c01() {
+    let board = BandaiBoard {
+        fcg:     false,
+        lz93d50: true,
+        eeprom:  Some(EepromChip::C24C01),
+        sram:    false,
+    };
+    let mut m = Mapper016::new(16, 32, board);
+
+    // The word address comes first, with the read flag in bit 0
+    i2c_start(&mut m);
+    assert!(i2c_send(&mut m, 0x05 << 1));
+    assert!(i2c_send(&mut m, 0x5A));
+    i2c_stop(&mut m);
+
+    i2c_start(&mut m);
+    assert!(i2c_send(&mut m, (0x05 << 1) | 0x01));
+    assert_eq!(i2c_receive(&mut m, false), 0x5A);
+    i2c_stop(&mut m);
+    assert_eq!(m.battery_ram().len(), 128);
+}
+
+#[test]
+fn mapper_016_selects_register_window_by_submapper() {
+    let header = |mapper: u8, submapper: u8| {
+        // NES 2.0 header with the mapper in the low 8 bits
+        CartridgeHeader::parse(&[
+            0x4E,
+            0x45,
+            0x53,
+            0x1A,
+            0x10,
+            0x20,
+            mapper << 4,
+            (mapper & 0xF0) | 0x08,
+            submapper << 4,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        ])
+        .unwrap()
+    };
+
+    // FCG-1/2 takes registers at $6000 and counts the IRQ directly
+    let mut m = Mapper016::new(16, 32, BandaiBoard::from_header(&header(16, 4)));
+    write(&mut m, Addr(0x8008), 0x03);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(0));
+    write(&mut m, Addr(0x6008), 0x03);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(3 * 0x4000));
+    write(&mut m, Addr(0x600B), 0x01);
+    write(&mut m, Addr(0x600A), 0x01);
+    m.cpu_tick();
+    assert!(!m.has_irq());
+    m.cpu_tick();
+    assert!(m.has_irq());
+
+    // LZ93D50 loads the counter from the latch
+    let mut m = Mapper016::new(16, 32, BandaiBoard::from_header(&header(16, 5)));
+    write(&mut m, Addr(0x6008), 0x03);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(0));
+    write(&mut m, Addr(0x800B), 0x00);
+    write(&mut m, Addr(0x800A), 0x01);
+    m.cpu_tick();
+    assert!(m.has_irq());
+    assert_eq!(m.battery_ram().len(), 256);
+
+    assert_eq!(
+        BandaiBoard::from_header(&header(159, 0)).eeprom,
+        Some(EepromChip::C24C01)
+    );
+    assert!(BandaiBoard::from_header(&header(153, 0)).sram);
+}
+
+#[test]
+fn discrete_boards_latch_prg_and_chr_banks() {
+    let mut m = Mapper011::new(8, 16);
+    write(&mut m, Addr(0x8000), 0x52);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(2 * 0x8000));
+    assert_eq!(m.read_chr(Addr(0x0010)), Mapped::Chr(5 * 0x2000 + 0x10));
+
+    let mut m = Mapper066::new(8, 4);
+    write(&mut m, Addr(0x8000), 0x21);
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg(2 * 0x8000 + 0x4000));
+    assert_eq!(m.read_chr(Addr(0x0010)), Mapped::Chr(0x2000 + 0x10));
+
+    // NINA-03/06 only decodes addresses with A8 set
+    let mut m = Mapper079::new(4, 8);
+    assert_eq!(m.write(Addr(0x4000), Byte(0x0F)), Mapped::Unmapped);
+    write(&mut m, Addr(0x4000), 0x0F);
+    write(&mut m, Addr(0x5100), 0x0B);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(0x8000));
+    assert_eq!(m.read_chr(Addr(0x0000)), Mapped::Chr(3 * 0x2000));
+
+    // Banks wrap around a smaller ROM
+    let mut m = Mapper034::new(4, 1, false);
+    write(&mut m, Addr(0x8000), 0x03);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(0x8000));
+}
+
+#[test]
+fn mapper_034_switches_nina_001_banks_through_prg_ram() {
+    let mut m = Mapper034::new(4, 2, true);
+
+    assert_eq!(m.write(Addr(0x7FFD), Byte(0x01)), Mapped::PrgRam(0x1FFD));
+    write(&mut m, Addr(0x7FFE), 0x02);
+    write(&mut m, Addr(0x7FFF), 0x03);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(0x8000));
+    assert_eq!(m.read_chr(Addr(0x0010)), Mapped::Chr(2 * 0x1000 + 0x10));
+    assert_eq!(m.read_chr(Addr(0x1010)), Mapped::Chr(3 * 0x1000 + 0x10));
+
+    // ROM is not a register on NINA-001
+    assert_eq!(m.write(Addr(0x8000), Byte(0x00)), Mapped::Unmapped);
+}
+
+#[test]
+fn mapper_071_selects_one_screen_mirroring() {
+    let mut m = Mapper071::new(8, 0, false);
+    write(&mut m, Addr(0xC000), 0x05);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(5 * 0x4000));
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg(7 * 0x4000));
+
+    // Mirroring stays with the hardware until $9000 is written
+    write(&mut m, Addr(0x8000), 0x10);
+    assert_eq!(m.mirror(), Mirror::Hardware);
+    write(&mut m, Addr(0x9000), 0x10);
+    assert_eq!(m.mirror(), Mirror::OneScreenHi);
+
+    let m = Mapper071::new(8, 0, true);
+    assert_eq!(m.mirror(), Mirror::OneScreenLo);
+}
+
+#[test]
+fn mapper_206_switches_banks() {
+    let mut m = Mapper206::new(8, 16);
+
+    for (r, v) in [(0, 0x05), (2, 0x09), (6, 0x03), (7, 0x04)] {
+        write(&mut m, Addr(0x8000), r);
+        write(&mut m, Addr(0x8001), v);
+    }
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(3 * 0x2000));
+    assert_eq!(m.read(Addr(0xA000)), Mapped::Prg(4 * 0x2000));
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg(14 * 0x2000));
+    assert_eq!(m.read(Addr(0xE000)), Mapped::Prg(15 * 0x2000));
+    assert_eq!(m.read_chr(Addr(0x0410)), Mapped::Chr(4 * 0x0400 + 0x0410));
+    assert_eq!(m.read_chr(Addr(0x1010)), Mapped::Chr(9 * 0x0400 + 0x10));
+}
+
+#[test]
+fn mapper_228_latches_address_and_data() {
+    let mut m = Mapper228::new(96, 64);
+
+    // Chip 3, page 5 in 16 KB mode, CHR bank $0A << 2 | 2, horizontal
+    write(
+        &mut m,
+        Addr(0x8000 | 0x2000 | (3 << 11) | (5 << 6) | 0x20 | 0x0A),
+        0x02,
+    );
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg((64 + 5) * 0x4000));
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg((64 + 5) * 0x4000));
+    assert_eq!(m.read_chr(Addr(0x0000)), Mapped::Chr(0x2A * 0x2000));
+    assert_eq!(m.mirror(), Mirror::Horizontal);
+
+    // 32 KB mode of chip 1
+    write(&mut m, Addr(0x8000 | (1 << 11) | (5 << 6)), 0x00);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg((32 + 4) * 0x4000));
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg((32 + 5) * 0x4000));
+
+    // Chip 2 does not exist
+    write(&mut m, Addr(0x8000 | (2 << 11)), 0x00);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Unmapped);
+
+    // 4 bit RAM
+    write(&mut m, Addr(0x5FF1), 0xA5);
+    assert_eq!(m.read(Addr(0x4025)), Mapped::Data(Byte(0x05)));
+}
+
+#[test]
+fn mapper_028_combines_outer_and_inner_banks() {
+    let mut m = Mapper028::new(32, 4);
+    let select = |m: &mut Mapper028, reg: u8, v: u8| {
+        write(m, Addr(0x5000), reg);
+        write(m, Addr(0x8000), v);
+    };
+
+    // Power-up maps the last 32 KB
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(30 * 0x4000));
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg(31 * 0x4000));
+
+    // 128 KB game in UNROM-like mode with $C000 fixed
+    select(&mut m, 0x81, 0x05);
+    select(&mut m, 0x80, 0x2E);
+    select(&mut m, 0x01, 0x03);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(11 * 0x4000));
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg(11 * 0x4000));
+    assert_eq!(m.mirror(), Mirror::Vertical);
+
+    // $8000 fixed mode takes the first half of the outer bank
+    select(&mut m, 0x80, 0x28);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(10 * 0x4000));
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg(11 * 0x4000));
+
+    // 64 KB game in 32 KB mode
+    select(&mut m, 0x80, 0x10);
+    select(&mut m, 0x01, 0x01);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(10 * 0x4000));
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Prg(11 * 0x4000));
+
+    // CHR bank and one-screen page
+    assert_eq!(m.mirror(), Mirror::OneScreenLo);
+    select(&mut m, 0x00, 0x12);
+    assert_eq!(m.mirror(), Mirror::OneScreenHi);
+    assert_eq!(m.read_chr(Addr(0x0010)), Mapped::Chr(2 * 0x2000 + 0x10));
+}
+
+#[test]
+fn mapper_030_maps_flash_and_nametables() {
+    let mut m = Mapper030::new(32, 4, true, Unrom512Mirror::OneScreen);
+
+    write(&mut m, Addr(0xC000), 0xE3);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Flash(3 * 0x4000));
+    assert_eq!(m.read(Addr(0xC000)), Mapped::Flash(31 * 0x4000));
+    assert_eq!(
+        m.write(Addr(0x9555), Byte(0xAA)),
+        Mapped::Flash(3 * 0x4000 + 0x1555)
+    );
+    assert_eq!(m.read_chr(Addr(0x0010)), Mapped::Chr(3 * 0x2000 + 0x10));
+    assert_eq!(m.read_nametable(Addr(0x2C05)), Mapped::CiRam(0x0405));
+
+    // Four-screen keeps the nametables in the last CHR RAM bank
+    let mut m = Mapper030::new(32, 4, false, Unrom512Mirror::FourScreen);
+    write(&mut m, Addr(0x8000), 0x03);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Prg(3 * 0x4000));
+    assert_eq!(
+        m.read_nametable(Addr(0x2C05)),
+        Mapped::Chr(3 * 0x2000 + 0x0C05)
+    );
+}
+
+#[test]
+fn mapper_111_switches_pages_and_leds() {
+    let mut m = Mapper111::new(32, 2);
+
+    write(&mut m, Addr(0x5000), 0x73);
+    assert_eq!(m.read(Addr(0x8000)), Mapped::Flash(3 * 0x8000));
+    assert_eq!(m.read_chr(Addr(0x0010)), Mapped::Chr(0x2000 + 0x10));
+    assert_eq!(m.leds(), (false, true));
+
+    // Each page holds four screens
+    m.write_nametable(Addr(0x2C05), Byte(0x42));
+    assert_eq!(m.read_nametable(Addr(0x2C05)), Mapped::Data(Byte(0x42)));
+    assert_eq!(m.read_nametable(Addr(0x2005)), Mapped::Data(Byte(0x00)));
+    write(&mut m, Addr(0x7000), 0x00);
+    assert_eq!(m.read_nametable(Addr(0x2C05)), Mapped::Data(Byte(0x00)));
+    assert_eq!(m.leds(), (true, true));
+}
+
+#[test]
+fn fds_timer_raises_irq() -> Result<()> {
+    let mut m = Fds::new(DiskImage::parse(b"\x01*NINTENDO-HVC*")?);
+    write(&mut m, Addr(0x4023), 0x01);
+    write(&mut m, Addr(0x4025), 0x08);
+    assert!(matches!(m.mirror(), Mirror::Horizontal));
+
+    // Reload of 3 fires on the fourth cycle, repeat keeps the timer running
+    write(&mut m, Addr(0x4020), 0x03);
+    write(&mut m, Addr(0x4021), 0x00);
+    write(&mut m, Addr(0x4022), 0x03);
+    for _ in 0..3 {
+        m.cpu_tick();
+    }
+    assert!(!m.has_irq());
+    m.cpu_tick();
+    assert!(m.has_irq());
+    assert_eq!(m.read(Addr(0x4030)), Mapped::Data(Byte(0x01)));
+    assert!(!m.has_irq());
+    for _ in 0..4 {
+        m.cpu_tick();
+    }
+    assert!(m.has_irq());
+
+    // Disabling disk registers stops the timer
+    write(&mut m, Addr(0x4023), 0x00);
+    assert!(!m.has_irq());
+    for _ in 0..8 {
+        m.cpu_tick();
+    }
+    assert!(!m.has_irq());
+    assert_eq!(m.read(Addr(0x4030)), Mapped::Unmapped);
+    Ok(())
+}